@@ -17,9 +17,11 @@ https://github.com/thebino/exif-sorter
 #[derive(Parser)]
 #[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
 pub struct Args {
-    /// Directory to walk through and search for exif data.
-    #[arg(short, long, default_value = ".", global = true)]
-    pub source_dir: String,
+    /// Directory to walk through and search for exif data. Repeat to sort
+    /// several sources in one run (default: `sources` from the config
+    /// file, else the current directory).
+    #[arg(short, long, global = true)]
+    pub source_dir: Vec<String>,
 
     /// Base directory to move source files into.
     #[arg(short, long, default_value = "./sorted", global = true)]
@@ -47,9 +49,14 @@ pub struct CliArgs {
 
     /// Move files into the target instead of copying them.
     /// Default is copy: the source stays untouched.
-    #[arg(long = "move")]
+    #[arg(long = "move", conflicts_with = "mode")]
     pub move_files: bool,
 
+    /// How files get into the target (default: copy; may also come from the
+    /// config file). `--move` is shorthand for `--mode move`.
+    #[arg(long, value_enum)]
+    pub mode: Option<ModeArg>,
+
     /// What to do when the target filename already exists
     /// (default: suffix; may also come from the config file).
     #[arg(long, value_enum)]
@@ -60,11 +67,126 @@ pub struct CliArgs {
     #[arg(long)]
     pub pattern: Option<String>,
 
+    /// Source metadata to carry over to copies, comma-separated
+    /// (default: times; may also come from the config file).
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub preserve: Option<Vec<PreserveArg>>,
+
+    /// Set each sorted file's modified time to the capture time it was
+    /// sorted by (EXIF, GPS, video or filename dates only).
+    #[arg(long)]
+    pub touch: bool,
+
+    /// With `--touch`, also apply dates that came from file timestamps.
+    #[arg(long, requires = "touch")]
+    pub touch_low_confidence: bool,
+
+    /// Number of files transferred in parallel (default: 1; may also come
+    /// from the config file). Helps on fast disks and network targets.
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// Start even if the target filesystem looks too small for the run.
+    #[arg(long)]
+    pub ignore_free_space: bool,
+
+    /// Manifest format (default: csv; may also come from the config file).
+    /// jsonl also records candidate dates, camera, time zone and more.
+    #[arg(long, value_enum)]
+    pub manifest_format: Option<ManifestFormatArg>,
+
+    /// Take only files matching this glob; repeatable. A glob without a
+    /// "/" matches the file name, one with a "/" the path below the source.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Leave out files and directories matching this glob; repeatable.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Directory levels to descend below the source (0: only its own files).
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Follow symbolic links to files and directories.
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// Take hidden files and descend into hidden directories.
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Do not descend into directories on other filesystems.
+    #[arg(long)]
+    pub one_file_system: bool,
+
+    /// Stop when a path of the source cannot be read, instead of listing
+    /// it and sorting the rest.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Recognise media by content rather than extension: every file is
+    /// sniffed, so files without or with a wrong extension are sorted too.
+    #[arg(long)]
+    pub detect_content: bool,
+
+    /// Look inside ZIP and TAR archives (.zip, .tar, .tar.gz, .tgz) in the
+    /// source and sort their media; a source that is an archive always is.
+    #[arg(long)]
+    pub archives: bool,
+
+    /// Give targets the extension matching their content when the source's
+    /// names another type; the manifest keeps the old one.
+    #[arg(long)]
+    pub fix_extensions: bool,
+
+    /// Skip files an earlier run already imported into the target, even
+    /// if renamed since (recognised by size, modified time and checksum);
+    /// only new files are dated.
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// Plan into this file instead of memory, dating files as the walk
+    /// finds them, for trees too large to hold; page through it with
+    /// `review`. A dry run stops once the file is written.
+    #[arg(long, value_name = "FILE")]
+    pub plan_file: Option<String>,
+
     /// Path to a config file (default: ~/.config/exif-sorter/config.toml).
     #[arg(long)]
     pub config: Option<String>,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ModeArg {
+    /// Copy, leaving the source untouched (default).
+    Copy,
+    /// Move; the source is removed.
+    Move,
+    /// Copy-on-write clone where the filesystem supports it, else copy.
+    Reflink,
+    /// Hard link to the source (same filesystem only, takes no space).
+    Hardlink,
+    /// Symbolic link to the absolute source path.
+    Symlink,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum PreserveArg {
+    /// Modified and access time, birth time where it can be set.
+    Times,
+    /// Permission bits.
+    Mode,
+    /// Owner and group (only when running as root).
+    Owner,
+    /// Extended attributes (xdg origin, quarantine, SELinux labels).
+    Xattrs,
+    /// Everything above.
+    All,
+    /// Nothing, not even times.
+    None,
+}
+
 #[derive(Clone, Copy, clap::ValueEnum)]
 pub enum CollisionArg {
     /// Append a random suffix and store both files (default).
@@ -76,14 +198,188 @@ pub enum CollisionArg {
     Dedupe,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ManifestFormatArg {
+    /// exif-sorter-manifest.csv, one row per file (default).
+    Csv,
+    /// exif-sorter-manifest.jsonl, one JSON object per file.
+    Jsonl,
+}
+
 #[derive(Parser)]
 #[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
 pub struct RevertArgs {
-    /// Path to the manifest CSV written by a previous run.
+    /// Path to the manifest (CSV or JSON Lines) written by a previous run.
     #[arg(short, long)]
     pub manifest: String,
 
     /// Print what would be reverted without touching any file.
     #[arg(long)]
     pub dry_run: bool,
+
+    /// Only revert the run with this id (shown after every run).
+    #[arg(long, value_name = "ID")]
+    pub run: Option<String>,
+
+    /// Only revert the newest run in the manifest.
+    #[arg(long, conflicts_with = "run")]
+    pub last: bool,
+
+    /// Only files captured on or after this date (YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<chrono::NaiveDate>,
+
+    /// Only files captured on or before this date (YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<chrono::NaiveDate>,
+
+    /// Only these categories, comma-separated.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub category: Vec<CategoryArg>,
+
+    /// Only files whose source or target path matches this glob,
+    /// e.g. '**/2019/**'.
+    #[arg(long, value_name = "GLOB")]
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum CategoryArg {
+    /// Files sorted into a date folder.
+    Sorted,
+    /// Files without a usable date.
+    Unsorted,
+    /// Files with unrecognizable content.
+    Corrupt,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct QueryArgs {
+    /// Only files captured in this year.
+    #[arg(long)]
+    pub year: Option<i32>,
+
+    /// Only files captured on or after this date (YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub from: Option<chrono::NaiveDate>,
+
+    /// Only files captured on or before this date (YYYY-MM-DD).
+    #[arg(long, value_name = "DATE")]
+    pub to: Option<chrono::NaiveDate>,
+
+    /// Only files dated from file timestamps.
+    #[arg(long)]
+    pub low_confidence: bool,
+
+    /// Only files whose camera make or model contains this text.
+    #[arg(long, value_name = "TEXT")]
+    pub camera: Option<String>,
+
+    /// Only this category.
+    #[arg(long, value_enum)]
+    pub category: Option<CategoryArg>,
+
+    /// Only files stored by the run with this id.
+    #[arg(long, value_name = "ID")]
+    pub run: Option<String>,
+
+    /// Only files whose source no longer exists.
+    #[arg(long)]
+    pub source_missing: bool,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value = "csv")]
+    pub format: OutputFormatArg,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum OutputFormatArg {
+    /// One row per file with a header.
+    Csv,
+    /// An array of objects.
+    Json,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct VerifyArgs {
+    /// Move misfiled files into the folder their date resolves to today,
+    /// recording every move in the manifest.
+    #[arg(long)]
+    pub fix: bool,
+
+    /// Folder layout for runs that recorded none (default: from the config
+    /// file, else "{year}/{date}").
+    #[arg(long)]
+    pub pattern: Option<String>,
+
+    /// Path to a config file (default: ~/.config/exif-sorter/config.toml).
+    #[arg(long)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct RelayoutArgs {
+    /// The new folder layout (default: from the config file). Tokens:
+    /// {year}, {month}, {day}, {date}.
+    #[arg(long)]
+    pub pattern: Option<String>,
+
+    /// Print the moves instead of making them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Path to a config file (default: ~/.config/exif-sorter/config.toml).
+    #[arg(long)]
+    pub config: Option<String>,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct WatchArgs {
+    /// Seconds a new file must stay unchanged, after it was closed, before
+    /// it is sorted.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    pub settle: u64,
+
+    #[command(flatten)]
+    pub run: CliArgs,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct ImportArgs {
+    /// Look for cards in this directory and the ones in it (like
+    /// /media/$USER) instead of among the mounted removable devices.
+    #[arg(long, value_name = "DIR")]
+    pub mount_root: Option<String>,
+
+    /// Afterwards, remove the files from the card that the library holds
+    /// an identical copy of; asks first.
+    #[arg(long)]
+    pub remove_imported: bool,
+
+    /// With --remove-imported, remove without asking.
+    #[arg(long, requires = "remove_imported")]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub run: CliArgs,
+}
+
+#[derive(Parser)]
+#[clap(version, author, help_template = HELP_TEMPLATE, about, long_about)]
+pub struct ReviewArgs {
+    /// The plan file written with --plan-file.
+    pub file: String,
+
+    /// The page to show, from 1.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub page: u64,
+
+    /// Items per page.
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    pub page_size: u64,
 }
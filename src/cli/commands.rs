@@ -1,6 +1,8 @@
 use clap::Subcommand;
 
-use crate::cli::args::{CliArgs, RevertArgs};
+use crate::cli::args::{
+    CliArgs, ImportArgs, QueryArgs, RelayoutArgs, RevertArgs, ReviewArgs, VerifyArgs, WatchArgs,
+};
 
 #[derive(Subcommand)]
 pub enum Commands {
@@ -10,9 +12,60 @@ pub enum Commands {
     ///  Uses the Command line interface
     Cli(CliArgs),
 
+    /// Merge the sorted library in the source directory into the one in the
+    /// target directory, trusting the dates its manifest recorded
+    Merge(CliArgs),
+
+    /// Continue a run that was interrupted, from the journal in the target
+    /// directory
+    Resume,
+
+    /// Watch the source directories and sort new files once they are
+    /// complete, until stopped with Ctrl-C or SIGTERM
+    Watch(WatchArgs),
+
+    /// Copy what is new on the mounted camera cards (volumes with a DCIM
+    /// folder) into the target directory, optionally removing what the
+    /// library holds from the cards afterwards
+    Import(ImportArgs),
+
+    /// Page through a plan file written with --plan-file
+    Review(ReviewArgs),
+
     /// Undo a previous run using its manifest file
     Revert(RevertArgs),
 
+    /// Show why a file is (or would be) sorted where it is: its date
+    /// candidates, the routing decision and its manifest history
+    Explain {
+        /// The file to explain, in a source or a sorted library
+        file: String,
+
+        /// Folder layout for the planned target (default: from the config
+        /// file, else "{year}/{date}")
+        #[arg(long)]
+        pattern: Option<String>,
+
+        /// Path to a config file (default: ~/.config/exif-sorter/config.toml).
+        #[arg(long)]
+        config: Option<String>,
+    },
+
+    /// Check the target directory against its manifests: missing, changed,
+    /// unrecorded and misfiled files
+    Verify(VerifyArgs),
+
+    /// Move the files of the target directory into the folders a new
+    /// pattern gives them, recording every move in the manifest
+    Relayout(RelayoutArgs),
+
+    /// Search the catalog of the target directory
+    Query(QueryArgs),
+
+    /// List the runs recorded in the target directory, or show one
+    #[command(subcommand)]
+    Runs(RunsCommand),
+
     /// Generate shell completions (used by the release pipeline)
     #[command(hide = true)]
     Completions {
@@ -24,3 +77,15 @@ pub enum Commands {
     #[command(hide = true)]
     Manpage,
 }
+
+#[derive(Subcommand)]
+pub enum RunsCommand {
+    /// One line per run: id, start time, mode and result
+    List,
+
+    /// Options, result and files of a single run
+    Show {
+        /// Run id as printed by `runs list` (or after a run)
+        id: String,
+    },
+}
@@ -1,11 +1,34 @@
 use std::io::IsTerminal;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use indicatif::{ProgressBar, ProgressStyle};
+use anyhow::bail;
+use chrono::{DateTime, Local, Utc};
+use globset::Glob;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 
-use crate::cli::args::{CliArgs, CollisionArg, RevertArgs};
+use crate::cli::args::{
+    CategoryArg, CliArgs, CollisionArg, ImportArgs, ManifestFormatArg, ModeArg, OutputFormatArg,
+    PreserveArg, QueryArgs, RelayoutArgs, RevertArgs, ReviewArgs, VerifyArgs, WatchArgs,
+};
+use crate::cli::commands::RunsCommand;
+use crate::error::AppError;
+use crate::sorter::card::{self, CardCheck};
+use crate::sorter::catalog::{Catalog, CatalogQuery};
 use crate::sorter::config::SorterConfig;
-use crate::sorter::{self, CollisionPolicy, ProcessOptions, TransferMode};
+use crate::sorter::control::RunControl;
+use crate::sorter::dir::{Excluded, ScanOptions};
+use crate::sorter::explain::Verdict;
+use crate::sorter::ledger::Imported;
+use crate::sorter::manifest::ManifestFormat;
+use crate::sorter::preserve::Preserve;
+use crate::sorter::runs::RunInfo;
+use crate::sorter::spill::{PlanSpill, SpilledPlan};
+use crate::sorter::watch::WatchEvent;
+use crate::sorter::{
+    self, CollisionPolicy, PlannedAction, ProcessOptions, ProcessSummary, RevertFilter,
+    TouchPolicy, TransferMode,
+};
 
 use self::args::Args;
 
@@ -13,6 +36,219 @@ pub mod args;
 pub mod commands;
 
 pub fn run_cli(args: &Args, cli_args: &CliArgs) -> anyhow::Result<()> {
+    let options = process_options(cli_args)?;
+    let sources = source_dirs(args, cli_args.config.as_deref());
+    if let Some(plan_file) = &cli_args.plan_file {
+        return plan_to_file(args, cli_args, &sources, Path::new(plan_file), &options);
+    }
+    let plan = sorter::plan_sources(&sources, Path::new(&args.target_dir), &options, |_, _| {})?;
+    execute_plan(args, cli_args, plan, None, &options)
+}
+
+/// Plan into `plan_file`, counting items as the walk goes; then, unless
+/// this is a dry run, execute the plan read back from it.
+fn plan_to_file(
+    args: &Args,
+    cli_args: &CliArgs,
+    sources: &[PathBuf],
+    plan_file: &Path,
+    options: &ProcessOptions,
+) -> anyhow::Result<()> {
+    let target = Path::new(&args.target_dir);
+    let spill = PlanSpill::create(plan_file, sources, target)?;
+    let bar = progress_bar();
+    if let Some(bar) = &bar {
+        bar.set_style(
+            ProgressStyle::with_template("{spinner} {pos} files planned, elapsed {elapsed}")
+                .expect("static template is valid"),
+        );
+    }
+    let plan = sorter::plan_streaming(
+        sources,
+        target,
+        options,
+        |item| spill.push(item),
+        |done| {
+            if let Some(bar) = &bar {
+                bar.set_position(done as u64);
+            }
+        },
+    )?;
+    if let Some(bar) = &bar {
+        bar.finish_and_clear();
+    }
+    let spilled = spill.finish()?;
+    println!(
+        "Planned {} files into '{}'.",
+        spilled.len(),
+        plan_file.display()
+    );
+    if options.dry_run {
+        print_imported(&plan.imported, true);
+        print_excluded(&plan.excluded, true);
+        for error in &plan.scan_errors {
+            println!("  cannot scan {error}");
+        }
+        println!(
+            "Page through it with `exif-sorter review {}`.",
+            plan_file.display()
+        );
+        return Ok(());
+    }
+    execute_plan(args, cli_args, plan, Some(&spilled), options)
+}
+
+/// Print one page of a plan file.
+pub fn run_review(review_args: &ReviewArgs) -> anyhow::Result<()> {
+    let plan = SpilledPlan::open(Path::new(&review_args.file))?;
+    let size = review_args.page_size as usize;
+    let pages = plan.len().div_ceil(size).max(1);
+    let page = review_args.page as usize;
+    if page > pages {
+        bail!("the plan ends at page {pages} ({size} files per page)");
+    }
+    println!(
+        "Page {page} of {pages} ({} files into '{}'):",
+        plan.len(),
+        plan.target().display()
+    );
+    for item in plan.page((page - 1) * size, size)? {
+        println!(
+            "  {} -> {}  ({})",
+            item.image.source_full(),
+            item.planned_target.display(),
+            routing(&item.action)
+        );
+    }
+    Ok(())
+}
+
+pub fn run_merge(args: &Args, cli_args: &CliArgs) -> anyhow::Result<()> {
+    let options = process_options(cli_args)?;
+    let [library] = source_dirs(args, cli_args.config.as_deref())
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("merge takes one library; run it once per library"))?;
+    let plan = sorter::merge::plan_merge(&library, Path::new(&args.target_dir), &options)?;
+    execute_plan(args, cli_args, plan, None, &options)
+}
+
+pub fn run_watch(args: &Args, watch_args: &WatchArgs) -> anyhow::Result<()> {
+    let options = process_options(&watch_args.run)?;
+    let sources = source_dirs(args, watch_args.run.config.as_deref());
+    sorter::watch::watch(
+        &sources,
+        Path::new(&args.target_dir),
+        &options,
+        Duration::from_secs(watch_args.settle),
+        |event| match event {
+            WatchEvent::Watching => {
+                let sources: Vec<_> = sources.iter().map(|s| s.display().to_string()).collect();
+                println!(
+                    "Watching {} for new files (Ctrl-C or SIGTERM stops).",
+                    sources.join(", ")
+                );
+            }
+            WatchEvent::Resumed(summary) => {
+                println!("Finished the interrupted run:");
+                print_summary(&summary, &options, &args.target_dir);
+            }
+            WatchEvent::Sorted(summary) => print_summary(&summary, &options, &args.target_dir),
+            WatchEvent::Failed(reason) => eprintln!("error: {reason}"),
+        },
+    )
+}
+
+/// Import every mounted card in turn, each as an incremental run of its
+/// `DCIM` folder, then check and offer to remove what was imported.
+pub fn run_import(args: &Args, import_args: &ImportArgs) -> anyhow::Result<()> {
+    let options = process_options(&import_args.run)?;
+    let cards = card::find_cards(import_args.mount_root.as_deref().map(Path::new))?;
+    if cards.is_empty() {
+        bail!("no camera card found (no mounted volume has a DCIM folder)");
+    }
+    let target = Path::new(&args.target_dir);
+    for card in cards {
+        println!("Importing from '{}' ({}):", card.root.display(), card.uuid);
+        let options = ProcessOptions {
+            incremental: true,
+            volume: Some(card.clone()),
+            ..options.clone()
+        };
+        let plan = sorter::plan_sources(&[card.dcim()], target, &options, |_, _| {})?;
+        execute_plan(args, &import_args.run, plan, None, &options)?;
+        if import_args.remove_imported {
+            let check = card::check_imports(&card, target, &options.scan)?;
+            remove_from_card(&check, &card.root, options.dry_run, import_args.yes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Report `check` and remove its verified files from the card at `root`,
+/// once confirmed on the terminal (or right away with `yes`).
+fn remove_from_card(
+    check: &CardCheck,
+    root: &Path,
+    dry_run: bool,
+    yes: bool,
+) -> anyhow::Result<()> {
+    for (imported, reason) in &check.unverified {
+        println!("  keeping {}: {reason}", imported.path.display());
+    }
+    if check.not_imported > 0 {
+        println!(
+            "{} files on the card are not imported; they stay.",
+            check.not_imported
+        );
+    }
+    let count = check.verified.len();
+    if count == 0 {
+        return Ok(());
+    }
+    if dry_run {
+        println!("Would remove {count} verified files from the card.");
+        return Ok(());
+    }
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            println!("{count} verified files stay on the card (pass --yes to remove them).");
+            return Ok(());
+        }
+        print!(
+            "Remove {count} verified files from '{}'? [y/N] ",
+            root.display()
+        );
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("Left them on the card.");
+            return Ok(());
+        }
+    }
+    let failed = card::remove_verified(check);
+    println!("Removed {} files from the card.", count - failed.len());
+    for (path, e) in &failed {
+        eprintln!("  cannot remove {}: {e}", path.display());
+    }
+    Ok(())
+}
+
+/// The sources from `-s`, else from the config file, else the working
+/// directory.
+pub(crate) fn source_dirs(args: &Args, config: Option<&str>) -> Vec<PathBuf> {
+    if !args.source_dir.is_empty() {
+        return args.source_dir.iter().map(PathBuf::from).collect();
+    }
+    let config = SorterConfig::load(config.map(Path::new));
+    match config.sources {
+        Some(sources) if !sources.is_empty() => sources.into_iter().map(PathBuf::from).collect(),
+        _ => vec![PathBuf::from(".")],
+    }
+}
+
+/// Run options from the flags, then the config file, then the defaults.
+fn process_options(cli_args: &CliArgs) -> anyhow::Result<ProcessOptions> {
     // Precedence: command-line flag > config file > built-in default.
     let config = SorterConfig::load(cli_args.config.as_deref().map(Path::new));
 
@@ -35,25 +271,624 @@ pub fn run_cli(args: &Args, cli_args: &CliArgs) -> anyhow::Result<()> {
         })
         .unwrap_or_default();
 
-    let options = ProcessOptions {
-        dry_run: cli_args.dry_run,
-        mode: if cli_args.move_files || config.move_files.unwrap_or(false) {
-            TransferMode::Move
+    let mode = cli_args
+        .mode
+        .map(|arg| match arg {
+            ModeArg::Copy => TransferMode::Copy,
+            ModeArg::Move => TransferMode::Move,
+            ModeArg::Reflink => TransferMode::Reflink,
+            ModeArg::Hardlink => TransferMode::Hardlink,
+            ModeArg::Symlink => TransferMode::Symlink,
+        })
+        .or(cli_args.move_files.then_some(TransferMode::Move))
+        .or_else(|| match config.mode.as_deref() {
+            Some("copy") => Some(TransferMode::Copy),
+            Some("move") => Some(TransferMode::Move),
+            Some("reflink") => Some(TransferMode::Reflink),
+            Some("hardlink") => Some(TransferMode::Hardlink),
+            Some("symlink") => Some(TransferMode::Symlink),
+            Some(other) => {
+                eprintln!("warning: unknown mode '{other}' in config, using copy");
+                None
+            }
+            None => None,
+        })
+        .or(config.move_files.unwrap_or(false).then_some(TransferMode::Move))
+        .unwrap_or(TransferMode::Copy);
+
+    let preserve = match &cli_args.preserve {
+        Some(args) => Preserve::from_names(args.iter().map(|arg| match arg {
+            PreserveArg::Times => "times",
+            PreserveArg::Mode => "mode",
+            PreserveArg::Owner => "owner",
+            PreserveArg::Xattrs => "xattrs",
+            PreserveArg::All => "all",
+            PreserveArg::None => "none",
+        }))?,
+        None => match &config.preserve {
+            Some(names) => {
+                Preserve::from_names(names.iter().map(String::as_str)).unwrap_or_else(|e| {
+                    eprintln!("warning: {e} in config, preserving times only");
+                    Preserve::default()
+                })
+            }
+            None => Preserve::default(),
+        },
+    };
+
+    let touch = if !(cli_args.touch || config.touch.unwrap_or(false)) {
+        TouchPolicy::Off
+    } else if cli_args.touch_low_confidence || config.touch_low_confidence.unwrap_or(false) {
+        TouchPolicy::All
+    } else {
+        TouchPolicy::HighConfidence
+    };
+
+    let manifest = cli_args
+        .manifest_format
+        .map(|arg| match arg {
+            ManifestFormatArg::Csv => ManifestFormat::Csv,
+            ManifestFormatArg::Jsonl => ManifestFormat::Jsonl,
+        })
+        .or_else(|| match config.manifest_format.as_deref() {
+            Some("csv") => Some(ManifestFormat::Csv),
+            Some("jsonl") => Some(ManifestFormat::Jsonl),
+            Some(other) => {
+                eprintln!("warning: unknown manifest_format '{other}' in config, using csv");
+                None
+            }
+            None => None,
+        })
+        .unwrap_or_default();
+
+    let scan = ScanOptions {
+        include: if cli_args.include.is_empty() {
+            config.include.unwrap_or_default()
         } else {
-            TransferMode::Copy
+            cli_args.include.clone()
         },
+        exclude: if cli_args.exclude.is_empty() {
+            config.exclude.unwrap_or_default()
+        } else {
+            cli_args.exclude.clone()
+        },
+        max_depth: cli_args.max_depth.or(config.max_depth),
+        follow_symlinks: cli_args.follow_symlinks || config.follow_symlinks.unwrap_or(false),
+        hidden: cli_args.hidden || config.hidden.unwrap_or(false),
+        one_file_system: cli_args.one_file_system || config.one_file_system.unwrap_or(false),
+        strict: cli_args.strict || config.strict.unwrap_or(false),
+        detect_content: cli_args.detect_content || config.detect_content.unwrap_or(false),
+        archives: cli_args.archives || config.archives.unwrap_or(false),
+    };
+
+    Ok(ProcessOptions {
+        dry_run: cli_args.dry_run,
+        mode,
         collision,
+        preserve,
+        touch,
+        jobs: cli_args.jobs.or(config.jobs).unwrap_or(1).max(1).into(),
+        manifest,
+        scan,
+        fix_extensions: cli_args.fix_extensions || config.fix_extensions.unwrap_or(false),
+        incremental: cli_args.incremental || config.incremental.unwrap_or(false),
+        volume: None,
         pattern: cli_args
             .pattern
             .clone()
             .or(config.pattern)
             .unwrap_or_else(|| sorter::config::DEFAULT_PATTERN.to_string()),
+        control: cancel_on_ctrl_c(),
+    })
+}
+
+/// Check the space a plan needs, execute it with a progress bar and print
+/// the summary. The items are read from `spilled` when given, a chunk at a
+/// time, rather than from the plan.
+fn execute_plan(
+    args: &Args,
+    cli_args: &CliArgs,
+    mut plan: sorter::Plan,
+    spilled: Option<&SpilledPlan>,
+    options: &ProcessOptions,
+) -> anyhow::Result<()> {
+    let space = match spilled {
+        Some(spilled) => plan.spilled_space_check(options.mode, spilled)?,
+        None => plan.space_check(options.mode),
+    };
+    if !space.is_sufficient() {
+        let message = format!(
+            "the run needs {} but only {} are free on '{}'",
+            HumanBytes(space.needed),
+            HumanBytes(space.available.unwrap_or_default()),
+            args.target_dir
+        );
+        if cli_args.dry_run || cli_args.ignore_free_space {
+            eprintln!("warning: {message}");
+        } else {
+            bail!("{message} (pass --ignore-free-space to start anyway)");
+        }
+    }
+
+    let excluded = std::mem::take(&mut plan.excluded);
+    let imported = std::mem::take(&mut plan.imported);
+    // `execute` counts these into the summary and the run history.
+    let scan_errors = plan.scan_errors.clone();
+    let bar = progress_bar();
+    let total = spilled.map_or(plan.items.len(), SpilledPlan::len);
+    if let Some(bar) = &bar {
+        bar.set_length(total as u64);
+    }
+    let on_item = |_: usize, _: &sorter::ItemOutcome| {
+        if let Some(bar) = &bar {
+            bar.inc(1);
+        }
+    };
+    let summary = match spilled {
+        Some(spilled) => sorter::execute_spilled(plan, spilled, options, on_item)?,
+        None => sorter::execute(plan, options, on_item)?,
+    };
+    if let Some(bar) = &bar {
+        bar.finish_and_clear();
+    }
+
+    print_summary(&summary, options, &args.target_dir);
+    print_imported(&imported, options.dry_run);
+    print_excluded(&excluded, options.dry_run);
+    if !scan_errors.is_empty() {
+        println!(
+            "Could not scan {} paths; the run does not cover them (--strict stops instead):",
+            summary.scan_errors
+        );
+        for error in &scan_errors {
+            println!("  {error}");
+        }
+    }
+    if summary.cancelled {
+        bail!(AppError::Cancelled());
+    }
+
+    Ok(())
+}
+
+pub fn run_revert(revert_args: &RevertArgs) -> anyhow::Result<()> {
+    let manifest_path = Path::new(&revert_args.manifest);
+    let run = if revert_args.last {
+        match sorter::revert::last_run(manifest_path)? {
+            Some(id) => Some(id),
+            None => bail!("no run ids in '{}'", revert_args.manifest),
+        }
+    } else {
+        revert_args.run.clone()
+    };
+    let filter = RevertFilter {
+        run,
+        from: revert_args.from,
+        to: revert_args.to,
+        categories: revert_args
+            .category
+            .iter()
+            .map(|arg| category_name(*arg))
+            .map(String::from)
+            .collect(),
+        path: match &revert_args.path {
+            Some(glob) => Some(Glob::new(glob)?.compile_matcher()),
+            None => None,
+        },
+    };
+    let summary = sorter::revert(manifest_path, &filter, revert_args.dry_run)?;
+    let verb = if revert_args.dry_run {
+        "Would revert"
+    } else {
+        "Reverted"
+    };
+    println!(
+        "{verb} {} transfers ({} skipped).",
+        summary.reverted, summary.skipped
+    );
+    if !summary.modified.is_empty() {
+        println!(
+            "Left {} files alone that changed since the run:",
+            summary.modified.len()
+        );
+        for path in &summary.modified {
+            println!("  {path}");
+        }
+    }
+    if summary.removed_dirs > 0 {
+        println!("Removed {} empty directories.", summary.removed_dirs);
+    }
+    if let Some(manifest) = &summary.manifest {
+        println!(
+            "Undo this revert with: exif-sorter revert -m {}",
+            manifest.display()
+        );
+    }
+    Ok(())
+}
+
+pub fn run_verify(args: &Args, verify_args: &VerifyArgs) -> anyhow::Result<()> {
+    let pattern = verify_args
+        .pattern
+        .clone()
+        .or(SorterConfig::load(verify_args.config.as_deref().map(Path::new)).pattern)
+        .unwrap_or_else(|| sorter::config::DEFAULT_PATTERN.to_string());
+    let report = sorter::verify::verify(Path::new(&args.target_dir), &pattern, verify_args.fix)?;
+
+    println!(
+        "Checked {} recorded files in '{}'.",
+        report.checked, args.target_dir
+    );
+    let list = |title: &str, paths: &[std::path::PathBuf]| {
+        if !paths.is_empty() {
+            println!("{title} ({}):", paths.len());
+            for path in paths {
+                println!("  {}", path.display());
+            }
+        }
     };
-    let is_move = options.mode == TransferMode::Move;
+    list("Missing", &report.missing);
+    list("Changed since the run (size or checksum)", &report.changed);
+    list("Not in any manifest", &report.untracked);
+    if !report.misfiled.is_empty() {
+        println!("Misfiled ({}):", report.misfiled.len());
+        for misfiled in &report.misfiled {
+            println!(
+                "  {} -> {} ({} {})",
+                misfiled.path.display(),
+                misfiled.expected_dir.display(),
+                misfiled.date_source,
+                misfiled.date
+            );
+        }
+    }
+    if let (Some(run_id), Some(manifest)) = (&report.fix_run_id, &report.manifest) {
+        println!(
+            "Moved {} misfiled files (undo with: exif-sorter revert -m {} --run {run_id})",
+            report.fixed,
+            manifest.display()
+        );
+    } else if !report.misfiled.is_empty() {
+        println!("Run with --fix to move the misfiled files.");
+    }
+
+    if report.problems() > 0 {
+        bail!("verify found {} problems", report.problems());
+    }
+    println!("No problems found.");
+    Ok(())
+}
+
+pub fn run_relayout(args: &Args, relayout_args: &RelayoutArgs) -> anyhow::Result<()> {
+    let Some(pattern) = relayout_args
+        .pattern
+        .clone()
+        .or(SorterConfig::load(relayout_args.config.as_deref().map(Path::new)).pattern)
+    else {
+        bail!("no pattern to re-layout to: pass --pattern or set it in the config file");
+    };
+    let report =
+        sorter::relayout::relayout(Path::new(&args.target_dir), &pattern, relayout_args.dry_run)?;
+
+    for relocation in &report.relocations {
+        println!(
+            "{} -> {}",
+            relocation.from.display(),
+            relocation.to_dir.display()
+        );
+    }
+    for path in &report.undated {
+        println!("no date, left in place: {}", path.display());
+    }
+    for path in &report.missing {
+        println!("missing: {}", path.display());
+    }
+    for (path, reason) in &report.failed {
+        println!("failed: {} ({reason})", path.display());
+    }
+    if relayout_args.dry_run {
+        println!(
+            "Would move {} files to \"{pattern}\"; {} already in place.",
+            report.relocations.len(),
+            report.unchanged
+        );
+        return Ok(());
+    }
+    let moved = report.relocations.len() - report.failed.len();
+    println!(
+        "Moved {moved} files to \"{pattern}\"; {} already in place.",
+        report.unchanged
+    );
+    if let (Some(run_id), Some(manifest)) = (&report.run_id, &report.manifest) {
+        println!(
+            "Undo with: exif-sorter revert -m {} --run {run_id}",
+            manifest.display()
+        );
+    }
+    if !report.failed.is_empty() {
+        bail!("{} files could not be moved", report.failed.len());
+    }
+    Ok(())
+}
+
+pub fn run_query(args: &Args, query_args: &QueryArgs) -> anyhow::Result<()> {
+    let catalog = Catalog::open_existing(Path::new(&args.target_dir))?;
+    let records = catalog.query(&CatalogQuery {
+        year: query_args.year,
+        from: query_args.from,
+        to: query_args.to,
+        low_confidence: query_args.low_confidence,
+        camera: query_args.camera.clone(),
+        category: query_args
+            .category
+            .map(|arg| category_name(arg).to_string()),
+        run: query_args.run.clone(),
+        source_missing: query_args.source_missing,
+    })?;
+
+    match query_args.format {
+        OutputFormatArg::Json => println!("{}", serde_json::to_string_pretty(&records)?),
+        OutputFormatArg::Csv => {
+            println!(
+                "path,source,category,date,datetime,date_source,low_confidence,camera_make,\
+                 camera_model,latitude,longitude,width,height,size,checksum,run_id"
+            );
+            for record in &records {
+                let optional = |value: Option<String>| value.unwrap_or_default();
+                let row = [
+                    record.path.clone(),
+                    record.source.clone(),
+                    record.category.clone(),
+                    optional(record.date.clone()),
+                    optional(record.datetime.clone()),
+                    optional(record.date_source.clone()),
+                    record.low_confidence.to_string(),
+                    optional(record.camera_make.clone()),
+                    optional(record.camera_model.clone()),
+                    optional(record.latitude.map(|value| value.to_string())),
+                    optional(record.longitude.map(|value| value.to_string())),
+                    optional(record.width.map(|value| value.to_string())),
+                    optional(record.height.map(|value| value.to_string())),
+                    optional(record.size.map(|value| value.to_string())),
+                    optional(record.checksum.clone()),
+                    record.run_id.clone(),
+                ];
+                let row: Vec<String> = row
+                    .iter()
+                    .map(|field| sorter::manifest::csv_escape(field))
+                    .collect();
+                println!("{}", row.join(","));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn category_name(arg: CategoryArg) -> &'static str {
+    match arg {
+        CategoryArg::Sorted => "sorted",
+        CategoryArg::Unsorted => "unsorted",
+        CategoryArg::Corrupt => "corrupt",
+    }
+}
+
+pub fn run_explain(
+    args: &Args,
+    file: &str,
+    pattern: Option<&str>,
+    config: Option<&str>,
+) -> anyhow::Result<()> {
+    let config = SorterConfig::load(config.map(Path::new));
+    let pattern = pattern
+        .map(str::to_string)
+        .or(config.pattern)
+        .unwrap_or_else(|| sorter::config::DEFAULT_PATTERN.to_string());
+    let explanation =
+        sorter::explain::explain(Path::new(file), Path::new(&args.target_dir), &pattern)?;
+    let facts = &explanation.facts;
+
+    println!("File     {}", explanation.file.display());
+    let camera = [facts.camera_make.as_deref(), facts.camera_model.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !camera.is_empty() {
+        println!("Camera   {camera}");
+    }
+    if let Some(offset) = &facts.offset {
+        println!("Offset   {offset} (EXIF OffsetTimeOriginal)");
+    }
+    println!(
+        "Content  {}",
+        if explanation.content_recognized {
+            "recognized file signature"
+        } else {
+            "no known file signature"
+        }
+    );
+
+    println!("Date candidates:");
+    if facts.candidates.is_empty() {
+        println!("  none");
+    }
+    for candidate in &facts.candidates {
+        let (marker, note) = match explanation.verdict(candidate) {
+            Verdict::Chosen => ("→", "used"),
+            Verdict::Implausible => (" ", "rejected: before 1980 or in the future"),
+            Verdict::Outranked => (" ", "not used: a more trusted date came first"),
+        };
+        println!(
+            "  {marker} {}  {:<24} {note}",
+            candidate.datetime.format("%Y-%m-%d %H:%M:%S"),
+            candidate.source.to_string()
+        );
+    }
+
+    println!("Routing  {}", routing(&explanation.action));
+    println!("Target   {}", explanation.planned_target.display());
+
+    if !explanation.history.is_empty() {
+        println!("History:");
+        for (manifest, entry) in &explanation.history {
+            let when = entry
+                .timestamp
+                .map(|time| {
+                    DateTime::<Local>::from(time)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "  {when}  {:<18} {} -> {}",
+                entry.action, entry.source, entry.target
+            );
+            let run = if entry.run_id.is_empty() {
+                "-"
+            } else {
+                entry.run_id.as_str()
+            };
+            println!("    run {run} in {}", manifest.display());
+        }
+    }
+    Ok(())
+}
 
-    // Live progress on stderr only when attached to a terminal — keeps
-    // piped/CI output clean.
-    let bar = if std::io::stderr().is_terminal() {
+pub fn run_runs(args: &Args, command: &RunsCommand) -> anyhow::Result<()> {
+    let target_dir = Path::new(&args.target_dir);
+    let runs = sorter::runs::read_runs(target_dir)?;
+    match command {
+        RunsCommand::List => {
+            if runs.is_empty() {
+                println!("No runs recorded in '{}'.", args.target_dir);
+            }
+            for run in &runs {
+                let started = run
+                    .started
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S");
+                println!(
+                    "{}  {started}  {:<8}  {}",
+                    run.id,
+                    run.options.mode,
+                    run_result(run)
+                );
+            }
+        }
+        RunsCommand::Show { id } => {
+            let Some(run) = runs.iter().find(|run| run.id == *id) else {
+                bail!("no run '{id}' in '{}'", args.target_dir);
+            };
+            let time = |t: DateTime<Utc>| {
+                t.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            };
+            println!("Run {}", run.id);
+            println!("  version   {}", run.version);
+            println!("  started   {}", time(run.started));
+            println!(
+                "  finished  {}",
+                run.finished.map(time).unwrap_or_else(|| "-".to_string())
+            );
+            for source in &run.sources {
+                println!("  source    {}", source.display());
+            }
+            println!("  target    {}", run.target.display());
+            println!(
+                "  options   {}, on collision {:?}, pattern {}, jobs {}",
+                run.options.mode, run.options.collision, run.options.pattern, run.options.jobs
+            );
+            println!("  result    {}", run_result(run));
+
+            let manifest = target_dir.join(run.options.manifest.filename());
+            if manifest.exists() {
+                println!("Files:");
+                for entry in sorter::manifest::read_manifest(&manifest)?
+                    .iter()
+                    .filter(|entry| entry.run_id == run.id)
+                {
+                    println!(
+                        "  {:<18} {} -> {}",
+                        entry.action, entry.source, entry.target
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One-line outcome of a recorded run.
+fn run_result(run: &RunInfo) -> String {
+    match &run.totals {
+        None => "interrupted (or still running)".to_string(),
+        Some(totals) => {
+            let status = if totals.cancelled {
+                "cancelled"
+            } else {
+                "finished"
+            };
+            format!(
+                "{status}: {} of {} transferred, {} unsorted, {} corrupt, {} failed",
+                totals.transferred,
+                totals.total(),
+                totals.unsorted,
+                totals.corrupt,
+                totals.failed
+            )
+        }
+    }
+}
+
+pub fn run_resume(args: &Args) -> anyhow::Result<()> {
+    let control = cancel_on_ctrl_c();
+    let (options, summary) = sorter::resume(Path::new(&args.target_dir), &control, |_, _| {})?;
+    print_summary(&summary, &options, &args.target_dir);
+    if summary.cancelled {
+        bail!(AppError::Cancelled());
+    }
+    Ok(())
+}
+
+/// The first Ctrl-C lets the item in flight finish and stops there, so the
+/// manifest and the journal stay consistent; a second one quits at once.
+fn cancel_on_ctrl_c() -> RunControl {
+    let control = RunControl::new();
+    let handler = control.clone();
+    let installed = ctrlc::set_handler(move || {
+        if handler.is_cancelled() {
+            std::process::exit(130);
+        }
+        eprintln!("\nStopping after the current file (press Ctrl-C again to quit now)");
+        handler.cancel();
+    });
+    if let Err(e) = installed {
+        eprintln!("warning: cannot handle Ctrl-C ({e}), it will abort mid-file");
+    }
+    control
+}
+
+/// Where `action` routes a file and why, in a few words.
+fn routing(action: &PlannedAction) -> String {
+    match action {
+        PlannedAction::Sorted {
+            date, date_source, ..
+        } if date_source.is_low_confidence() => {
+            format!("sorted by {date_source} {date} (low confidence)")
+        }
+        PlannedAction::Sorted {
+            date, date_source, ..
+        } => format!("sorted by {date_source} {date}"),
+        PlannedAction::Unsorted { reason } => format!("unsorted: {reason}"),
+        PlannedAction::Corrupt { reason } => format!("corrupt: {reason}"),
+    }
+}
+
+/// Live progress on stderr only when attached to a terminal — keeps
+/// piped/CI output clean.
+fn progress_bar() -> Option<ProgressBar> {
+    if std::io::stderr().is_terminal() {
         let bar = ProgressBar::no_length();
         bar.set_style(
             ProgressStyle::with_template(
@@ -64,33 +899,67 @@ pub fn run_cli(args: &Args, cli_args: &CliArgs) -> anyhow::Result<()> {
         Some(bar)
     } else {
         None
-    };
-    let summary = sorter::process(
-        Path::new(&args.source_dir),
-        Path::new(&args.target_dir),
-        &options,
-        |done, total| {
-            if let Some(bar) = &bar {
-                bar.set_length(total as u64);
-                bar.set_position(done as u64);
-            }
-        },
-    )?;
-    if let Some(bar) = &bar {
-        bar.finish_and_clear();
     }
+}
+
+/// What the scan rules left out: every path with its rule on a dry run,
+/// else just how many.
+fn print_excluded(excluded: &[Excluded], dry_run: bool) {
+    if excluded.is_empty() {
+        return;
+    }
+    if !dry_run {
+        println!(
+            "{} paths left out by scan rules (list them with --dry-run).",
+            excluded.len()
+        );
+        return;
+    }
+    println!("Left out by scan rules ({}):", excluded.len());
+    for excluded in excluded {
+        println!("  {} ({})", excluded.path.display(), excluded.rule);
+    }
+}
+
+/// What `--incremental` skipped: every file with where the library holds it
+/// on a dry run, else just how many.
+fn print_imported(imported: &[Imported], dry_run: bool) {
+    if imported.is_empty() {
+        return;
+    }
+    if !dry_run {
+        println!(
+            "{} files already imported, skipped (list them with --dry-run).",
+            imported.len()
+        );
+        return;
+    }
+    println!("Already imported ({}):", imported.len());
+    for file in imported {
+        match &file.imported_as {
+            Some(earlier) => println!(
+                "  {} -> {} (imported as {})",
+                file.path.display(),
+                file.target.display(),
+                earlier.display()
+            ),
+            None => println!("  {} -> {}", file.path.display(), file.target.display()),
+        }
+    }
+}
 
-    let verb = match (cli_args.dry_run, is_move) {
-        (true, true) => "Would move",
-        (true, false) => "Would copy",
-        (false, true) => "Moved",
-        (false, false) => "Copied",
+fn print_summary(summary: &ProcessSummary, options: &ProcessOptions, target_dir: &str) {
+    let (mode, dry_run) = (options.mode, options.dry_run);
+    let verb = if dry_run {
+        format!("Would {mode}")
+    } else {
+        mode.past_tense().to_string()
     };
     println!(
         "{verb} {} of {} images into '{}'.",
         summary.transferred,
         summary.total(),
-        args.target_dir
+        target_dir
     );
     if summary.low_confidence > 0 {
         println!(
@@ -128,25 +997,18 @@ pub fn run_cli(args: &Args, cli_args: &CliArgs) -> anyhow::Result<()> {
             println!("  {path} ({reason})");
         }
     }
-    if !cli_args.dry_run && summary.total() > 0 {
+    if summary.cancelled && !dry_run {
         println!(
-            "Manifest: {}/{} (undo with: exif-sorter revert -m <manifest>)",
-            args.target_dir,
-            sorter::manifest::MANIFEST_FILENAME
+            "Cancelled; continue with: exif-sorter -t {} resume",
+            target_dir
+        );
+    }
+    if !dry_run && summary.total() > 0 {
+        println!(
+            "Manifest: {}/{} (undo this run with: exif-sorter revert -m <manifest> --run {})",
+            target_dir,
+            options.manifest.filename(),
+            summary.run_id
         );
     }
-
-    Ok(())
-}
-
-pub fn run_revert(revert_args: &RevertArgs) -> anyhow::Result<()> {
-    let (reverted, skipped) =
-        sorter::revert(Path::new(&revert_args.manifest), revert_args.dry_run)?;
-    let verb = if revert_args.dry_run {
-        "Would revert"
-    } else {
-        "Reverted"
-    };
-    println!("{verb} {reverted} transfers ({skipped} skipped).");
-    Ok(())
 }
@@ -2,10 +2,16 @@
 pub enum AppError {
     #[error("Could not parse {0} from DateTimeOriginal!")]
     DateTimeParsingEror(chrono::ParseError),
+    #[error("Cancelled by the user")]
+    Cancelled(),
     #[error("Intermittent IO error during iteration")]
     IntermittentIO(),
     #[error("Invalid source directory: {expected:?} could not be found!")]
     InvalidSource { expected: String },
+    #[error("An interrupted run is pending in {journal:?}; finish it with `exif-sorter resume` or delete the journal")]
+    InterruptedRun { journal: String },
+    #[error("The manifest {manifest:?} has the columns of an older version; move it aside to start a new one")]
+    OutdatedManifest { manifest: String },
     #[error("No DateTimeOriginal found!")]
     NoDateTimeOriginalFound(),
     #[error("No usable date tag found in exif data!")]
@@ -14,4 +20,8 @@ pub enum AppError {
     NoExifInformation(),
     #[error("No date found in exif data or file metadata!")]
     NoDateFound(),
+    #[error("cannot scan {path}: {message} (--strict)")]
+    ScanFailed { path: String, message: String },
+    #[error("verification failed for {target}: checksum mismatch after copy")]
+    VerificationFailed { target: String },
 }
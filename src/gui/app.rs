@@ -3,11 +3,12 @@ use std::sync::mpsc::{self, Receiver};
 
 use eframe::egui;
 use egui_extras::{Column, TableBuilder};
+use indicatif::HumanBytes;
 
 use crate::sorter::{
     ItemOutcome, Plan, PlannedAction, ProcessOptions, ProcessSummary, TransferMode,
 };
-use crate::worker::{self, WorkerEvent};
+use crate::worker::{self, WorkerEvent, WorkerHandle};
 
 /// Scan → review → confirm, same phases as the TUI. Nothing is written to
 /// disk before the user confirms the plan.
@@ -23,7 +24,9 @@ pub(crate) struct SorterApp {
     pub(crate) phase: Phase,
     pub(crate) source_dir: String,
     pub(crate) target_dir: String,
-    pub(crate) move_files: bool,
+    pub(crate) transfer_mode: TransferMode,
+    /// Leave out what earlier runs already imported into the target.
+    pub(crate) incremental: bool,
     pub(crate) error: Option<String>,
     /// Display copy of the plan; a clone is handed to the execute worker.
     pub(crate) plan: Option<Plan>,
@@ -31,7 +34,10 @@ pub(crate) struct SorterApp {
     pub(crate) outcomes: Vec<Option<ItemOutcome>>,
     pub(crate) progress: Option<(usize, usize)>,
     pub(crate) summary: Option<ProcessSummary>,
+    /// The running scan or execute was paused.
+    pub(crate) paused: bool,
     rx: Option<Receiver<WorkerEvent>>,
+    worker: Option<WorkerHandle>,
 }
 
 impl SorterApp {
@@ -40,13 +46,16 @@ impl SorterApp {
             phase: Phase::Setup,
             source_dir,
             target_dir,
-            move_files: false, // copy is the safe default for recovered media
+            transfer_mode: TransferMode::Copy, // safe default for recovered media
+            incremental: false,
             error: None,
             plan: None,
             outcomes: Vec::new(),
             progress: None,
             summary: None,
+            paused: false,
             rx: None,
+            worker: None,
         }
     }
 
@@ -58,14 +67,12 @@ impl SorterApp {
             WorkerEvent::PlanReady(plan) => {
                 self.outcomes = vec![None; plan.items.len()];
                 self.plan = Some(plan);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.phase = Phase::Review;
             }
             WorkerEvent::PlanFailed(reason) => {
                 self.error = Some(reason);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.phase = Phase::Setup;
             }
             WorkerEvent::ItemDone { index, outcome } => {
@@ -81,8 +88,11 @@ impl SorterApp {
                         *slot = Some(outcome);
                     }
                 }
+                // Parallel runs report items in completion order, so count
+                // them instead of trusting the index.
                 let total = self.plan.as_ref().map(|p| p.items.len()).unwrap_or(0);
-                self.progress = Some((index + 1, total));
+                let done = self.progress.map_or(0, |(done, _)| done) + 1;
+                self.progress = Some((done.min(total), total));
             }
             // Return to the review table (not a separate screen) so the
             // status column and the summary bar stay visible and the user
@@ -91,19 +101,38 @@ impl SorterApp {
                 self.deselect_completed();
                 self.summary = Some(summary);
                 self.error = None;
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.phase = Phase::Review;
             }
             WorkerEvent::ExecuteFailed(reason) => {
                 self.error = Some(reason);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
+                self.phase = Phase::Review;
+            }
+            WorkerEvent::Paused(paused) => self.paused = paused,
+            WorkerEvent::Cancelled(None) => {
+                self.worker_stopped();
+                self.phase = Phase::Setup;
+            }
+            // Like `Finished`: what was done stays marked and deselected, so
+            // running again processes the rest.
+            WorkerEvent::Cancelled(Some(summary)) => {
+                self.deselect_completed();
+                self.summary = Some(summary);
+                self.error = None;
+                self.worker_stopped();
                 self.phase = Phase::Review;
             }
         }
     }
 
+    fn worker_stopped(&mut self) {
+        self.progress = None;
+        self.paused = false;
+        self.rx = None;
+        self.worker = None;
+    }
+
     /// After a run, drop successfully-handled files from the selection so a
     /// follow-up run does not re-process them (copy would duplicate, move
     /// would fail on the missing source). Failures stay selected for retry.
@@ -127,7 +156,8 @@ impl SorterApp {
     }
 
     pub(crate) fn can_scan(&self) -> bool {
-        !self.source_dir.is_empty() && PathBuf::from(&self.source_dir).is_dir()
+        let sources = worker::split_sources(&self.source_dir);
+        !sources.is_empty() && sources.iter().all(|source| source.is_dir())
     }
 
     pub(crate) fn selected_count(&self) -> usize {
@@ -139,11 +169,8 @@ impl SorterApp {
 
     fn options(&self) -> ProcessOptions {
         ProcessOptions {
-            mode: if self.move_files {
-                TransferMode::Move
-            } else {
-                TransferMode::Copy
-            },
+            mode: self.transfer_mode,
+            incremental: self.incremental,
             ..ProcessOptions::default()
         }
     }
@@ -158,13 +185,13 @@ impl SorterApp {
         self.progress = Some((0, 0));
         self.phase = Phase::Planning;
         let repaint = ctx.clone();
-        worker::spawn_plan(
-            PathBuf::from(&self.source_dir),
+        self.worker = Some(worker::spawn_plan(
+            worker::split_sources(&self.source_dir),
             PathBuf::from(&self.target_dir),
             self.options(),
             tx,
             move || repaint.request_repaint(),
-        );
+        ));
     }
 
     fn start_execute(&mut self, ctx: &egui::Context) {
@@ -179,7 +206,9 @@ impl SorterApp {
         self.progress = Some((0, plan.items.len()));
         self.phase = Phase::Executing;
         let repaint = ctx.clone();
-        worker::spawn_execute(plan, self.options(), tx, move || repaint.request_repaint());
+        self.worker = Some(worker::spawn_execute(plan, self.options(), tx, move || {
+            repaint.request_repaint()
+        }));
     }
 
     fn reset(&mut self) {
@@ -244,12 +273,14 @@ impl SorterApp {
 
         egui::Grid::new("dirs").num_columns(3).show(ui, |ui| {
             ui.label("Source:");
-            ui.add(
-                egui::TextEdit::singleline(&mut self.source_dir).desired_width(420.0),
-            );
+            ui.add(egui::TextEdit::singleline(&mut self.source_dir).desired_width(420.0))
+                .on_hover_text("Several sources are separated like in PATH");
             if ui.button("Browse…").clicked() {
-                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
-                    self.source_dir = dir.to_string_lossy().into_owned();
+                if let Some(dirs) = rfd::FileDialog::new().pick_folders() {
+                    match worker::join_sources(&dirs) {
+                        Ok(field) => self.source_dir = field,
+                        Err(e) => self.error = Some(format!("{e:#}")),
+                    }
                 }
             }
             ui.end_row();
@@ -267,9 +298,10 @@ impl SorterApp {
         });
 
         ui.add_space(8.0);
+        mode_selector(ui, "setup_mode", &mut self.transfer_mode);
         ui.checkbox(
-            &mut self.move_files,
-            "Move files instead of copying (the source is removed)",
+            &mut self.incremental,
+            "Skip files already imported into the target",
         );
         ui.add_space(12.0);
 
@@ -300,9 +332,25 @@ impl SorterApp {
     fn ui_progress(&mut self, ui: &mut egui::Ui, label: &str) {
         ui.add_space(8.0);
         ui.horizontal(|ui| {
-            ui.add(egui::Spinner::new());
-            ui.label(label);
+            if self.paused {
+                ui.label("Paused");
+            } else {
+                ui.add(egui::Spinner::new());
+                ui.label(label);
+            }
         });
+        // Both take effect between two files.
+        if let Some(worker) = &self.worker {
+            ui.horizontal(|ui| {
+                let pause = if self.paused { "Continue" } else { "Pause" };
+                if ui.button(pause).clicked() {
+                    worker.set_paused(!self.paused);
+                }
+                if ui.button("Cancel").clicked() {
+                    worker.cancel();
+                }
+            });
+        }
         if let Some((done, total)) = self.progress {
             let fraction = done as f32 / total.max(1) as f32;
             ui.add(
@@ -349,8 +397,85 @@ impl SorterApp {
             if corrupt > 0 {
                 ui.colored_label(egui::Color32::RED, format!("{corrupt} corrupt"));
             }
+            if let Some(plan) = &self.plan {
+                let space = plan.space_check(self.transfer_mode);
+                let text = match space.available {
+                    Some(available) => format!(
+                        "needs {} of {} free",
+                        HumanBytes(space.needed),
+                        HumanBytes(available)
+                    ),
+                    None => format!("needs {}", HumanBytes(space.needed)),
+                };
+                if space.is_sufficient() {
+                    ui.label(text);
+                } else {
+                    ui.colored_label(egui::Color32::RED, text);
+                }
+            }
         });
 
+        if let Some(plan) = self
+            .plan
+            .as_ref()
+            .filter(|plan| !plan.scan_errors.is_empty())
+        {
+            egui::CollapsingHeader::new(
+                egui::RichText::new(format!(
+                    "{} paths could not be scanned; the plan does not cover them",
+                    plan.scan_errors.len()
+                ))
+                .color(egui::Color32::RED),
+            )
+            .id_salt("scan_errors")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("scan_error_list")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for error in &plan.scan_errors {
+                            ui.label(error.to_string());
+                        }
+                    });
+            });
+        }
+        if let Some(plan) = self.plan.as_ref().filter(|plan| !plan.imported.is_empty()) {
+            egui::CollapsingHeader::new(format!("{} files already imported", plan.imported.len()))
+                .id_salt("imported")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("imported_list")
+                        .max_height(120.0)
+                        .show(ui, |ui| {
+                            for file in &plan.imported {
+                                ui.label(format!(
+                                    "{} → {}",
+                                    file.path.display(),
+                                    file.target.display()
+                                ));
+                            }
+                        });
+                });
+        }
+        if let Some(plan) = self.plan.as_ref().filter(|plan| !plan.excluded.is_empty()) {
+            egui::CollapsingHeader::new(format!(
+                "{} paths left out by scan rules",
+                plan.excluded.len()
+            ))
+            .id_salt("excluded")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("excluded_list")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for excluded in &plan.excluded {
+                            ui.label(format!("{} — {}", excluded.path.display(), excluded.rule));
+                        }
+                    });
+            });
+        }
+
         ui.horizontal(|ui| {
             // Completed items stay locked: selecting them again would copy
             // twice (or fail on a moved source).
@@ -371,10 +496,7 @@ impl SorterApp {
                     }
                 }
             }
-            ui.checkbox(
-                &mut self.move_files,
-                "Move instead of copy (source is removed)",
-            );
+            mode_selector(ui, "review_mode", &mut self.transfer_mode);
         });
         ui.separator();
 
@@ -391,10 +513,24 @@ impl SorterApp {
             ui.colored_label(egui::Color32::RED, format!("Error: {error}"));
         }
         let n = self.selected_count();
-        let move_files = self.move_files;
+        let verb = capitalized(&self.transfer_mode.to_string());
+        let sufficient = self
+            .plan
+            .as_ref()
+            .is_none_or(|plan| plan.space_check(self.transfer_mode).is_sufficient());
+        if !sufficient {
+            ui.colored_label(
+                egui::Color32::RED,
+                "Not enough free space on the target for this selection — the run may fail halfway.",
+            );
+        }
         ui.horizontal(|ui| {
-            let verb = if move_files { "Move" } else { "Copy" };
-            let button = egui::Button::new(format!("{verb} {n} files"));
+            let label = if sufficient {
+                format!("{verb} {n} files")
+            } else {
+                format!("{verb} {n} files anyway")
+            };
+            let button = egui::Button::new(label);
             if ui.add_enabled(n > 0, button).clicked() {
                 self.start_execute(ctx);
             }
@@ -411,7 +547,7 @@ impl SorterApp {
         let Some(summary) = &self.summary else {
             return;
         };
-        let verb = if self.move_files { "Moved" } else { "Copied" };
+        let verb = self.transfer_mode.past_tense();
         ui.horizontal_wrapped(|ui| {
             ui.label(format!("{verb} {} of {}.", summary.transferred, summary.total()));
             if summary.low_confidence > 0 {
@@ -435,6 +571,15 @@ impl SorterApp {
                     format!("{} failed", summary.failed.len()),
                 );
             }
+            if summary.scan_errors > 0 {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("{} unreadable", summary.scan_errors),
+                );
+            }
+            if summary.cancelled {
+                ui.colored_label(egui::Color32::YELLOW, "stopped early");
+            }
             ui.weak("— adjust selection and run again to continue");
         });
     }
@@ -443,7 +588,9 @@ impl SorterApp {
         let Some(plan) = self.plan.as_mut() else {
             return;
         };
-        let source_prefix = plan.source.to_string_lossy().into_owned();
+        // With several sources, rows keep the name of the source they came
+        // from.
+        let several_sources = plan.sources.len() > 1;
         let target_prefix = plan.target.to_string_lossy().into_owned();
 
         TableBuilder::new(ui)
@@ -483,13 +630,17 @@ impl SorterApp {
                     row.col(|ui| {
                         // Always source_full(): source_path alone is the
                         // parent directory.
+                        let root = match item.source_root.parent() {
+                            Some(parent) if several_sources => parent,
+                            _ => &item.source_root,
+                        };
                         truncated_label(
                             ui,
-                            relative_to(&item.image.source_full(), &source_prefix),
+                            relative_to(&item.image.source_full(), &root.to_string_lossy()),
                         );
                     });
                     let (date_text, date_color, via) = match &item.action {
-                        PlannedAction::Sorted { date, date_source } => (
+                        PlannedAction::Sorted { date, date_source, .. } => (
                             date.to_string(),
                             if date_source.is_low_confidence() {
                                 egui::Color32::YELLOW
@@ -540,6 +691,43 @@ impl SorterApp {
 
 }
 
+/// Transfer mode picker with a one-line hint for the selected mode; shared
+/// by the setup and review screens.
+fn mode_selector(ui: &mut egui::Ui, id: &str, mode: &mut TransferMode) {
+    ui.horizontal(|ui| {
+        ui.label("Mode:");
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(capitalized(&mode.to_string()))
+            .show_ui(ui, |ui| {
+                for option in [
+                    TransferMode::Copy,
+                    TransferMode::Move,
+                    TransferMode::Reflink,
+                    TransferMode::Hardlink,
+                    TransferMode::Symlink,
+                ] {
+                    ui.selectable_value(mode, option, capitalized(&option.to_string()));
+                }
+            });
+        let hint = match mode {
+            TransferMode::Copy => "the source stays untouched",
+            TransferMode::Move => "the source is removed",
+            TransferMode::Reflink => "copy-on-write clone, falls back to copy",
+            TransferMode::Hardlink => "same filesystem only, takes no space",
+            TransferMode::Symlink => "links break if the source moves",
+        };
+        ui.weak(hint);
+    });
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 /// One-line cell text: truncate with … instead of wrapping into taller rows.
 fn truncated_label(ui: &mut egui::Ui, text: impl Into<egui::WidgetText>) {
     ui.add(egui::Label::new(text).truncate());
@@ -579,17 +767,26 @@ mod tests {
                     PathBuf::from(format!("/tmp/src/photo{i}.jpg")),
                     PathBuf::from("/tmp/dst"),
                 ),
+                source_root: PathBuf::from("/tmp/src"),
                 action: PlannedAction::Unsorted {
                     reason: "test".into(),
                 },
                 planned_target: PathBuf::from(format!("/tmp/dst/unsorted/photo{i}.jpg")),
                 selected: true,
+                size: 1024,
+                already_stored: false,
+                merge: None,
             })
             .collect();
         Plan {
-            source: PathBuf::from("/tmp/src"),
+            sources: vec![PathBuf::from("/tmp/src")],
             target: PathBuf::from("/tmp/dst"),
             items,
+            excluded: Vec::new(),
+            imported: Vec::new(),
+            scan_errors: Vec::new(),
+            available_space: None,
+            same_filesystem: false,
         }
     }
 
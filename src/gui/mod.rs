@@ -7,6 +7,7 @@ mod app;
 use crate::cli::args::Args;
 
 pub fn run_gui(args: Args) -> Result<()> {
+    let sources = crate::worker::join_sources(&crate::cli::source_dirs(&args, None))?;
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([900.0, 600.0])
@@ -21,7 +22,7 @@ pub fn run_gui(args: Args) -> Result<()> {
     eframe::run_native(
         "exif-sorter",
         native_options,
-        Box::new(move |_cc| Ok(Box::new(SorterApp::new(args.source_dir, args.target_dir)))),
+        Box::new(move |_cc| Ok(Box::new(SorterApp::new(sources, args.target_dir)))),
     )
     .map_err(|e| anyhow!("failed: {e:#}"))
 }
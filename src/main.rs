@@ -43,6 +43,41 @@ async fn main() {
                     process::exit(1);
                 }
             }
+            // Merge another sorted library
+            Commands::Merge(cli_args) => {
+                if let Err(e) = cli::run_merge(&args, cli_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Continue an interrupted run
+            Commands::Resume => {
+                if let Err(e) = cli::run_resume(&args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Sort new files as they arrive
+            Commands::Watch(watch_args) => {
+                if let Err(e) = cli::run_watch(&args, watch_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Import what is new on the mounted camera cards
+            Commands::Import(import_args) => {
+                if let Err(e) = cli::run_import(&args, import_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Page through a plan file
+            Commands::Review(review_args) => {
+                if let Err(e) = cli::run_review(review_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
             // Undo a previous run
             Commands::Revert(revert_args) => {
                 if let Err(e) = cli::run_revert(revert_args) {
@@ -50,6 +85,46 @@ async fn main() {
                     process::exit(1);
                 }
             }
+            // Explain the sorting of one file
+            Commands::Explain {
+                file,
+                pattern,
+                config,
+            } => {
+                if let Err(e) = cli::run_explain(&args, file, pattern.as_deref(), config.as_deref())
+                {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Audit a sorted library
+            Commands::Verify(verify_args) => {
+                if let Err(e) = cli::run_verify(&args, verify_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Re-layout a sorted library
+            Commands::Relayout(relayout_args) => {
+                if let Err(e) = cli::run_relayout(&args, relayout_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Search the library catalog
+            Commands::Query(query_args) => {
+                if let Err(e) = cli::run_query(&args, query_args) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
+            // Inspect recorded runs
+            Commands::Runs(runs_command) => {
+                if let Err(e) = cli::run_runs(&args, runs_command) {
+                    eprintln!("error: {e:#}");
+                    process::exit(1);
+                }
+            }
             Commands::Completions { shell } => {
                 use clap::CommandFactory as _;
                 let mut cmd = Args::command();
@@ -0,0 +1,454 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::bail;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use flate2::read::GzDecoder;
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+
+use super::dates::Dates;
+use super::dir::{self, ScanError};
+use super::image::Image;
+use super::ledger::{Imported, Ledger};
+use super::{plan_item, CollisionPolicy, PlannedItem, ProcessOptions};
+
+/// Separates an archive from the path of a member inside it, as in
+/// `takeout.zip!/Photos/IMG_0001.jpg`.
+pub const SEPARATOR: &str = "!/";
+
+/// Where `execute` extracts the selected members of archives, below the
+/// target; removed when the run ends.
+pub const STAGING_DIRNAME: &str = ".exif-sorter-staging";
+
+/// Bytes read from a member to recognise and date it: EXIF sits in the
+/// first segments of a photo, the `moov` box of most camera videos near the
+/// start.
+const HEAD_LEN: u64 = 1 << 20;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn kind(path: &Path) -> Option<Kind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if name.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// Whether `path` names a ZIP, TAR or gzipped TAR archive.
+pub fn is_archive(path: &Path) -> bool {
+    kind(path).is_some()
+}
+
+/// The source path of member `name` of `archive`.
+pub fn member_path(archive: &Path, name: &str) -> PathBuf {
+    PathBuf::from(format!("{}{SEPARATOR}{name}", archive.to_string_lossy()))
+}
+
+/// The archive and member name of a member's source path; `None` for
+/// paths outside archives.
+pub fn split(path: &Path) -> Option<(PathBuf, String)> {
+    let path = path.to_str()?;
+    path.match_indices(SEPARATOR).find_map(|(at, _)| {
+        let archive = Path::new(&path[..at]);
+        is_archive(archive).then(|| {
+            (
+                archive.to_path_buf(),
+                path[at + SEPARATOR.len()..].to_string(),
+            )
+        })
+    })
+}
+
+/// Whether the file at `path` is still there; for members, whether their
+/// archive is.
+pub fn source_exists(path: &Path) -> bool {
+    match split(path) {
+        Some((archive, _)) => archive.is_file(),
+        None => path.exists(),
+    }
+}
+
+/// A file inside an archive.
+pub struct Member {
+    /// Its path inside the archive.
+    pub name: String,
+    pub size: u64,
+    /// In UTC, like the filesystem timestamps of `Dates`.
+    pub modified: Option<NaiveDateTime>,
+}
+
+impl Member {
+    fn dates(&self) -> Dates {
+        Dates {
+            file_modified: self.modified,
+            ..Dates::default()
+        }
+    }
+}
+
+/// Call `f` with every file of `archive`, in archive order, and a reader
+/// of its content. TAR archives are read front to back exactly once.
+pub fn for_each_member(
+    archive: &Path,
+    mut f: impl FnMut(&Member, &mut dyn Read) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(kind) = kind(archive) else {
+        bail!("'{}' is not an archive", archive.display());
+    };
+    let file = File::open(archive)?;
+    match kind {
+        Kind::Zip => {
+            let mut zip = zip::ZipArchive::new(file)?;
+            for index in 0..zip.len() {
+                let mut entry = zip.by_index(index)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                let member = Member {
+                    name: entry.name().to_string(),
+                    size: entry.size(),
+                    modified: zip_modified(&entry),
+                };
+                f(&member, &mut entry)?;
+            }
+        }
+        Kind::Tar | Kind::TarGz => {
+            let reader: Box<dyn Read> = match kind {
+                Kind::TarGz => Box::new(GzDecoder::new(file)),
+                _ => Box::new(file),
+            };
+            let mut tar = tar::Archive::new(reader);
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let member = Member {
+                    name: entry.path()?.to_string_lossy().into_owned(),
+                    size: entry.size(),
+                    modified: entry
+                        .header()
+                        .mtime()
+                        .ok()
+                        .and_then(|secs| DateTime::from_timestamp(i64::try_from(secs).ok()?, 0))
+                        .map(|time| time.naive_utc()),
+                };
+                f(&member, &mut entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The modified time of a ZIP member, in UTC. The DOS time every entry
+/// carries is local time with no zone, so the extended timestamp field is
+/// preferred when the archiver wrote one.
+fn zip_modified<R: Read>(entry: &zip::read::ZipFile<'_, R>) -> Option<NaiveDateTime> {
+    let extended = entry.extra_data_fields().find_map(|field| match field {
+        zip::ExtraField::ExtendedTimestamp(timestamp) => timestamp.mod_time(),
+        _ => None,
+    });
+    if let Some(secs) = extended {
+        return DateTime::from_timestamp(i64::from(secs), 0).map(|time| time.naive_utc());
+    }
+    let local: NaiveDateTime = entry.last_modified()?.try_into().ok()?;
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.naive_utc())
+}
+
+/// A member extracted to a file of its own, removed when dropped unless
+/// kept.
+pub struct Spool {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Spool {
+    /// Extract `member` from `content` into `dir`, under a name of its own
+    /// that keeps the member's extension, with the member's modified time.
+    fn new(dir: &Path, member: &Member, mut content: impl Read) -> io::Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let extension = Path::new(&member.name)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let spool = Self {
+            path: dir.join(format!(
+                ".exif-sorter-member-{}-{}{extension}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            )),
+            keep: false,
+        };
+        let mut file = File::create(&spool.path)?;
+        io::copy(&mut content, &mut file)?;
+        drop(file);
+        if let Some(modified) = member.modified {
+            let mtime = filetime::FileTime::from_unix_time(modified.and_utc().timestamp(), 0);
+            let _ = filetime::set_file_mtime(&spool.path, mtime);
+        }
+        Ok(spool)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file, no longer removed on drop.
+    fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Extract member `name` of `archive` into `dir`.
+pub fn extract_member(archive: &Path, name: &str, dir: &Path) -> anyhow::Result<Spool> {
+    let mut spool = None;
+    for_each_member(archive, |member, content| {
+        if spool.is_none() && member.name == name {
+            spool = Some(Spool::new(dir, member, content)?);
+        }
+        Ok(())
+    })?;
+    match spool {
+        Some(spool) => Ok(spool),
+        None => bail!("'{name}' is not in '{}'", archive.display()),
+    }
+}
+
+/// Plan the media files inside `archive` like the files of a directory:
+/// members are taken by their extension or, with `detect_content`, by their
+/// first bytes, and dated from the first `HEAD_LEN` bytes, read into memory;
+/// nothing is extracted until `stage`. Members the ledger has seen unchanged
+/// go to `imported` instead. Fails with `AppError::Cancelled` when
+/// `options.control` is cancelled.
+pub(super) fn plan_archive(
+    archive: &Path,
+    source_root: &Path,
+    target: &Path,
+    options: &ProcessOptions,
+    ledger: &Ledger,
+    imported: &mut Vec<Imported>,
+) -> anyhow::Result<Vec<PlannedItem>> {
+    let mut items = Vec::new();
+    for_each_member(archive, |member, content| {
+        if !options.control.checkpoint() {
+            bail!(AppError::Cancelled());
+        }
+        let by_extension = Path::new(&member.name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(dir::is_supported_extension);
+        if !by_extension && !options.scan.detect_content {
+            return Ok(());
+        }
+        let path = member_path(archive, &member.name);
+        let mtime = member
+            .modified
+            .map(|modified| modified.and_utc().timestamp());
+        if let Some(found) = ledger.recorded(&path, member.size, mtime) {
+            imported.push(found);
+            return Ok(());
+        }
+        let mut head = Vec::new();
+        content.take(HEAD_LEN).read_to_end(&mut head)?;
+        if !by_extension && dir::sniffed_extension(&head).is_none() {
+            return Ok(());
+        }
+        debug!("{:<100}", path.to_string_lossy());
+
+        let mut image = Image::new(path, target.to_path_buf());
+        image.dates = member.dates();
+        image.head = Some(head);
+        let mut item = plan_item(image, member.size, source_root, target, options);
+        item.image.head = None;
+        items.push(item);
+        Ok(())
+    })?;
+    Ok(items)
+}
+
+/// Plan every archive of `archives`, each given with the source it was found
+/// in, reporting the ones that cannot be read as scan errors (failing on
+/// them under `strict`).
+pub(super) fn plan_archives(
+    archives: &[(PathBuf, PathBuf)],
+    target: &Path,
+    options: &ProcessOptions,
+    ledger: &Ledger,
+    imported: &mut Vec<Imported>,
+    scan_errors: &mut Vec<ScanError>,
+    mut on_archive: impl FnMut(),
+) -> anyhow::Result<Vec<PlannedItem>> {
+    let mut items = Vec::new();
+    for (source_root, archive) in archives {
+        match plan_archive(archive, source_root, target, options, ledger, imported) {
+            Ok(planned) => items.extend(planned),
+            Err(e) if options.control.is_cancelled() => return Err(e),
+            Err(e) => {
+                let message = format!("{e:#}");
+                if options.scan.strict {
+                    bail!(AppError::ScanFailed {
+                        path: archive.to_string_lossy().into_owned(),
+                        message,
+                    });
+                }
+                warn!("cannot read archive '{}': {message}", archive.display());
+                scan_errors.push(ScanError {
+                    path: archive.clone(),
+                    message,
+                });
+            }
+        }
+        on_archive();
+    }
+    Ok(items)
+}
+
+/// The archive members of a run, each archive read once: when the run
+/// reaches the first of its members, every selected member of it is
+/// extracted below the target. Dry runs extract only the members a dedupe
+/// has to compare, into the system's temporary directory. Members that
+/// cannot be extracted are left as they are and fail when transferred.
+/// Removes the staging directory when the run ends.
+pub(super) struct Staging {
+    dir: PathBuf,
+    created: bool,
+    /// The archives not read yet.
+    pending: HashMap<PathBuf, Members>,
+    /// Extracted members not yet handed to their item, by index.
+    staged: HashMap<usize, (PathBuf, Dates)>,
+}
+
+/// An archive's planned members by name, in plan order: their size, and
+/// their index if they are to be extracted. Members sharing a name are
+/// told apart by taking, in archive order, the next planned one of their
+/// name and size.
+type Members = HashMap<String, VecDeque<(u64, Option<usize>)>>;
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if self.created {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+impl Staging {
+    pub(super) fn new(target: &Path, options: &ProcessOptions) -> Self {
+        let dir = if options.dry_run {
+            std::env::temp_dir().join(format!("exif-sorter-staging-{}", std::process::id()))
+        } else {
+            target.join(STAGING_DIRNAME)
+        };
+        Self {
+            dir,
+            created: false,
+            pending: HashMap::new(),
+            staged: HashMap::new(),
+        }
+    }
+
+    /// Note the item numbered `index` in the plan. Every item of the run
+    /// is noted, in plan order, before the first `stage`.
+    pub(super) fn plan(&mut self, index: usize, item: &PlannedItem, options: &ProcessOptions) {
+        let Some((archive, name)) = split(Path::new(&item.image.source_full())) else {
+            return;
+        };
+        let needed = !options.dry_run
+            || (options.collision == CollisionPolicy::Dedupe && item.planned_target.exists());
+        self.pending
+            .entry(archive)
+            .or_default()
+            .entry(name)
+            .or_default()
+            .push_back((item.size, (item.selected && needed).then_some(index)));
+    }
+
+    /// Point the images of the archive members among `items` at their
+    /// extracted files, reading the archives none of the run's earlier
+    /// items came from.
+    pub(super) fn stage(
+        &mut self,
+        items: &mut [(usize, PlannedItem)],
+        options: &ProcessOptions,
+    ) -> anyhow::Result<()> {
+        for (index, item) in items {
+            if let Some((archive, _)) = split(Path::new(&item.image.source_full())) {
+                if let Some(members) = self.pending.remove(&archive) {
+                    self.extract(&archive, members, options)?;
+                }
+            }
+            if let Some((path, dates)) = self.staged.remove(index) {
+                item.image.dates = dates;
+                item.image.staged = Some(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn extract(
+        &mut self,
+        archive: &Path,
+        mut members: Members,
+        options: &ProcessOptions,
+    ) -> anyhow::Result<()> {
+        if !members.values().flatten().any(|(_, index)| index.is_some()) {
+            return Ok(());
+        }
+        if !self.created {
+            fs::create_dir_all(&self.dir)?;
+            self.created = true;
+        }
+        let (dir, staged) = (&self.dir, &mut self.staged);
+        let result = for_each_member(archive, |member, content| {
+            if !options.control.checkpoint() {
+                bail!(AppError::Cancelled());
+            }
+            let Some(queue) = members.get_mut(&member.name) else {
+                return Ok(());
+            };
+            if queue.front().is_none_or(|(size, _)| *size != member.size) {
+                // Left out when planned, e.g. imported by an earlier run.
+                return Ok(());
+            }
+            if let Some(index) = queue.pop_front().and_then(|(_, index)| index) {
+                let spool = Spool::new(dir, member, content)?;
+                staged.insert(index, (spool.keep(), member.dates()));
+            }
+            Ok(())
+        });
+        match result {
+            Err(_) if options.control.is_cancelled() => {}
+            Err(e) => warn!("cannot extract from '{}': {e:#}", archive.display()),
+            Ok(()) => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::checksum;
+use super::dir::{self, ScanOptions};
+use super::ledger::{Imported, Ledger};
+
+/// The folder cameras keep their pictures in (DCF, the camera file system
+/// standard).
+pub const DCIM_DIRNAME: &str = "DCIM";
+
+/// The mounts of this process, one per line (Linux).
+const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// Links named by filesystem UUID to the block devices (Linux).
+const BY_UUID: &str = "/dev/disk/by-uuid";
+
+/// The kernel's view of every block device and partition (Linux).
+const SYS_BLOCK: &str = "/sys/class/block";
+
+/// A mounted camera card.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Volume {
+    /// The filesystem UUID, or the mount point's name when none can be
+    /// found (desktops mount cards under their label or UUID). A name is
+    /// shared by every card with that label, like `EOS_DIGITAL` on Canon
+    /// cameras, so such cards are recognised by their paths as one.
+    pub uuid: String,
+    /// Where it is mounted.
+    pub root: PathBuf,
+}
+
+impl Volume {
+    /// The camera's folder on the card.
+    pub fn dcim(&self) -> PathBuf {
+        self.root.join(DCIM_DIRNAME)
+    }
+}
+
+/// One line of `MOUNTINFO`.
+struct Mount {
+    /// The device, like `/dev/sdb1`.
+    source: String,
+    point: PathBuf,
+}
+
+/// The camera cards that are mounted: volumes with a `DCIM` folder, by
+/// mount point, on devices the kernel calls removable. With `mount_root`
+/// (like `/media/$USER`), that directory and the ones in it are looked at
+/// instead, whatever they are on; built-in card readers that report their
+/// cards as fixed need it.
+pub fn find_cards(mount_root: Option<&Path>) -> anyhow::Result<Vec<Volume>> {
+    let mounts = read_mounts();
+    let candidates = match mount_root {
+        Some(root) => {
+            let mut dirs = vec![root.to_path_buf()];
+            for entry in fs::read_dir(root)? {
+                dirs.push(entry?.path());
+            }
+            dirs
+        }
+        None => mounts
+            .iter()
+            .filter(|mount| mount.source.starts_with("/dev/") && is_removable(&mount.source))
+            .map(|mount| mount.point.clone())
+            .collect(),
+    };
+    let uuids = uuids_by_device();
+    let mut cards: Vec<Volume> = candidates
+        .into_iter()
+        .filter(|dir| dir.join(DCIM_DIRNAME).is_dir())
+        .map(|dir| {
+            let root = dir.canonicalize().unwrap_or(dir);
+            let uuid = mounts
+                .iter()
+                .find(|mount| mount.point == root)
+                .and_then(|mount| fs::canonicalize(&mount.source).ok())
+                .and_then(|device| uuids.get(&device).cloned())
+                .unwrap_or_else(|| {
+                    let name = root
+                        .file_name()
+                        .unwrap_or(root.as_os_str())
+                        .to_string_lossy()
+                        .into_owned();
+                    warn!(
+                        "no filesystem UUID for '{}', telling it apart by the name '{name}'",
+                        root.display()
+                    );
+                    name
+                });
+            Volume { uuid, root }
+        })
+        .collect();
+    cards.sort_by(|a, b| a.root.cmp(&b.root));
+    cards.dedup_by(|a, b| a.root == b.root);
+    Ok(cards)
+}
+
+/// The mounts of `MOUNTINFO`; none where it cannot be read.
+fn read_mounts() -> Vec<Mount> {
+    let Ok(content) = fs::read_to_string(MOUNTINFO) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            // id parent major:minor root point options [optional…] - type source …
+            let fields: Vec<&str> = line.split(' ').collect();
+            let separator = fields.iter().position(|field| *field == "-")?;
+            Some(Mount {
+                source: unescape(fields.get(separator + 2)?),
+                point: PathBuf::from(unescape(fields.get(4)?)),
+            })
+        })
+        .collect()
+}
+
+/// Undo the octal escapes of `MOUNTINFO` fields, like `\040` for a space.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut at = 0;
+    while at < bytes.len() {
+        let code = bytes
+            .get(at + 1..at + 4)
+            .filter(|_| bytes[at] == b'\\')
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok());
+        match code {
+            Some(code) => {
+                unescaped.push(code);
+                at += 4;
+            }
+            None => {
+                unescaped.push(bytes[at]);
+                at += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// Whether the kernel calls `device` (like `/dev/sdb1`) removable; for a
+/// partition, whether its disk is.
+fn is_removable(device: &str) -> bool {
+    let Some(name) = fs::canonicalize(device)
+        .ok()
+        .and_then(|device| device.file_name().map(|name| name.to_os_string()))
+    else {
+        return false;
+    };
+    let Ok(mut sys) = fs::canonicalize(Path::new(SYS_BLOCK).join(name)) else {
+        return false;
+    };
+    if sys.join("partition").exists() {
+        sys.pop();
+    }
+    fs::read_to_string(sys.join("removable")).is_ok_and(|removable| removable.trim() == "1")
+}
+
+/// Filesystem UUIDs by the device they are on, from `BY_UUID`.
+fn uuids_by_device() -> HashMap<PathBuf, String> {
+    let Ok(entries) = fs::read_dir(BY_UUID) else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let device = entry.path().canonicalize().ok()?;
+            Some((device, entry.file_name().to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+/// What `check_imports` found on a card.
+#[derive(Default)]
+pub struct CardCheck {
+    /// Files the library holds an identical copy of: safe to remove.
+    pub verified: Vec<Imported>,
+    /// Imported files whose copy is gone, differs or only links back to
+    /// the card, with the reason.
+    pub unverified: Vec<(Imported, String)>,
+    /// Files of the card no run imported yet.
+    pub not_imported: usize,
+}
+
+/// Look up every file of `card`'s `DCIM` folder in the import ledger of
+/// `target` and compare the imported ones byte for byte (by checksum) with
+/// the library's copy. Reads only; see `remove_verified`.
+pub fn check_imports(
+    card: &Volume,
+    target: &Path,
+    scan: &ScanOptions,
+) -> anyhow::Result<CardCheck> {
+    let ledger = Ledger::load(target, Some(card))?;
+    let mut check = CardCheck::default();
+    for entry in dir::scan(&card.dcim(), scan)?.files {
+        let found = fs::metadata(entry.path())
+            .ok()
+            .and_then(|metadata| ledger.find(entry.path(), &metadata));
+        let Some(imported) = found else {
+            check.not_imported += 1;
+            continue;
+        };
+        match verify_copy(&imported) {
+            Ok(()) => check.verified.push(imported),
+            Err(reason) => check.unverified.push((imported, reason)),
+        }
+    }
+    Ok(check)
+}
+
+/// Whether the library's copy of `imported` has the content of the file on
+/// the card; the reason it cannot stand in for it otherwise.
+fn verify_copy(imported: &Imported) -> Result<(), String> {
+    let copy = fs::symlink_metadata(&imported.target)
+        .map_err(|_| format!("'{}' is gone", imported.target.display()))?;
+    if copy.is_symlink() {
+        return Err(format!(
+            "'{}' only links to the card",
+            imported.target.display()
+        ));
+    }
+    let checksum = |path: &Path| {
+        checksum::file_checksum(path).map_err(|e| format!("cannot read '{}': {e}", path.display()))
+    };
+    if checksum(&imported.path)? != checksum(&imported.target)? {
+        return Err(format!("'{}' differs", imported.target.display()));
+    }
+    Ok(())
+}
+
+/// Remove the verified files of `check` from the card; the ones that could
+/// not be removed, with the error.
+pub fn remove_verified(check: &CardCheck) -> Vec<(PathBuf, std::io::Error)> {
+    check
+        .verified
+        .iter()
+        .filter_map(|imported| {
+            fs::remove_file(&imported.path)
+                .err()
+                .map(|e| (imported.path.clone(), e))
+        })
+        .collect()
+}
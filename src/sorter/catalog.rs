@@ -0,0 +1,397 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use chrono::NaiveDate;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
+
+use super::archive;
+use super::card::Volume;
+use super::manifest::ManifestEntry;
+use super::revert::REVERTED_PREFIX;
+use super::TransferMode;
+
+pub const CATALOG_FILENAME: &str = "exif-sorter-catalog.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    path           TEXT PRIMARY KEY,
+    source         TEXT NOT NULL,
+    action         TEXT NOT NULL,
+    category       TEXT NOT NULL,
+    checksum       TEXT,
+    size           INTEGER,
+    date           TEXT,
+    datetime       TEXT,
+    date_source    TEXT,
+    low_confidence INTEGER NOT NULL DEFAULT 0,
+    time_zone      TEXT,
+    camera_make    TEXT,
+    camera_model   TEXT,
+    latitude       REAL,
+    longitude      REAL,
+    width          INTEGER,
+    height         INTEGER,
+    run_id         TEXT NOT NULL,
+    recorded       TEXT
+);
+CREATE INDEX IF NOT EXISTS files_date ON files (date);
+CREATE TABLE IF NOT EXISTS imports (
+    source      TEXT NOT NULL,
+    size        INTEGER,
+    mtime       INTEGER,
+    checksum    TEXT,
+    target      TEXT NOT NULL,
+    run_id      TEXT NOT NULL,
+    volume      TEXT,
+    volume_path TEXT
+);
+CREATE INDEX IF NOT EXISTS imports_target ON imports (target);
+";
+
+const COLUMNS: &str = "path, source, action, category, checksum, size, date, datetime, \
+    date_source, low_confidence, camera_make, camera_model, latitude, longitude, width, \
+    height, run_id";
+
+/// The library's index: one row per file the target holds, in an SQLite
+/// database next to the manifest. Kept current by `ManifestWriter`, which
+/// applies every row it records, so `execute`, `resume` and `revert` all
+/// update it. Rows are keyed by target path.
+///
+/// It also holds the import ledger: which source file (by path, size,
+/// modified time and checksum, and for camera cards by volume UUID and path
+/// on the card) went to which target, for `--incremental`.
+pub struct Catalog {
+    conn: Connection,
+}
+
+/// One file in the catalog, as returned by `Catalog::query`.
+#[derive(Clone, Debug, Serialize)]
+pub struct CatalogRecord {
+    pub path: String,
+    pub source: String,
+    /// The manifest action that stored it (copied, moved, …).
+    pub action: String,
+    pub category: String,
+    /// Empty for renames and links, which copy no bytes.
+    pub checksum: Option<String>,
+    pub size: Option<u64>,
+    pub date: Option<String>,
+    pub datetime: Option<String>,
+    pub date_source: Option<String>,
+    pub low_confidence: bool,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub run_id: String,
+}
+
+/// One source file of the import ledger, as returned by `Catalog::imports`.
+#[derive(Clone, Debug)]
+pub struct ImportRecord {
+    /// Absolute path of the source when it was imported.
+    pub source: PathBuf,
+    pub size: Option<u64>,
+    /// Modified time of the source, in seconds since the epoch.
+    pub mtime: Option<i64>,
+    pub checksum: Option<String>,
+    /// Where the library stored it (or the stored file it duplicated).
+    pub target: PathBuf,
+    pub run_id: String,
+    /// UUID of the removable volume the source was on, for card imports.
+    pub volume: Option<String>,
+    /// The source's path below that volume's mount point.
+    pub volume_path: Option<PathBuf>,
+}
+
+/// What `Catalog::query` selects. Every criterion that is set must match;
+/// the default selects everything.
+#[derive(Default)]
+pub struct CatalogQuery {
+    pub year: Option<i32>,
+    /// Capture dates from `from` to `to`, inclusive.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only files dated from file timestamps.
+    pub low_confidence: bool,
+    /// Text contained in the camera make or model, case-insensitive.
+    pub camera: Option<String>,
+    /// sorted, unsorted or corrupt.
+    pub category: Option<String>,
+    pub run: Option<String>,
+    /// Only files whose source path no longer exists.
+    pub source_missing: bool,
+}
+
+impl Catalog {
+    /// Open the catalog in `dir`, creating it if needed.
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(CATALOG_FILENAME);
+        let conn = Connection::open(&path)
+            .with_context(|| format!("cannot open catalog {}", path.display()))?;
+        // One transaction per manifest row; WAL keeps that from syncing
+        // the whole database every time.
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Open the catalog in `dir` without writing to it; `None` if there is
+    /// none.
+    pub fn open_read_only(dir: &Path) -> anyhow::Result<Option<Self>> {
+        let path = dir.join(CATALOG_FILENAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("cannot open catalog {}", path.display()))?;
+        Ok(Some(Self { conn }))
+    }
+
+    /// Open the existing catalog in `dir`; fails if there is none.
+    pub fn open_existing(dir: &Path) -> anyhow::Result<Self> {
+        if !dir.join(CATALOG_FILENAME).exists() {
+            bail!(
+                "no catalog in '{}' (runs record one from this version on)",
+                dir.display()
+            );
+        }
+        Self::open(dir)
+    }
+
+    /// Run `update` in one transaction, so a batch of rows costs a single
+    /// commit.
+    pub fn in_transaction(&self, update: impl FnOnce(&Self)) -> anyhow::Result<()> {
+        let transaction = self.conn.unchecked_transaction()?;
+        update(self);
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Bring the catalog in line with one manifest row: a transfer adds or
+    /// replaces the file at its target (and a move drops its source, should
+    /// that be catalogued), a reverted transfer removes it. Transfers and
+    /// duplicates are entered in the import ledger, which a revert forgets;
+    /// sources on `volume` also by their path on it. Other rows that stored
+    /// nothing (failed, …) change nothing.
+    pub fn apply(&self, entry: &ManifestEntry, volume: Option<&Volume>) -> anyhow::Result<()> {
+        if entry.action.starts_with(REVERTED_PREFIX) {
+            self.conn
+                .execute("DELETE FROM files WHERE path = ?1", params![entry.target])?;
+            self.conn.execute(
+                "DELETE FROM imports WHERE target = ?1",
+                params![entry.target],
+            )?;
+            return Ok(());
+        }
+        if entry.action == "duplicate" {
+            return self.record_import(entry, None, volume);
+        }
+        let Some(mode) = TransferMode::from_action(&entry.action) else {
+            return Ok(());
+        };
+        // A move inside the library (`verify --fix`, `relayout`) leaves its
+        // old path and takes its imports along.
+        let mut moved_inside = false;
+        if mode == TransferMode::Move {
+            self.conn
+                .execute("DELETE FROM files WHERE path = ?1", params![entry.source])?;
+            moved_inside = self.conn.execute(
+                "UPDATE imports SET target = ?2 WHERE target = ?1",
+                params![entry.source, entry.target],
+            )? > 0;
+        }
+        self.conn.execute(
+            "INSERT OR REPLACE INTO files (path, source, action, category, checksum, size, \
+             date, datetime, date_source, low_confidence, time_zone, camera_make, camera_model, \
+             latitude, longitude, width, height, run_id, recorded) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, \
+             ?17, ?18, ?19)",
+            params![
+                entry.target,
+                entry.source,
+                entry.action,
+                entry.category,
+                (!entry.checksum.is_empty()).then_some(&entry.checksum),
+                entry.size.and_then(|size| i64::try_from(size).ok()),
+                entry.date.map(|date| date.to_string()),
+                entry.datetime.map(|datetime| datetime.to_string()),
+                entry.date_source.map(|source| source.to_string()),
+                entry
+                    .date_source
+                    .is_some_and(|source| source.is_low_confidence()),
+                entry.time_zone,
+                entry.camera_make,
+                entry.camera_model,
+                entry.latitude,
+                entry.longitude,
+                entry.width,
+                entry.height,
+                entry.run_id,
+                entry.timestamp.map(|time| time.to_rfc3339()),
+            ],
+        )?;
+        if !moved_inside {
+            self.record_import(entry, Some(mode), volume)?;
+        }
+        Ok(())
+    }
+
+    /// Enter the source of `entry` in the import ledger, by its absolute
+    /// path. A symlink's row has the size of the link, so the size is taken
+    /// from the file it points to; rows without a checksum borrow the one
+    /// catalogued for their target.
+    fn record_import(
+        &self,
+        entry: &ManifestEntry,
+        mode: Option<TransferMode>,
+        volume: Option<&Volume>,
+    ) -> anyhow::Result<()> {
+        let source =
+            std::path::absolute(&entry.source).unwrap_or_else(|_| PathBuf::from(&entry.source));
+        let on_volume = volume.and_then(|volume| {
+            let path = source.strip_prefix(&volume.root).ok()?;
+            Some((&volume.uuid, path.to_string_lossy()))
+        });
+        let size = match mode {
+            Some(TransferMode::Symlink) => fs::metadata(&entry.target).ok().map(|m| m.len()),
+            _ => entry.size,
+        };
+        self.conn.execute(
+            "INSERT INTO imports (source, size, mtime, checksum, target, run_id, volume, \
+             volume_path) \
+             VALUES (?1, ?2, ?3, coalesce(?4, (SELECT checksum FROM files WHERE path = ?5)), \
+             ?5, ?6, ?7, ?8)",
+            params![
+                source.to_string_lossy(),
+                size.and_then(|size| i64::try_from(size).ok()),
+                entry.mtime_before.map(|time| time.timestamp()),
+                (!entry.checksum.is_empty()).then_some(&entry.checksum),
+                entry.target,
+                entry.run_id,
+                on_volume.as_ref().map(|(uuid, _)| uuid),
+                on_volume.as_ref().map(|(_, path)| path),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The import ledger, oldest first; empty for catalogs written before
+    /// there was one.
+    pub fn imports(&self) -> anyhow::Result<Vec<ImportRecord>> {
+        let has_ledger: bool = self.conn.query_row(
+            "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'imports'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_ledger {
+            return Ok(Vec::new());
+        }
+        let mut statement = self.conn.prepare(
+            "SELECT source, size, mtime, checksum, target, run_id, volume, volume_path \
+             FROM imports ORDER BY rowid",
+        )?;
+        let records = statement
+            .query_map([], |row| {
+                Ok(ImportRecord {
+                    source: PathBuf::from(row.get::<_, String>(0)?),
+                    size: row.get::<_, Option<i64>>(1)?.map(|size| size as u64),
+                    mtime: row.get(2)?,
+                    checksum: row.get(3)?,
+                    target: PathBuf::from(row.get::<_, String>(4)?),
+                    run_id: row.get(5)?,
+                    volume: row.get(6)?,
+                    volume_path: row.get::<_, Option<String>>(7)?.map(PathBuf::from),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// Files matching `query`, by capture time, undated last.
+    pub fn query(&self, query: &CatalogQuery) -> anyhow::Result<Vec<CatalogRecord>> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(year) = query.year {
+            conditions.push("substr(date, 1, 4) = ?");
+            values.push(Value::Text(format!("{year:04}")));
+        }
+        if let Some(from) = query.from {
+            conditions.push("date >= ?");
+            values.push(Value::Text(from.to_string()));
+        }
+        if let Some(to) = query.to {
+            conditions.push("date <= ?");
+            values.push(Value::Text(to.to_string()));
+        }
+        if query.low_confidence {
+            conditions.push("low_confidence = 1");
+        }
+        if let Some(camera) = &query.camera {
+            conditions
+                .push("(coalesce(camera_make, '') || ' ' || coalesce(camera_model, '')) LIKE ?");
+            values.push(Value::Text(format!("%{camera}%")));
+        }
+        if let Some(category) = &query.category {
+            conditions.push("category = ?");
+            values.push(Value::Text(category.clone()));
+        }
+        if let Some(run) = &query.run {
+            conditions.push("run_id = ?");
+            values.push(Value::Text(run.clone()));
+        }
+
+        let mut sql = format!("SELECT {COLUMNS} FROM files");
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY datetime IS NULL, datetime, path");
+        let mut statement = self.conn.prepare(&sql)?;
+        let records = statement
+            .query_map(rusqlite::params_from_iter(values), record_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records
+            .into_iter()
+            .filter(|record| {
+                !query.source_missing || !archive::source_exists(Path::new(&record.source))
+            })
+            .collect())
+    }
+}
+
+fn record_from_row(row: &rusqlite::Row) -> rusqlite::Result<CatalogRecord> {
+    Ok(CatalogRecord {
+        path: row.get(0)?,
+        source: row.get(1)?,
+        action: row.get(2)?,
+        category: row.get(3)?,
+        checksum: row.get(4)?,
+        size: row.get::<_, Option<i64>>(5)?.map(|size| size as u64),
+        date: row.get(6)?,
+        datetime: row.get(7)?,
+        date_source: row.get(8)?,
+        low_confidence: row.get(9)?,
+        camera_make: row.get(10)?,
+        camera_model: row.get(11)?,
+        latitude: row.get(12)?,
+        longitude: row.get(13)?,
+        width: row.get(14)?,
+        height: row.get(15)?,
+        run_id: row.get(16)?,
+    })
+}
+
+/// The catalog for the manifest at `manifest_path`: it lives next to it.
+pub fn catalog_dir(manifest_path: &Path) -> PathBuf {
+    match manifest_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
@@ -0,0 +1,32 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// Hex BLAKE3 checksum of a file's content, as stored in the manifest.
+pub fn file_checksum(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    let mut file = File::open(path)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize().to_hex().to_string());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// Copy `reader` into `writer`, hashing the bytes on the way through, so the
+/// source is read exactly once. Returns the hex checksum of what was read.
+pub fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize().to_hex().to_string());
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+}
@@ -11,19 +11,57 @@ pub const DEFAULT_PATTERN: &str = "{year}/{date}";
 /// explicit `--config` path. Command-line flags take precedence over it.
 ///
 /// ```toml
+/// sources = ["/home/me/Pictures/Phone", "/media/camera/DCIM"]
 /// pattern = "{year}/{month}"
-/// move = false
+/// mode = "reflink"
 /// on_collision = "dedupe"
+/// preserve = ["times", "mode", "xattrs"]
+/// jobs = 4
+/// manifest_format = "jsonl"
+/// exclude = ["Thumbnails", "*.mov"]
+/// max_depth = 4
 /// ```
 #[derive(Debug, Default, Deserialize)]
 pub struct SorterConfig {
+    /// Source directories sorted when no `-s` is given.
+    pub sources: Option<Vec<String>>,
     /// Folder layout, e.g. "{year}/{month}/{day}" or "{year}/{date}".
     pub pattern: Option<String>,
     /// Move instead of copy.
     #[serde(rename = "move")]
     pub move_files: Option<bool>,
+    /// "copy", "move", "reflink", "hardlink" or "symlink"; wins over `move`.
+    pub mode: Option<String>,
     /// "suffix", "skip" or "dedupe".
     pub on_collision: Option<String>,
+    /// Metadata carried over to copies: "times", "mode", "owner",
+    /// "xattrs", "all" or "none".
+    pub preserve: Option<Vec<String>>,
+    /// Set modified times to the capture time (`--touch`).
+    pub touch: Option<bool>,
+    /// Include file-timestamp dates when touching.
+    pub touch_low_confidence: Option<bool>,
+    /// Files transferred in parallel.
+    pub jobs: Option<u16>,
+    /// "csv" or "jsonl".
+    pub manifest_format: Option<String>,
+    /// Scan rules; see `dir::ScanOptions`.
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: Option<bool>,
+    pub hidden: Option<bool>,
+    pub one_file_system: Option<bool>,
+    /// Fail when a path of the source cannot be read.
+    pub strict: Option<bool>,
+    /// Recognise media by content (`--detect-content`).
+    pub detect_content: Option<bool>,
+    /// Sort the media inside archives of the source (`--archives`).
+    pub archives: Option<bool>,
+    /// Correct target extensions to the content (`--fix-extensions`).
+    pub fix_extensions: Option<bool>,
+    /// Skip what earlier runs already imported (`--incremental`).
+    pub incremental: Option<bool>,
 }
 
 impl SorterConfig {
@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Cancel or pause a running `plan` or `execute` from another thread
+/// (a UI, a Ctrl-C handler). Clones share state. Both phases look at it
+/// between items only, so a file is never left half-transferred.
+#[derive(Clone, Default)]
+pub struct RunControl {
+    inner: Arc<ControlState>,
+}
+
+#[derive(Default)]
+struct ControlState {
+    cancelled: AtomicBool,
+    paused: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl RunControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop at the next item boundary. Also ends a pause.
+    pub fn cancel(&self) {
+        // Under the lock, so a `checkpoint` cannot miss the wakeup between
+        // seeing the run uncancelled and starting to wait.
+        let _paused = self.lock_paused();
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.wake.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Hold at the next item boundary until `resume` (or `cancel`).
+    pub fn pause(&self) {
+        *self.lock_paused() = true;
+    }
+
+    pub fn resume(&self) {
+        *self.lock_paused() = false;
+        self.inner.wake.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.lock_paused()
+    }
+
+    /// Called between items: blocks while paused, then tells whether to go
+    /// on (`false` once cancelled).
+    pub fn checkpoint(&self) -> bool {
+        let mut paused = self.lock_paused();
+        while *paused && !self.is_cancelled() {
+            paused = self
+                .inner
+                .wake
+                .wait(paused)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        !self.is_cancelled()
+    }
+
+    fn lock_paused(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.inner.paused.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
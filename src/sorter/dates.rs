@@ -1,23 +1,37 @@
 use std::time::SystemTime;
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::image::DateSource;
 
 #[derive(Default, Clone)]
 pub struct Dates {
-    pub file_creation_date: Option<NaiveDate>,
-    pub file_modified_date: Option<NaiveDate>,
+    /// Filesystem timestamps, in UTC.
+    pub file_created: Option<NaiveDateTime>,
+    pub file_modified: Option<NaiveDateTime>,
     pub exif_date_time_original: Option<NaiveDate>,
 }
 
 impl Dates {
     pub fn new(created: Option<SystemTime>, modified: Option<SystemTime>) -> Self {
-        let created = created.map(|created| DateTime::<Utc>::from(created).date_naive());
-        let modified = modified.map(|modified| DateTime::<Utc>::from(modified).date_naive());
+        let created = created.map(|created| DateTime::<Utc>::from(created).naive_utc());
+        let modified = modified.map(|modified| DateTime::<Utc>::from(modified).naive_utc());
 
         Self {
-            file_creation_date: created,
-            file_modified_date: modified,
+            file_created: created,
+            file_modified: modified,
             exif_date_time_original: None,
         }
     }
 }
+
+/// A date a file offered, as listed in the detailed manifest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateCandidate {
+    pub source: DateSource,
+    /// Midnight for date-only sources.
+    pub datetime: NaiveDateTime,
+    /// Passed `Image::is_plausible_date`; implausible dates are never used.
+    pub plausible: bool,
+}
@@ -1,8 +1,21 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use ignore::WalkBuilder;
-use tracing::{debug, info};
+use anyhow::bail;
+use globset::{Glob, GlobMatcher};
+use ignore::gitignore::Gitignore;
+use ignore::{Match, WalkBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+
+use super::archive;
+
+/// Per-directory ignore file in gitignore syntax, honoured by every scan.
+pub const IGNORE_FILENAME: &str = ".exifsorterignore";
 
 const SUPPORTED_EXTENSIONS: [&str; 17] = [
     // images
@@ -13,57 +26,497 @@ const SUPPORTED_EXTENSIONS: [&str; 17] = [
     "mp4", "mov", "m4v", "3gp",
 ];
 
-fn is_image_file(entry: &ignore::DirEntry) -> bool {
+/// Whether `ext` is the extension of a supported media file.
+pub fn is_supported_extension(ext: &str) -> bool {
     // Case-insensitive: cameras write uppercase extensions (DSC09903.ARW,
     // R0010002.JPG) into DCIM directories.
+    SUPPORTED_EXTENSIONS
+        .iter()
+        .any(|e| ext.eq_ignore_ascii_case(e))
+}
+
+fn is_image_file(entry: &ignore::DirEntry) -> bool {
     entry
         .path()
         .extension()
         .and_then(|s| s.to_str())
-        .map(|ext| {
-            SUPPORTED_EXTENSIONS
+        .map(is_supported_extension)
+        .unwrap_or(false)
+}
+
+/// The extension matching the content of the file at `path`, sniffed from
+/// its magic bytes, if it is a supported media file.
+pub fn content_extension(path: &Path) -> Option<&'static str> {
+    infer::get_from_path(path)
+        .ok()
+        .flatten()
+        .map(|kind| kind.extension())
+        .filter(|ext| SUPPORTED_EXTENSIONS.contains(ext))
+}
+
+/// Like `content_extension`, from the first bytes of a file.
+pub fn sniffed_extension(head: &[u8]) -> Option<&'static str> {
+    infer::get(head)
+        .map(|kind| kind.extension())
+        .filter(|ext| SUPPORTED_EXTENSIONS.contains(ext))
+}
+
+/// Whether two extensions name the same media type, e.g. `JPG` and `jpeg`.
+pub fn same_media_type(a: &str, b: &str) -> bool {
+    fn canonical(ext: &str) -> String {
+        let ext = ext.to_ascii_lowercase();
+        match ext.as_str() {
+            "jpeg" => "jpg".to_string(),
+            "heic" => "heif".to_string(),
+            "m4v" | "3gp" => "mp4".to_string(),
+            _ => ext,
+        }
+    }
+    canonical(a) == canonical(b)
+}
+
+/// Which files a scan takes. The default takes every supported media file
+/// below the directory except hidden ones and those an `.exifsorterignore`
+/// excludes. `.gitignore` and `.ignore` files are not honoured: a photo is
+/// not a build artifact.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// When set, only files matching one of these globs are taken.
+    pub include: Vec<String>,
+    /// Files and directories matching one of these globs are left out.
+    pub exclude: Vec<String>,
+    /// Directory levels to descend; 0 takes only the directory's own files.
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    /// Take hidden files and descend into hidden directories.
+    pub hidden: bool,
+    /// Stay on the filesystem of the scanned directory.
+    pub one_file_system: bool,
+    /// Fail the scan on the first path it cannot read instead of reporting
+    /// it with the others.
+    pub strict: bool,
+    /// Sniff the content of every file, taking supported media whatever
+    /// its extension. Reads the start of each file, so slower.
+    pub detect_content: bool,
+    /// Take ZIP and TAR archives too, whose media members are planned like
+    /// files (see `archive`).
+    pub archives: bool,
+}
+
+/// The rule that left a path out of a scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExclusionRule {
+    /// Matched this `exclude` glob.
+    Excluded(String),
+    /// Matched no `include` glob.
+    NotIncluded,
+    /// Matched this pattern of an `.exifsorterignore` file.
+    IgnoreFile {
+        file: PathBuf,
+        pattern: String,
+    },
+    Hidden,
+    /// A directory below `max_depth`.
+    MaxDepth(usize),
+    /// A symlink, not followed.
+    Symlink,
+    /// On another filesystem than the scanned directory.
+    OtherFileSystem,
+}
+
+impl fmt::Display for ExclusionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Excluded(glob) => write!(f, "exclude {glob}"),
+            Self::NotIncluded => write!(f, "matches no include"),
+            Self::IgnoreFile { file, pattern } => write!(f, "{pattern} in {}", file.display()),
+            Self::Hidden => write!(f, "hidden"),
+            Self::MaxDepth(depth) => write!(f, "deeper than max depth {depth}"),
+            Self::Symlink => write!(f, "symlink, not followed"),
+            Self::OtherFileSystem => write!(f, "on another filesystem"),
+        }
+    }
+}
+
+/// A file or directory a scan left out. A directory stands for everything
+/// below it.
+#[derive(Clone, Debug)]
+pub struct Excluded {
+    pub path: PathBuf,
+    pub rule: ExclusionRule,
+}
+
+/// A path the scan could not read: an unreadable directory, a broken
+/// symlink being followed, an I/O error on failing media. Nothing below it
+/// is covered.
+#[derive(Clone, Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl ScanError {
+    fn new(error: &ignore::Error, dir: &Path) -> Self {
+        // The walker wraps the I/O error in the path and depth it hit.
+        let mut path = None;
+        let mut error = error;
+        loop {
+            match error {
+                ignore::Error::WithPath { path: at, err } => {
+                    path.get_or_insert_with(|| at.clone());
+                    error = err;
+                }
+                ignore::Error::WithDepth { err, .. }
+                | ignore::Error::WithLineNumber { err, .. } => {
+                    error = err;
+                }
+                ignore::Error::Loop { child, .. } => {
+                    path.get_or_insert_with(|| child.clone());
+                    break;
+                }
+                _ => break,
+            }
+        }
+        Self {
+            path: path.unwrap_or_else(|| dir.to_path_buf()),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The result of `scan`.
+#[derive(Default)]
+pub struct Scan {
+    pub files: Vec<ignore::DirEntry>,
+    /// Archives to plan the members of, with `ScanOptions::archives`.
+    pub archives: Vec<PathBuf>,
+    /// Media files and directories a rule left out, sorted by path.
+    pub excluded: Vec<Excluded>,
+    /// Paths that could not be read, sorted by path.
+    pub errors: Vec<ScanError>,
+}
+
+/// A glob of `include` or `exclude`. One without a `/` matches the file
+/// name (like `*.mov` or `Thumbnails`), one with a `/` the path relative
+/// to the scanned directory.
+struct PathGlob {
+    original: String,
+    matcher: GlobMatcher,
+    by_name: bool,
+}
+
+impl PathGlob {
+    fn new(original: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            original: original.to_string(),
+            matcher: Glob::new(original)?.compile_matcher(),
+            by_name: !original.contains('/'),
+        })
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        if self.by_name {
+            relative
+                .file_name()
+                .is_some_and(|name| self.matcher.is_match(name))
+        } else {
+            self.matcher.is_match(relative)
+        }
+    }
+}
+
+/// The rules of one scan, shared by the walker's threads.
+struct Rules {
+    root: PathBuf,
+    include: Vec<PathGlob>,
+    exclude: Vec<PathGlob>,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    hidden: bool,
+    root_device: Option<u64>,
+    detect_content: bool,
+    archives: bool,
+    /// `.exifsorterignore` of each directory seen, if it has one.
+    ignore_files: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl Rules {
+    fn new(root: &Path, options: &ScanOptions) -> anyhow::Result<Self> {
+        let globs = |globs: &[String]| {
+            globs
                 .iter()
-                .any(|e| ext.eq_ignore_ascii_case(e))
+                .map(|glob| PathGlob::new(glob))
+                .collect::<anyhow::Result<Vec<_>>>()
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            include: globs(&options.include)?,
+            exclude: globs(&options.exclude)?,
+            max_depth: options.max_depth,
+            follow_symlinks: options.follow_symlinks,
+            hidden: options.hidden,
+            root_device: options.one_file_system.then(|| device(root)).flatten(),
+            detect_content: options.detect_content,
+            archives: options.archives,
+            ignore_files: Mutex::new(HashMap::new()),
         })
-        .unwrap_or(false)
+    }
+
+    /// Whether `entry` is a media file: by its extension or, when content
+    /// is detected, by its magic bytes. Archives count when taken.
+    fn is_media(&self, entry: &ignore::DirEntry) -> bool {
+        let is_file = !entry.file_type().is_some_and(|ft| ft.is_dir());
+        is_image_file(entry)
+            || (self.archives && is_file && archive::is_archive(entry.path()))
+            || (self.detect_content && is_file && content_extension(entry.path()).is_some())
+    }
+
+    /// The rule that leaves `entry` out, if any. Files that are not media
+    /// files (`is_media`) are left out without one.
+    fn exclusion(&self, entry: &ignore::DirEntry, is_media: bool) -> Option<ExclusionRule> {
+        let path = entry.path();
+        let is_symlink = entry.path_is_symlink() && !self.follow_symlinks;
+        let is_dir = if is_symlink {
+            path.is_dir()
+        } else {
+            entry.file_type().is_some_and(|ft| ft.is_dir())
+        };
+        if !is_dir && !is_media {
+            return None;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+        if let Some(max_depth) = self.max_depth.filter(|&max| is_dir && entry.depth() > max) {
+            return Some(ExclusionRule::MaxDepth(max_depth));
+        }
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden && !self.hidden {
+            return Some(ExclusionRule::Hidden);
+        }
+        if is_symlink {
+            return Some(ExclusionRule::Symlink);
+        }
+        if self.root_device.is_some() && is_dir && device(path) != self.root_device {
+            return Some(ExclusionRule::OtherFileSystem);
+        }
+        if let Some(rule) = self.ignored(path, is_dir) {
+            return Some(rule);
+        }
+        if let Some(glob) = self.exclude.iter().find(|glob| glob.matches(relative)) {
+            return Some(ExclusionRule::Excluded(glob.original.clone()));
+        }
+        if !is_dir
+            && !self.include.is_empty()
+            && !self.include.iter().any(|glob| glob.matches(relative))
+        {
+            return Some(ExclusionRule::NotIncluded);
+        }
+        None
+    }
+
+    /// The `.exifsorterignore` pattern that ignores `path`. The file in the
+    /// deepest directory that has an opinion wins, as with `.gitignore`.
+    fn ignored(&self, path: &Path, is_dir: bool) -> Option<ExclusionRule> {
+        for dir in path.ancestors().skip(1) {
+            if let Some(ignore_file) = self.ignore_file(dir) {
+                match ignore_file.matched(path, is_dir) {
+                    Match::Ignore(glob) => {
+                        return Some(ExclusionRule::IgnoreFile {
+                            file: dir.join(IGNORE_FILENAME),
+                            pattern: glob.original().to_string(),
+                        })
+                    }
+                    Match::Whitelist(_) => return None,
+                    Match::None => {}
+                }
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        None
+    }
+
+    fn ignore_file(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        let mut ignore_files = self.ignore_files.lock().expect("mutex poisoned");
+        ignore_files
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let path = dir.join(IGNORE_FILENAME);
+                if !path.is_file() {
+                    return None;
+                }
+                let (ignore_file, error) = Gitignore::new(&path);
+                if let Some(e) = error {
+                    warn!("in '{}': {e}", path.display());
+                }
+                Some(Arc::new(ignore_file))
+            })
+            .clone()
+    }
+}
+
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt as _;
+    std::fs::metadata(path).ok().map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
 }
 
 /// Scan given directory including its subdirectories and returns a list of findings including their source path
 pub fn scan_dir(dir: &Path) -> anyhow::Result<Vec<ignore::DirEntry>> {
-    let mut files: Vec<ignore::DirEntry> = Vec::new();
-    let files_arc: Arc<Mutex<Vec<ignore::DirEntry>>> = Arc::new(Mutex::new(Vec::new()));
+    Ok(scan(dir, &ScanOptions::default())?.files)
+}
+
+/// Scan `dir` for media files under `options`, recording what each rule
+/// left out and what could not be read. With `options.strict`, a path that
+/// cannot be read fails the scan.
+pub fn scan(dir: &Path, options: &ScanOptions) -> anyhow::Result<Scan> {
+    let files = Mutex::new(Vec::new());
+    let walked = walk(dir, options, |entry| {
+        files.lock().expect("mutex poisoned").push(entry);
+        true
+    })?;
+    let files = files.into_inner().expect("mutex poisoned");
+
+    info!(
+        "Found {} images, {} archives, {} paths excluded, {} unreadable",
+        files.len(),
+        walked.archives.len(),
+        walked.excluded.len(),
+        walked.errors.len()
+    );
+
+    Ok(Scan {
+        files,
+        archives: walked.archives,
+        excluded: walked.excluded,
+        errors: walked.errors,
+    })
+}
+
+/// What `walk` found besides the media files it handed out.
+pub struct Walk {
+    /// Archives to plan the members of, with `ScanOptions::archives`.
+    pub archives: Vec<PathBuf>,
+    /// Media files and directories a rule left out, sorted by path.
+    pub excluded: Vec<Excluded>,
+    /// Paths that could not be read, sorted by path.
+    pub errors: Vec<ScanError>,
+}
 
+/// `scan` without collecting the files: each media file goes to `on_file`
+/// as soon as the walk finds it, on the walker's threads, in no particular
+/// order. The walk stops early once `on_file` returns `false`.
+pub fn walk(
+    dir: &Path,
+    options: &ScanOptions,
+    on_file: impl Fn(ignore::DirEntry) -> bool + Sync,
+) -> anyhow::Result<Walk> {
+    let archives: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    let excluded: Arc<Mutex<Vec<Excluded>>> = Arc::new(Mutex::new(Vec::new()));
+    let errors: Mutex<Vec<ScanError>> = Mutex::new(Vec::new());
+    let rules = Arc::new(Rules::new(dir, options)?);
+
+    let filter_rules = Arc::clone(&rules);
+    let filter_excluded = Arc::clone(&excluded);
     let walker = WalkBuilder::new(dir)
-        .hidden(true) // ignoring hidden files
-        .filter_entry(|entry| {
-            entry
-                .file_type()
-                .map(|ft| ft.is_dir()) // add subdirectories to walker
-                .unwrap_or(false)
-                || is_image_file(entry)
+        // Every rule is applied (and reported) by `Rules` instead.
+        .standard_filters(false)
+        .follow_links(options.follow_symlinks)
+        .same_file_system(options.one_file_system && cfg!(not(unix)))
+        .filter_entry(move |entry| {
+            let is_media = filter_rules.is_media(entry);
+            match filter_rules.exclusion(entry, is_media) {
+                Some(rule) => {
+                    filter_excluded
+                        .lock()
+                        .expect("mutex poisoned")
+                        .push(Excluded {
+                            path: entry.path().to_path_buf(),
+                            rule,
+                        });
+                    false
+                }
+                None => {
+                    entry
+                        .file_type()
+                        .map(|ft| ft.is_dir()) // add subdirectories to walker
+                        .unwrap_or(false)
+                        || is_media
+                }
+            }
         })
         .build_parallel();
 
+    let strict = options.strict;
+    let (archives_ref, errors_ref, on_file) = (&archives, &errors, &on_file);
     walker.run(|| {
-        let files_arc = Arc::clone(&files_arc);
+        let rules = Arc::clone(&rules);
         Box::new(move |result| {
-            if let Ok(entry) = result {
-                if entry.file_type().map(|ft| ft.is_file()).unwrap_or(true) && is_image_file(&entry)
-                {
-                    // Use OsStr-based display so non-UTF-8 filenames don't panic.
-                    debug!("{:<100}", entry.path().as_os_str().to_string_lossy());
+            match result {
+                Ok(entry) => {
+                    // Entries below the root already passed the filter.
+                    if entry.file_type().map(|ft| ft.is_file()).unwrap_or(true)
+                        && (entry.depth() > 0 || rules.is_media(&entry))
+                    {
+                        // Use OsStr-based display so non-UTF-8 filenames don't panic.
+                        debug!("{:<100}", entry.path().as_os_str().to_string_lossy());
 
-                    files_arc.lock().expect("mutex poisoned").push(entry);
+                        if options.archives && archive::is_archive(entry.path()) {
+                            archives_ref
+                                .lock()
+                                .expect("mutex poisoned")
+                                .push(entry.into_path());
+                        } else if !on_file(entry) {
+                            return ignore::WalkState::Quit;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let error = ScanError::new(&e, dir);
+                    warn!("cannot scan {error}");
+                    errors_ref.lock().expect("mutex poisoned").push(error);
+                    if strict {
+                        return ignore::WalkState::Quit;
+                    }
                 }
             }
             ignore::WalkState::Continue
         })
     });
 
-    let mut guard = files_arc.lock().expect("mutex poisoned");
-    files.append(&mut guard);
+    let mut archives = archives.into_inner().expect("mutex poisoned");
+    archives.sort();
+
+    let mut excluded = std::mem::take(&mut *excluded.lock().expect("mutex poisoned"));
+    excluded.sort_by(|a, b| a.path.cmp(&b.path));
 
-    info!("Found {} images", files.len());
+    let mut errors = errors.into_inner().expect("mutex poisoned");
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    if let Some(error) = errors.first().filter(|_| strict) {
+        bail!(AppError::ScanFailed {
+            path: error.path.to_string_lossy().into_owned(),
+            message: error.message.clone(),
+        });
+    }
 
-    Ok(files)
+    Ok(Walk {
+        archives,
+        excluded,
+        errors,
+    })
 }
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+use super::dates::DateCandidate;
+use super::image::{Image, MediaFacts};
+use super::manifest::{self, ManifestEntry, ManifestFormat};
+use super::{content_recognized, planned_target, route, PlannedAction};
+
+/// Why a single file is (or would be) where it is: the planning pipeline
+/// run on that one file, plus what the manifests remember about it.
+pub struct Explanation {
+    pub file: PathBuf,
+    /// Every date the file offers, with camera and EXIF offset.
+    pub facts: MediaFacts,
+    /// The content carries a known file signature.
+    pub content_recognized: bool,
+    /// What `plan` would decide for the file today.
+    pub action: PlannedAction,
+    pub planned_target: PathBuf,
+    /// Manifest rows naming the file as source or target, oldest first,
+    /// with the manifest each came from.
+    pub history: Vec<(PathBuf, ManifestEntry)>,
+}
+
+/// How `decide_action` treated one date candidate.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    /// The date the file is sorted by.
+    Chosen,
+    /// Failed `Image::is_plausible_date` (camera clock reset, future date).
+    Implausible,
+    /// Plausible, but a more trusted source came first.
+    Outranked,
+}
+
+impl Explanation {
+    pub fn verdict(&self, candidate: &DateCandidate) -> Verdict {
+        match &self.action {
+            PlannedAction::Sorted {
+                datetime,
+                date_source,
+                ..
+            } if *date_source == candidate.source && *datetime == candidate.datetime => {
+                Verdict::Chosen
+            }
+            _ if !candidate.plausible => Verdict::Implausible,
+            _ => Verdict::Outranked,
+        }
+    }
+}
+
+/// Run the dating and routing of `plan` on `file` alone, as if sorting it
+/// into `target` with `pattern`, and collect its manifest history from the
+/// libraries it lives in (any ancestor directory with a manifest) and from
+/// `target`. Reads only.
+pub fn explain(file: &Path, target: &Path, pattern: &str) -> anyhow::Result<Explanation> {
+    if !file.is_file() {
+        bail!("'{}' is not a file", file.display());
+    }
+    let image = Image::new(file.to_path_buf(), target.to_path_buf());
+    let (dated, facts) = image.date_with_facts();
+    let action = route(&image, dated);
+    let planned_target = planned_target(&image, &action, target, pattern);
+
+    Ok(Explanation {
+        file: file.to_path_buf(),
+        facts,
+        content_recognized: content_recognized(file),
+        action,
+        planned_target,
+        history: history(file, target)?,
+    })
+}
+
+fn history(file: &Path, target: &Path) -> anyhow::Result<Vec<(PathBuf, ManifestEntry)>> {
+    let canonical = file.canonicalize()?;
+    let names_file = |path: &str| {
+        Path::new(path) == file
+            || Path::new(path)
+                .canonicalize()
+                .is_ok_and(|path| path == canonical)
+    };
+
+    let mut dirs: Vec<PathBuf> = canonical
+        .ancestors()
+        .skip(1)
+        .map(Path::to_path_buf)
+        .collect();
+    if let Ok(target) = target.canonicalize() {
+        if !dirs.contains(&target) {
+            dirs.push(target);
+        }
+    }
+    let mut history = Vec::new();
+    for dir in dirs {
+        for format in [ManifestFormat::Csv, ManifestFormat::Jsonl] {
+            let manifest_path = dir.join(format.filename());
+            if !manifest_path.exists() {
+                continue;
+            }
+            history.extend(
+                manifest::read_manifest(&manifest_path)?
+                    .into_iter()
+                    .filter(|entry| names_file(&entry.source) || names_file(&entry.target))
+                    .map(|entry| (manifest_path.clone(), entry)),
+            );
+        }
+    }
+    history.sort_by_key(|(_, entry)| entry.timestamp);
+    Ok(history)
+}
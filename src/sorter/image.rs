@@ -5,22 +5,24 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::bail;
-use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+use anyhow::{bail, Context as _};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use exif::{Exif, In, Tag};
 use rand::Rng as _;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 
 use crate::error::AppError;
 
-use super::dates::Dates;
+use super::dates::{DateCandidate, Dates};
+use super::preserve::{self, Preserve};
 use super::TransferMode;
 
 /// Where a date was extracted from, ordered by trustworthiness.
 /// Recovered files (e.g. PhotoRec output) often carry filesystem timestamps
 /// from the recovery run, not the capture — callers can use this to route
 /// low-confidence dates differently.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DateSource {
     ExifDateTimeOriginal,
     ExifDateTimeDigitized,
@@ -48,12 +50,67 @@ impl std::fmt::Display for DateSource {
     }
 }
 
+impl std::str::FromStr for DateSource {
+    type Err = ();
+
+    /// The inverse of `Display`, for reading CSV manifests.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            DateSource::ExifDateTimeOriginal,
+            DateSource::ExifDateTimeDigitized,
+            DateSource::ExifDateTime,
+            DateSource::ExifGpsDate,
+            DateSource::VideoCreationTime,
+            DateSource::Filename,
+            DateSource::FileCreated,
+            DateSource::FileModified,
+        ]
+        .into_iter()
+        .find(|source| source.to_string() == s)
+        .ok_or(())
+    }
+}
+
 impl DateSource {
     /// File timestamps are unreliable on recovered media (they reflect the
     /// recovery, not the capture).
     pub fn is_low_confidence(&self) -> bool {
         matches!(self, DateSource::FileCreated | DateSource::FileModified)
     }
+
+    /// Whether a datetime from this source is UTC. EXIF datetimes and
+    /// filename dates are the camera's wall clock, i.e. local time.
+    pub fn is_utc(&self) -> bool {
+        matches!(
+            self,
+            DateSource::VideoCreationTime | DateSource::FileCreated | DateSource::FileModified
+        )
+    }
+}
+
+/// EXIF tags holding a full datetime, from most to least specific.
+const DATETIME_TAGS: [(Tag, DateSource); 3] = [
+    (Tag::DateTimeOriginal, DateSource::ExifDateTimeOriginal),
+    (Tag::DateTimeDigitized, DateSource::ExifDateTimeDigitized),
+    (Tag::DateTime, DateSource::ExifDateTime),
+];
+
+/// What a file's metadata says beyond the date it is sorted by, for the
+/// manifest and the catalog.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MediaFacts {
+    /// Every date found, in the order of the fallback chain.
+    pub candidates: Vec<DateCandidate>,
+    /// EXIF `OffsetTimeOriginal`, e.g. "+02:00".
+    pub offset: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// Decimal degrees from the EXIF GPS tags; south and west negative.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Pixel dimensions from the image header.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
 }
 
 #[derive(Clone)]
@@ -66,6 +123,15 @@ pub struct Image {
     pub target_filename: String,
     pub target_filetype: String,
     pub error: Option<AppError>,
+    /// For archive members: the file the member was extracted to, read and
+    /// transferred in place of the source.
+    pub staged: Option<PathBuf>,
+    /// For archive members while they are planned: the first bytes of the
+    /// member, read in place of the source.
+    pub head: Option<Vec<u8>>,
+    /// What planning read of the source's metadata, for the manifest;
+    /// `None` when it was not gathered (see `date_with_facts`).
+    pub facts: Option<MediaFacts>,
 }
 
 impl Image {
@@ -102,22 +168,67 @@ impl Image {
             target_filename: filename.to_string(),
             target_filetype: filetype,
             error: None,
+            staged: None,
+            head: None,
+            facts: None,
         }
     }
 
     pub fn source_filename(&self) -> String {
+        // Files picked up by their content may have no extension at all.
+        if self.source_filetype.is_empty() {
+            return self.source_filename.clone();
+        }
         format!("{}.{}", self.source_filename, self.source_filetype)
     }
 
     pub fn source_full(&self) -> String {
         format!(
-            "{}/{}.{}",
+            "{}/{}",
             self.source_path.to_string_lossy(),
-            self.source_filename,
-            self.source_filetype
+            self.source_filename()
         )
     }
 
+    /// Where the content of the source is read from: the source itself or,
+    /// for archive members, the file they were extracted to.
+    pub fn content_path(&self) -> PathBuf {
+        self.staged
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(self.source_full()))
+    }
+
+    /// Whether the source is a file inside an archive.
+    pub fn is_archive_member(&self) -> bool {
+        super::archive::split(Path::new(&self.source_full())).is_some()
+    }
+
+    /// A video by its extension or, for files whose extension says
+    /// nothing about it, by its content.
+    fn is_video(&self) -> bool {
+        super::video::is_video_extension(&self.source_filetype)
+            || (!super::dir::is_supported_extension(&self.source_filetype)
+                && self
+                    .content_extension()
+                    .is_some_and(super::video::is_video_extension))
+    }
+
+    /// The extension the content calls for, see `dir::content_extension`.
+    pub fn content_extension(&self) -> Option<&'static str> {
+        match &self.head {
+            Some(head) => super::dir::sniffed_extension(head),
+            None => super::dir::content_extension(&self.content_path()),
+        }
+    }
+
+    /// The creation time of a video's container.
+    fn video_time(&self) -> Option<NaiveDateTime> {
+        match &self.head {
+            Some(head) => super::video::creation_time_in(head),
+            None => super::video::creation_time(&self.content_path()),
+        }
+    }
+
     pub fn target_filename(&self) -> String {
         format!("{}.{}", self.target_filename, self.target_filetype)
     }
@@ -140,31 +251,36 @@ impl Image {
     /// Implausible dates (camera clock reset to epoch/2000, dates in the
     /// future) are skipped so the next source gets a chance.
     pub fn read_exif_date(&self) -> anyhow::Result<(NaiveDate, DateSource)> {
-        let full_path = self
-            .source_path
-            .join(format!("{}.{}", self.source_filename, self.source_filetype));
-
-        let file = std::fs::File::open(full_path.as_path())?;
+        self.read_exif_datetime()
+            .map(|(datetime, source)| (datetime.date(), source))
+    }
 
-        let mut bufreader = std::io::BufReader::new(&file);
+    /// Like `read_exif_date`, with the time of day. The GPS date stamp
+    /// carries no time and resolves to midnight.
+    pub fn read_exif_datetime(&self) -> anyhow::Result<(NaiveDateTime, DateSource)> {
         let exifreader = exif::Reader::new();
-        let exif = match exifreader.read_from_container(&mut bufreader) {
+        let read = match &self.head {
+            Some(head) => exifreader.read_from_container(&mut std::io::Cursor::new(head)),
+            None => {
+                let file = std::fs::File::open(self.content_path())?;
+                let mut bufreader = std::io::BufReader::new(&file);
+                exifreader.read_from_container(&mut bufreader)
+            }
+        };
+        let exif = match read {
             Ok(exif) => exif,
             Err(_) => bail!(AppError::NoExifInformation()),
         };
+        self.exif_datetime(&exif)
+    }
 
-        const DATETIME_TAGS: [(Tag, DateSource); 3] = [
-            (Tag::DateTimeOriginal, DateSource::ExifDateTimeOriginal),
-            (Tag::DateTimeDigitized, DateSource::ExifDateTimeDigitized),
-            (Tag::DateTime, DateSource::ExifDateTime),
-        ];
-
+    fn exif_datetime(&self, exif: &Exif) -> anyhow::Result<(NaiveDateTime, DateSource)> {
         for (tag, source) in DATETIME_TAGS {
-            match Self::extract_datetime_from_exif(&exif, tag) {
+            match Self::extract_datetime_from_exif(exif, tag) {
                 Ok(datetime) => {
                     let date = datetime.date();
                     if Self::is_plausible_date(date) {
-                        return Ok((date, source));
+                        return Ok((datetime, source));
                     }
                     debug!(
                         "File '{}': implausible {source} '{date}', trying next source",
@@ -179,46 +295,157 @@ impl Image {
 
         // GPS date comes from the satellite fix, independent of the camera
         // clock — a good last resort when all datetime tags are missing.
-        if let Some(field) = exif.get_field(Tag::GPSDateStamp, In::PRIMARY) {
-            let date_str = field.display_value().to_string().replace('"', "");
-            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                .or_else(|_| NaiveDate::parse_from_str(&date_str, "%Y:%m:%d"));
-            if let Ok(date) = date {
-                if Self::is_plausible_date(date) {
-                    return Ok((date, DateSource::ExifGpsDate));
-                }
+        if let Some(date) = Self::gps_date(exif) {
+            if Self::is_plausible_date(date) {
+                return Ok((date.and_time(NaiveTime::MIN), DateSource::ExifGpsDate));
             }
         }
 
         bail!(AppError::NoExifDateFound())
     }
 
+    fn read_exif_data(&self) -> Option<Exif> {
+        if let Some(head) = &self.head {
+            return exif::Reader::new()
+                .read_from_container(&mut std::io::Cursor::new(head))
+                .ok();
+        }
+        let file = File::open(self.content_path()).ok()?;
+        exif::Reader::new()
+            .read_from_container(&mut std::io::BufReader::new(file))
+            .ok()
+    }
+
+    fn gps_date(exif: &Exif) -> Option<NaiveDate> {
+        let date_str = exif_string(exif, Tag::GPSDateStamp)?;
+        NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&date_str, "%Y:%m:%d"))
+            .ok()
+    }
+
+    /// Everything `extract_datetime` could have chosen from, plus camera,
+    /// time zone, position and dimensions. Reads the file again.
+    pub fn media_facts(&self) -> MediaFacts {
+        self.facts_from(self.read_exif_data().as_ref())
+    }
+
+    /// `extract_datetime` and `media_facts` together, reading the metadata
+    /// once.
+    pub fn date_with_facts(&self) -> (anyhow::Result<(NaiveDateTime, DateSource)>, MediaFacts) {
+        let exif = self.read_exif_data();
+        let facts = self.facts_from(exif.as_ref());
+        let video_time = facts
+            .candidates
+            .iter()
+            .find(|candidate| candidate.source == DateSource::VideoCreationTime)
+            .map(|candidate| candidate.datetime);
+        (self.datetime_from(exif.as_ref(), || video_time), facts)
+    }
+
+    fn facts_from(&self, exif: Option<&Exif>) -> MediaFacts {
+        let mut facts = MediaFacts::default();
+        let mut found = |source: DateSource, datetime: NaiveDateTime| {
+            facts.candidates.push(DateCandidate {
+                source,
+                datetime,
+                plausible: Self::is_plausible_date(datetime.date()),
+            });
+        };
+
+        if let Some(exif) = exif {
+            for (tag, source) in DATETIME_TAGS {
+                if let Ok(datetime) = Self::extract_datetime_from_exif(exif, tag) {
+                    found(source, datetime);
+                }
+            }
+            if let Some(date) = Self::gps_date(exif) {
+                found(DateSource::ExifGpsDate, date.and_time(NaiveTime::MIN));
+            }
+        }
+        if self.is_video() {
+            if let Some(time) = self.video_time() {
+                found(DateSource::VideoCreationTime, time);
+            }
+        }
+        if let Some(date) = super::filename_date::date_from_filename(&self.source_filename) {
+            found(DateSource::Filename, date.and_time(NaiveTime::MIN));
+        }
+        if let Some(created) = self.dates.file_created {
+            found(DateSource::FileCreated, created);
+        }
+        if let Some(modified) = self.dates.file_modified {
+            found(DateSource::FileModified, modified);
+        }
+
+        if let Some(exif) = exif {
+            facts.offset = exif_string(exif, Tag::OffsetTimeOriginal);
+            facts.camera_make = exif_string(exif, Tag::Make);
+            facts.camera_model = exif_string(exif, Tag::Model);
+            facts.latitude = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+            facts.longitude = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+        }
+        let size = match &self.head {
+            Some(head) => imagesize::blob_size(head),
+            None => imagesize::size(self.content_path()),
+        };
+        if let Ok(size) = size {
+            facts.width = u32::try_from(size.width).ok();
+            facts.height = u32::try_from(size.height).ok();
+        }
+        facts
+    }
+
     /// Full fallback chain: EXIF tags first, then a date embedded in the
     /// filename (phone/messenger exports are often EXIF-stripped), then
     /// filesystem timestamps (earliest of creation/modified — on copied or
     /// recovered files the modified date often predates the creation date).
     pub fn extract_date(&self) -> anyhow::Result<(NaiveDate, DateSource)> {
-        if let Ok(result) = self.read_exif_date() {
+        self.extract_datetime()
+            .map(|(datetime, source)| (datetime.date(), source))
+    }
+
+    /// The fallback chain of `extract_date`, with the time of day where the
+    /// source has one (midnight otherwise). Whether the result is local or
+    /// UTC depends on the source, see `DateSource::is_utc`.
+    pub fn extract_datetime(&self) -> anyhow::Result<(NaiveDateTime, DateSource)> {
+        self.datetime_from(self.read_exif_data().as_ref(), || {
+            self.is_video().then(|| self.video_time()).flatten()
+        })
+    }
+
+    /// The fallback chain on metadata already read: the EXIF of the file, if
+    /// any, and its video creation time, asked for only when EXIF has no
+    /// date.
+    fn datetime_from(
+        &self,
+        exif: Option<&Exif>,
+        video_time: impl FnOnce() -> Option<NaiveDateTime>,
+    ) -> anyhow::Result<(NaiveDateTime, DateSource)> {
+        if let Some(result) = exif.and_then(|exif| self.exif_datetime(exif).ok()) {
             return Ok(result);
         }
 
-        if super::video::is_video_extension(&self.source_filetype) {
-            if let Some(date) = super::video::creation_date(Path::new(&self.source_full())) {
-                if Self::is_plausible_date(date) {
-                    return Ok((date, DateSource::VideoCreationTime));
-                }
+        if let Some(time) = video_time() {
+            if Self::is_plausible_date(time.date()) {
+                return Ok((time, DateSource::VideoCreationTime));
             }
         }
 
         if let Some(date) = super::filename_date::date_from_filename(&self.source_filename) {
-            return Ok((date, DateSource::Filename));
+            return Ok((date.and_time(NaiveTime::MIN), DateSource::Filename));
         }
 
-        let created = self.dates.file_creation_date.filter(|d| Self::is_plausible_date(*d));
-        let modified = self.dates.file_modified_date.filter(|d| Self::is_plausible_date(*d));
+        let created = self
+            .dates
+            .file_created
+            .filter(|t| Self::is_plausible_date(t.date()));
+        let modified = self
+            .dates
+            .file_modified
+            .filter(|t| Self::is_plausible_date(t.date()));
 
         match (created, modified) {
-            (Some(c), Some(m)) if m < c => Ok((m, DateSource::FileModified)),
+            (Some(c), Some(m)) if m.date() < c.date() => Ok((m, DateSource::FileModified)),
             (Some(c), _) => Ok((c, DateSource::FileCreated)),
             (None, Some(m)) => Ok((m, DateSource::FileModified)),
             (None, None) => bail!(AppError::NoDateFound()),
@@ -277,9 +504,9 @@ impl Image {
     pub fn extract_file_modified_date(&self) -> anyhow::Result<NaiveDate> {
         let file = File::open(self.source_full())?;
         let system_time = file.metadata()?.modified()?;
-        let duration_since_epoch = system_time
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| anyhow::anyhow!("File modification timestamp is before Unix epoch: {e}"))?;
+        let duration_since_epoch = system_time.duration_since(UNIX_EPOCH).map_err(|e| {
+            anyhow::anyhow!("File modification timestamp is before Unix epoch: {e}")
+        })?;
 
         let secs = duration_since_epoch.as_secs() as i64;
         let nanos = duration_since_epoch.subsec_nanos();
@@ -315,8 +542,34 @@ impl Image {
     }
 
     /// Transfer the file to its target configuration. Copy leaves the source
-    /// untouched (safe default for recovered media); Move removes it.
-    pub fn transfer_to_target(self, mode: TransferMode, dry_run: bool) -> anyhow::Result<()> {
+    /// untouched (safe default for recovered media); Move removes it;
+    /// Reflink, Hardlink and Symlink create a clone or link of the source
+    /// and never touch it. Copies and clones carry over the metadata
+    /// selected in `preserve`.
+    ///
+    /// Returns the checksum of the content when bytes were copied (copy,
+    /// cross-device move, reflink fallback) — `None` when nothing was
+    /// copied (rename, clone, link) or on dry runs.
+    pub fn transfer_to_target(
+        self,
+        mode: TransferMode,
+        preserve: Preserve,
+        dry_run: bool,
+    ) -> anyhow::Result<Option<String>> {
+        self.transfer_claiming(mode, preserve, dry_run, |_| {})
+    }
+
+    /// Like `transfer_to_target`, calling `on_start` with the target path
+    /// just before the empty claim file, clone or link is created, so that
+    /// whatever a crash leaves at the path is on record. Not called on dry
+    /// runs.
+    pub fn transfer_claiming(
+        self,
+        mode: TransferMode,
+        preserve: Preserve,
+        dry_run: bool,
+        on_start: impl FnOnce(&Path),
+    ) -> anyhow::Result<Option<String>> {
         if !self.target_dir.exists() {
             debug!("Create target dir {}", self.target_dir.to_string_lossy());
 
@@ -325,63 +578,238 @@ impl Image {
             }
         }
 
-        info!(
-            "{} {} to {}",
-            match mode {
-                TransferMode::Copy => "copy",
-                TransferMode::Move => "move",
-            },
-            self.source_full(),
-            self.target_full()
-        );
-        if !dry_run {
-            let source_str = self.source_full();
-            let target_str = self.target_full();
-            let source = Path::new(&source_str);
-            let target = Path::new(&target_str);
-
-            // Atomically claim the target path before writing. If another
-            // process created the file between set_target() and here (TOCTOU),
-            // create_new returns AlreadyExists and we abort rather than
-            // silently overwriting data that belongs to someone else.
-            fs::OpenOptions::new()
-                .write(true)
-                .create_new(true)
-                .open(target)
-                .map_err(|e| anyhow::anyhow!("Cannot create target {}: {e}", self.target_full()))?;
-
-            // Read source metadata before the transfer; needed to restore the
-            // modified time on the copy path.
-            let source_meta = fs::metadata(source);
-
-            // Fast path for moves: same-filesystem rename is instant and
-            // keeps all timestamps (mtime and, on APFS, birthtime). It
-            // replaces our claim file, which we own. Falls back to copy when
-            // source and target are on different devices.
-            if mode == TransferMode::Move && fs::rename(source, target).is_ok() {
-                return Ok(());
-            }
+        info!("{mode} {} to {}", self.source_full(), self.target_full());
+        if dry_run {
+            return Ok(None);
+        }
 
-            if let Err(e) = fs::copy(source, target) {
-                let _ = fs::remove_file(target); // remove the empty claim file
-                return Err(e.into());
+        let source_str = self.source_full();
+        let target_str = self.target_full();
+        let content = self.content_path();
+        let source = content.as_path();
+        let target = Path::new(&target_str);
+        on_start(target);
+
+        // Links are created atomically and fail with AlreadyExists instead
+        // of replacing an existing file, so they need no claim.
+        match mode {
+            TransferMode::Hardlink => {
+                fs::hard_link(source, target)
+                    .with_context(|| format!("Cannot hardlink {target_str}"))?;
+                return Ok(None);
+            }
+            TransferMode::Symlink => {
+                // Relative link text would resolve against the target
+                // directory, not the cwd; always link the absolute path.
+                let absolute = source.canonicalize()?;
+                symlink_file(&absolute, target)
+                    .with_context(|| format!("Cannot symlink {target_str}"))?;
+                return Ok(None);
             }
+            _ => {}
+        }
 
-            // Preserve the modified time: after a sort the file mtime is the
-            // last remaining date signal outside of EXIF (PhotoRec stamps the
-            // capture date into it), don't reset it to now.
-            if let Ok(meta) = source_meta {
-                let mtime = filetime::FileTime::from_last_modification_time(&meta);
-                if let Err(e) = filetime::set_file_mtime(target, mtime) {
-                    debug!("Could not preserve mtime on {}: {e}", self.target_full());
+        // Read source metadata before the transfer; needed to restore it on
+        // the copy and clone paths.
+        let source_meta = fs::metadata(source);
+
+        // Clones are created with O_EXCL as well. Anything but a name clash
+        // (unsupported filesystem, different devices) falls through to the
+        // regular copy below.
+        if mode == TransferMode::Reflink {
+            match reflink_copy::reflink(source, target) {
+                Ok(()) => {
+                    if let Ok(meta) = &source_meta {
+                        preserve::apply(source, meta, target, preserve);
+                    }
+                    return Ok(None);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    return Err(e).with_context(|| format!("Cannot create target {target_str}"));
+                }
+                Err(e) => {
+                    debug!("reflink not possible ({e}), copying {source_str} instead");
                 }
             }
+        }
+
+        // Atomically claim the target path before writing. If another
+        // process created the file between set_target() and here (TOCTOU),
+        // create_new returns AlreadyExists and we abort rather than silently
+        // overwriting data that belongs to someone else.
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(target)
+            .with_context(|| format!("Cannot create target {target_str}"))?;
+
+        // Fast path for moves: same-filesystem rename is instant and keeps
+        // all timestamps (mtime and, on APFS, birthtime). It replaces our
+        // claim file, which we own. Falls back to copy when source and
+        // target are on different devices.
+        if mode == TransferMode::Move && fs::rename(source, target).is_ok() {
+            return Ok(None);
+        }
 
-            if mode == TransferMode::Move {
-                fs::remove_file(source)?;
+        let checksum = match copy_verified(source, target, source_meta.as_ref().ok(), preserve) {
+            Ok(checksum) => checksum,
+            Err(e) => {
+                let _ = fs::remove_file(target); // remove the empty claim file
+                return Err(e);
             }
+        };
+
+        // Only now is the copy known to be complete and identical.
+        if mode == TransferMode::Move {
+            fs::remove_file(source)?;
         }
 
+        Ok(Some(checksum))
+    }
+}
+
+/// A text tag's value without the quotes and padding cameras add.
+fn exif_string(exif: &Exif, tag: Tag) -> Option<String> {
+    let value = exif
+        .get_field(tag, In::PRIMARY)?
+        .display_value()
+        .to_string()
+        .replace('"', "");
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Degrees, minutes and seconds of a GPS tag as decimal degrees, negative
+/// when its reference tag reads `negative` ("S" or "W").
+fn gps_coordinate(exif: &Exif, tag: Tag, reference: Tag, negative: &str) -> Option<f64> {
+    let exif::Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let [degrees, minutes, seconds] = parts.get(..3)? else {
+        return None;
+    };
+    let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+    if !value.is_finite() {
+        return None;
+    }
+    let sign = if exif_string(exif, reference).as_deref() == Some(negative) {
+        -1.0
+    } else {
+        1.0
+    };
+    Some(sign * value)
+}
+
+/// Name of the in-progress copy for `target`, next to it in the same
+/// directory (so the final rename never crosses a filesystem). Derived from
+/// the claimed target name, which makes it unique and lets a later run find
+/// and clean up the leftovers of an interrupted copy.
+pub fn partial_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{name}{PARTIAL_SUFFIX}"))
+}
+
+pub const PARTIAL_SUFFIX: &str = ".exif-sorter-part";
+
+/// Crash-safe copy onto an already claimed `target`: write to the partial
+/// name, fsync, read it back and compare checksums, then rename over the
+/// (empty) claim and fsync the directory. A crash or a full disk can only
+/// ever leave a partial file behind, never a truncated file at the final
+/// name. Metadata is applied to the partial file, so the final name only
+/// ever appears complete. Returns the verified checksum.
+fn copy_verified(
+    source: &Path,
+    target: &Path,
+    source_meta: Option<&fs::Metadata>,
+    preserve: Preserve,
+) -> anyhow::Result<String> {
+    let partial = partial_path(target);
+    let result = (|| -> anyhow::Result<String> {
+        let mut reader = File::open(source)?;
+        // Truncating is safe: the name is derived from our own claim.
+        let mut writer = File::create(&partial)?;
+        let source_checksum = super::checksum::copy_hashed(&mut reader, &mut writer)?;
+        writer.sync_all()?;
+        drop(writer);
+
+        let written_checksum = super::checksum::file_checksum(&partial)?;
+        if written_checksum != source_checksum {
+            bail!(AppError::VerificationFailed {
+                target: target.to_string_lossy().into_owned()
+            });
+        }
+
+        if let Some(meta) = source_meta {
+            preserve::apply(source, meta, &partial, preserve);
+        }
+        fs::rename(&partial, target)?;
+        sync_parent_dir(target);
+        Ok(source_checksum)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Move `source` to `target`, which must not exist yet, like a move in
+/// `Image::transfer_to_target`: a rename where possible, else a verified
+/// copy, which must also have the checksum `expected` when given, before
+/// `source` is removed. `revert` moves files back this way.
+pub fn move_verified(
+    source: &Path,
+    target: &Path,
+    expected: Option<&str>,
+    preserve: Preserve,
+) -> anyhow::Result<()> {
+    let target_str = target.to_string_lossy();
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .with_context(|| format!("Cannot create target {target_str}"))?;
+    if fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+    let source_meta = fs::metadata(source);
+    let copied = copy_verified(source, target, source_meta.as_ref().ok(), preserve);
+    let copied = copied.and_then(|checksum| {
+        if expected.is_some_and(|expected| expected != checksum) {
+            bail!(AppError::VerificationFailed {
+                target: target_str.clone().into_owned()
+            });
+        }
         Ok(())
+    });
+    if let Err(e) = copied {
+        // the claim file, or a copy that is not what was recorded
+        let _ = fs::remove_file(target);
+        return Err(e);
     }
+    fs::remove_file(source)?;
+    Ok(())
+}
+
+/// Persist the directory entry of a freshly renamed file. Best effort: not
+/// every platform can open a directory for syncing.
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Err(e) = File::open(dir).and_then(|dir| dir.sync_all()) {
+            debug!("Could not fsync {}: {e}", dir.to_string_lossy());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_file(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
 }
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{MergeSource, PlannedAction, ProcessOptions};
+
+pub const JOURNAL_FILENAME: &str = "exif-sorter-journal.jsonl";
+
+/// One line of the run journal. The first line is the plan, the lines
+/// after it its items in order, every further line marks progress on one
+/// item.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JournalRecord {
+    Plan {
+        /// Carried over so a resumed run keeps writing under the same id.
+        run_id: String,
+        sources: Vec<PathBuf>,
+        target: PathBuf,
+        /// Boxed: the plan is written once, progress lines many times.
+        options: Box<ProcessOptions>,
+    },
+    /// The next item of the plan.
+    Item { item: JournalItem },
+    /// The item's transfer to `target` is about to begin.
+    Started { index: usize, target: PathBuf },
+    /// The item is decided and its manifest row written.
+    Done { index: usize },
+}
+
+/// What `resume` needs to rebuild a planned item without dating it again.
+#[derive(Serialize, Deserialize)]
+pub struct JournalItem {
+    pub source: PathBuf,
+    pub source_root: PathBuf,
+    pub action: PlannedAction,
+    pub selected: bool,
+    #[serde(default)]
+    pub merge: Option<MergeSource>,
+}
+
+/// Journal of an `execute` run in `{target}/exif-sorter-journal.jsonl`:
+/// the plan, then one line per started and finished item. Lines are
+/// flushed as they are written, so a killed run leaves an exact record of
+/// how far it got. Removed once the run completes; its presence means a
+/// run was interrupted. Records nothing on dry runs. Shared by parallel
+/// executor workers; each line is written whole under a lock.
+pub struct JournalWriter {
+    path: PathBuf,
+    file: Option<Mutex<File>>,
+}
+
+impl JournalWriter {
+    /// Start the journal of a new run with its plan.
+    pub fn create(
+        target_dir: &Path,
+        run_id: &str,
+        sources: &[PathBuf],
+        options: &ProcessOptions,
+        items: Vec<JournalItem>,
+    ) -> anyhow::Result<Self> {
+        Self::create_streamed(
+            target_dir,
+            run_id,
+            sources,
+            options,
+            items.into_iter().map(Ok),
+        )
+    }
+
+    /// Like `create`, writing the items as `items` reads them, so they need
+    /// not fit in memory. No journal is left behind when reading fails.
+    pub fn create_streamed(
+        target_dir: &Path,
+        run_id: &str,
+        sources: &[PathBuf],
+        options: &ProcessOptions,
+        items: impl IntoIterator<Item = anyhow::Result<JournalItem>>,
+    ) -> anyhow::Result<Self> {
+        let path = target_dir.join(JOURNAL_FILENAME);
+        if options.dry_run {
+            return Ok(Self { path, file: None });
+        }
+        let file = File::create(&path)
+            .with_context(|| format!("cannot create journal {}", path.display()))?;
+        let mut file = BufWriter::new(file);
+        let written = (|| {
+            let record = JournalRecord::Plan {
+                run_id: run_id.to_string(),
+                sources: sources.to_vec(),
+                target: target_dir.to_path_buf(),
+                options: Box::new(options.clone()),
+            };
+            writeln!(file, "{}", serde_json::to_string(&record)?)?;
+            for item in items {
+                let record = JournalRecord::Item { item: item? };
+                writeln!(file, "{}", serde_json::to_string(&record)?)?;
+            }
+            let file = file.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            anyhow::Ok(file)
+        })();
+        match written {
+            Ok(file) => Ok(Self {
+                path,
+                file: Some(Mutex::new(file)),
+            }),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Continue the journal of an interrupted run.
+    pub fn reopen(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("cannot open journal {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    pub fn started(&self, index: usize, target: &Path) {
+        self.append(&JournalRecord::Started {
+            index,
+            target: target.to_path_buf(),
+        });
+    }
+
+    pub fn done(&self, index: usize) {
+        self.append(&JournalRecord::Done { index });
+    }
+
+    /// The run is complete: the manifest is the permanent record.
+    pub fn finish(self) {
+        if self.file.is_some() {
+            drop(self.file);
+            if let Err(e) = fs::remove_file(&self.path) {
+                warn!("could not remove journal '{}': {e}", self.path.display());
+            }
+        }
+    }
+
+    fn append(&self, record: &JournalRecord) {
+        if let Some(file) = &self.file {
+            // Like the manifest, a failed journal write must not abort the
+            // sort itself; at worst `resume` redoes an item.
+            let line = serde_json::to_string(record).unwrap_or_default();
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writeln!(file, "{line}");
+        }
+    }
+}
+
+/// Drop the journal a cancelled run left in `target_dir`, for callers that
+/// continue such a run by executing the same plan again instead of with
+/// `resume` (the review UIs).
+pub fn discard(target_dir: &Path) {
+    let path = target_dir.join(JOURNAL_FILENAME);
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("could not remove journal '{}': {e}", path.display());
+        }
+    }
+}
+
+/// An interrupted run as read back from its journal. Its items stay in
+/// the journal until `items` reads them.
+pub struct PendingRun {
+    pub run_id: String,
+    pub sources: Vec<PathBuf>,
+    pub target: PathBuf,
+    pub options: ProcessOptions,
+    /// Items already finished; skipped on resume.
+    pub done: BTreeSet<usize>,
+    /// Items whose transfer began but did not finish, with the exact
+    /// target path they were being written to.
+    pub started: BTreeMap<usize, PathBuf>,
+    path: PathBuf,
+}
+
+impl PendingRun {
+    /// The items of the plan, in plan order, read from the journal as they
+    /// are needed.
+    pub fn items(&self) -> anyhow::Result<impl Iterator<Item = anyhow::Result<JournalItem>>> {
+        let lines = journal_lines(&self.path)?.skip(1);
+        Ok(lines.filter_map(|line| match line {
+            Ok(line) => match serde_json::from_slice(&line) {
+                Ok(JournalRecord::Item { item }) => Some(Ok(item)),
+                _ => None,
+            },
+            Err(e) => Some(Err(e)),
+        }))
+    }
+}
+
+/// Read the journal at `path`, all but its items. A torn last line (the
+/// process died while writing it) is ignored.
+pub fn read_journal(path: &Path) -> anyhow::Result<PendingRun> {
+    let mut lines = journal_lines(path)?;
+    let header = lines.next().transpose()?.unwrap_or_default();
+    let Ok(JournalRecord::Plan {
+        run_id,
+        sources,
+        target,
+        options,
+    }) = serde_json::from_slice(&header)
+    else {
+        anyhow::bail!("journal {} has no plan", path.display());
+    };
+
+    let mut done = BTreeSet::new();
+    let mut started = BTreeMap::new();
+    for line in lines {
+        match serde_json::from_slice(&line?) {
+            Ok(JournalRecord::Item { .. }) => {}
+            Ok(JournalRecord::Started { index, target }) => {
+                started.insert(index, target);
+            }
+            Ok(JournalRecord::Done { index }) => {
+                started.remove(&index);
+                done.insert(index);
+            }
+            Ok(JournalRecord::Plan { .. }) | Err(_) => {
+                warn!("ignoring unreadable journal line in '{}'", path.display());
+            }
+        }
+    }
+
+    Ok(PendingRun {
+        run_id,
+        sources,
+        target,
+        options: *options,
+        done,
+        started,
+        path: path.to_path_buf(),
+    })
+}
+
+/// The lines of the journal at `path`, as bytes: a torn line need not be
+/// valid UTF-8.
+fn journal_lines(path: &Path) -> anyhow::Result<impl Iterator<Item = anyhow::Result<Vec<u8>>>> {
+    let file =
+        File::open(path).with_context(|| format!("cannot read journal {}", path.display()))?;
+    let path = path.to_path_buf();
+    Ok(BufReader::new(file)
+        .split(b'\n')
+        .map(move |line| line.with_context(|| format!("cannot read journal {}", path.display()))))
+}
@@ -0,0 +1,132 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use super::card::Volume;
+use super::catalog::{Catalog, ImportRecord};
+use super::checksum;
+
+/// A file of the source that an earlier run already imported, left out of
+/// the plan by `--incremental`.
+#[derive(Clone, Debug)]
+pub struct Imported {
+    pub path: PathBuf,
+    /// Where the library holds its content.
+    pub target: PathBuf,
+    /// The path it was imported from, when that was another one: the file
+    /// was renamed or moved since, and recognised by its content.
+    pub imported_as: Option<PathBuf>,
+    pub run_id: String,
+}
+
+/// The import ledger of a library, loaded from its catalog for planning.
+#[derive(Default)]
+pub struct Ledger {
+    by_source: HashMap<PathBuf, Vec<ImportRecord>>,
+    /// Imports from the card being planned, by path on the card.
+    by_volume_path: HashMap<PathBuf, Vec<ImportRecord>>,
+    /// That card, if the sources are on one.
+    volume: Option<Volume>,
+    by_content: HashMap<(u64, String), ImportRecord>,
+    /// Sizes some checksummed import has: only files of these sizes are
+    /// worth hashing to look for a renamed one.
+    checksummed_sizes: HashSet<u64>,
+}
+
+impl Ledger {
+    /// The ledger of the library in `target`; empty when it has no catalog
+    /// yet. Opens the catalog read-only, so planning writes nothing. With
+    /// `volume`, files on that card are also looked up by their path on it,
+    /// so the card is recognised wherever it is mounted.
+    pub fn load(target: &Path, volume: Option<&Volume>) -> anyhow::Result<Self> {
+        let mut ledger = Self {
+            volume: volume.cloned(),
+            ..Self::default()
+        };
+        let Some(catalog) = Catalog::open_read_only(target)? else {
+            return Ok(ledger);
+        };
+        for record in catalog.imports()? {
+            if let (Some(size), Some(checksum)) = (record.size, &record.checksum) {
+                ledger.checksummed_sizes.insert(size);
+                ledger
+                    .by_content
+                    .entry((size, checksum.clone()))
+                    .or_insert_with(|| record.clone());
+            }
+            if let (Some(uuid), Some(path)) = (&record.volume, &record.volume_path) {
+                if volume.is_some_and(|volume| &volume.uuid == uuid) {
+                    ledger
+                        .by_volume_path
+                        .entry(path.clone())
+                        .or_default()
+                        .push(record.clone());
+                }
+            }
+            ledger
+                .by_source
+                .entry(record.source.clone())
+                .or_default()
+                .push(record);
+        }
+        Ok(ledger)
+    }
+
+    /// The earlier import of the file at `path`: one from the same path
+    /// with the same size and modified time, else one with the same
+    /// content. Only files whose size matches a recorded checksum are read.
+    pub fn find(&self, path: &Path, metadata: &Metadata) -> Option<Imported> {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .map(|time| DateTime::<Utc>::from(time).timestamp());
+        if let Some(imported) = self.recorded(path, metadata.len(), mtime) {
+            return Some(imported);
+        }
+        if !self.checksummed_sizes.contains(&metadata.len()) {
+            return None;
+        }
+        let checksum = checksum::file_checksum(path).ok()?;
+        let record = self.by_content.get(&(metadata.len(), checksum))?;
+        let source = absolute(path);
+        Some(Imported {
+            path: path.to_path_buf(),
+            target: record.target.clone(),
+            imported_as: (record.source != source).then(|| record.source.clone()),
+            run_id: record.run_id.clone(),
+        })
+    }
+
+    /// The earlier import from `path` itself, if the file has not changed
+    /// since: the same size and modified time (in seconds). On the card,
+    /// imports from the same path on it count, wherever it was mounted.
+    pub fn recorded(&self, path: &Path, size: u64, mtime: Option<i64>) -> Option<Imported> {
+        let source = absolute(path);
+        let on_volume = self
+            .volume
+            .as_ref()
+            .and_then(|volume| source.strip_prefix(&volume.root).ok())
+            .and_then(|path| self.by_volume_path.get(path));
+        let record = self
+            .by_source
+            .get(&source)
+            .into_iter()
+            .chain(on_volume)
+            .flat_map(|records| records.iter().rev())
+            .find(|record| {
+                record.size == Some(size) && record.mtime.is_some() && record.mtime == mtime
+            })?;
+        Some(Imported {
+            path: path.to_path_buf(),
+            target: record.target.clone(),
+            imported_as: None,
+            run_id: record.run_id.clone(),
+        })
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,108 +1,426 @@
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
-use chrono::Utc;
+use anyhow::bail;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
+use rand::Rng as _;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::card::Volume;
+use super::catalog::{self, Catalog};
+use super::dates::DateCandidate;
+use super::image::{DateSource, MediaFacts};
+use super::CollisionPolicy;
+use crate::error::AppError;
+
+/// Catalog rows applied in one transaction.
+const CATALOG_BATCH: usize = 256;
 
 pub const MANIFEST_FILENAME: &str = "exif-sorter-manifest.csv";
-pub const MANIFEST_HEADER: &str = "timestamp,action,category,source,target,date,date_source,reason";
+pub const MANIFEST_JSONL_FILENAME: &str = "exif-sorter-manifest.jsonl";
+pub const MANIFEST_HEADER: &str =
+    "timestamp,action,category,source,target,date,date_source,reason,checksum,mtime_before,size,run_id";
+
+/// A fresh identifier for one run: its local start time plus a random
+/// suffix, e.g. `20240521-153012-4f2a`. Sorts chronologically.
+pub fn new_run_id() -> String {
+    let suffix: u16 = rand::thread_rng().gen();
+    format!("{}-{suffix:04x}", Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+/// How the manifest is written. Both formats are read back by
+/// `read_manifest`, told apart by the file extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ManifestFormat {
+    /// Twelve string columns; compact and spreadsheet friendly.
+    #[default]
+    Csv,
+    /// One JSON object per line with every field of `ManifestEntry`,
+    /// including the facts only this format records (candidate dates,
+    /// camera, time zone, …).
+    Jsonl,
+}
+
+impl ManifestFormat {
+    /// File name of the manifest in the target directory.
+    pub fn filename(self) -> &'static str {
+        match self {
+            ManifestFormat::Csv => MANIFEST_FILENAME,
+            ManifestFormat::Jsonl => MANIFEST_JSONL_FILENAME,
+        }
+    }
+
+    /// The format of the manifest at `path`: JSON Lines for `.jsonl`, CSV
+    /// otherwise.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl") => ManifestFormat::Jsonl,
+            _ => ManifestFormat::Csv,
+        }
+    }
+}
+
+/// Why a `failed` row failed, for tooling that groups failures.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    /// The target name was taken between planning and the transfer.
+    AlreadyExists,
+    StorageFull,
+    /// The copy read back differently from the source.
+    VerificationFailed,
+    Other,
+}
 
-/// Appends one CSV row per decision to `{target}/exif-sorter-manifest.csv` —
-/// the audit trail ("why is this photo in 2009?") and the input for `revert`.
-/// Opens the file lazily so runs that touch nothing leave nothing behind;
-/// records nothing on dry runs.
+impl ErrorKind {
+    /// Classify a transfer error by the first I/O or app error in its chain.
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(AppError::VerificationFailed { .. }) = cause.downcast_ref::<AppError>() {
+                return ErrorKind::VerificationFailed;
+            }
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                return match io.kind() {
+                    std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+                    std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+                    std::io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+                    std::io::ErrorKind::StorageFull => ErrorKind::StorageFull,
+                    _ => ErrorKind::Other,
+                };
+            }
+        }
+        ErrorKind::Other
+    }
+}
+
+/// Appends one row per decision to the target's manifest — the audit trail
+/// ("why is this photo in 2009?") and the input for `revert`. Opens the
+/// file lazily so runs that touch nothing leave nothing behind; records
+/// nothing on dry runs. Shared by parallel executor workers: each row is
+/// written whole under a lock. Every row carries the run's id and the
+/// target's size and modified time right after the decision, and is
+/// applied to the catalog next to the manifest (see `Catalog::apply`) in
+/// batches, one transaction each; the last batch when the writer is
+/// dropped.
 pub struct ManifestWriter {
     path: PathBuf,
+    format: ManifestFormat,
     dry_run: bool,
-    file: Option<File>,
+    run_id: String,
+    /// The card the run imports from, for the import ledger.
+    volume: Option<Volume>,
+    file: Mutex<Option<File>>,
+    /// Opened with the first row; `None` inside when that failed.
+    catalog: OnceLock<Option<Mutex<CatalogBatch>>>,
+}
+
+/// The catalog and the rows not applied to it yet.
+struct CatalogBatch {
+    catalog: Catalog,
+    pending: Vec<ManifestEntry>,
 }
 
-/// A single manifest row, as consumed by `revert`.
+/// A single manifest row, as written by `ManifestWriter` and consumed by
+/// `revert`. CSV manifests carry the first twelve fields only; the rest
+/// read back empty from them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ManifestEntry {
+    /// When the row was written.
+    pub timestamp: Option<DateTime<Utc>>,
     pub action: String,
+    /// sorted, unsorted or corrupt.
+    pub category: String,
     pub source: String,
     pub target: String,
+    /// Capture date; `None` outside `sorted`.
+    pub date: Option<NaiveDate>,
+    pub date_source: Option<DateSource>,
+    /// Why the file went to unsorted/corrupt, or why the transfer failed.
+    pub reason: String,
+    /// BLAKE3 checksum of copied content; empty for renames, links and
+    /// rows written before checksums were recorded.
+    pub checksum: String,
+    /// Modified time of the source before the run (before `--touch`
+    /// changed it); `None` for rows written before it was always recorded.
+    pub mtime_before: Option<DateTime<Utc>>,
+    /// Size of the target right after the row was written (of the link
+    /// itself for symlinks); `None` when it did not exist or for rows
+    /// written before sizes were recorded.
+    pub size: Option<u64>,
+    /// Empty for rows written before runs had ids.
+    pub run_id: String,
+    /// The capture time behind `date`, midnight for date-only sources.
+    pub datetime: Option<NaiveDateTime>,
+    /// "UTC" for video and file times, the EXIF offset (e.g. "+02:00") for
+    /// camera times that have one; `None` means camera-local.
+    pub time_zone: Option<String>,
+    /// Every date the file offered, whether it was used or not.
+    pub candidates: Vec<DateCandidate>,
+    /// Modified time of the target right after the row was written.
+    pub mtime_after: Option<DateTime<Utc>>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// Decimal degrees; south and west negative.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// The collision policy the run applied.
+    pub collision: Option<CollisionPolicy>,
+    /// For `duplicate` rows: the stored file with the same content.
+    pub duplicate_of: Option<String>,
+    /// For `failed` rows.
+    pub error_kind: Option<ErrorKind>,
+    /// For files merged in from another library: the source that library
+    /// recorded, where the file originally came from.
+    pub origin: Option<String>,
+    /// The source's extension, when the target got another one to match
+    /// its content; empty when the source had none.
+    pub original_extension: Option<String>,
+}
+
+impl ManifestEntry {
+    /// An entry carrying the facts read from the source's metadata.
+    pub fn with_facts(facts: MediaFacts) -> Self {
+        Self {
+            candidates: facts.candidates,
+            time_zone: facts.offset,
+            camera_make: facts.camera_make,
+            camera_model: facts.camera_model,
+            latitude: facts.latitude,
+            longitude: facts.longitude,
+            width: facts.width,
+            height: facts.height,
+            ..Self::default()
+        }
+    }
 }
 
 impl ManifestWriter {
-    pub fn new(target_dir: &Path, dry_run: bool) -> Self {
+    pub fn new(target_dir: &Path, format: ManifestFormat, dry_run: bool, run_id: &str) -> Self {
+        Self::at(target_dir.join(format.filename()), dry_run, run_id)
+    }
+
+    /// A manifest at `path` instead of the target's standard one, in the
+    /// format its extension implies.
+    pub fn at(path: PathBuf, dry_run: bool, run_id: &str) -> Self {
         Self {
-            path: target_dir.join(MANIFEST_FILENAME),
+            format: ManifestFormat::of(&path),
+            path,
             dry_run,
-            file: None,
+            run_id: run_id.to_string(),
+            volume: None,
+            file: Mutex::new(None),
+            catalog: OnceLock::new(),
         }
     }
 
+    /// Enter sources on `volume` in the import ledger by their path on it
+    /// too (see `card`).
+    pub fn on_volume(mut self, volume: Option<Volume>) -> Self {
+        self.volume = volume;
+        self
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn record(
-        &mut self,
-        action: &str,
-        category: &str,
-        source: &str,
-        target: &str,
-        date: &str,
-        date_source: &str,
-        reason: &str,
-    ) {
+    /// Whether rows want the sources' `MediaFacts`: JSON Lines rows carry
+    /// them, and the catalog indexes camera, position and dimensions.
+    pub fn wants_facts(&self) -> bool {
+        let no_catalog = matches!(self.catalog.get(), Some(None));
+        !self.dry_run && (self.format == ManifestFormat::Jsonl || !no_catalog)
+    }
+
+    /// Append `entry`, stamped with the time, the run id and the target's
+    /// current size and modified time.
+    pub fn record(&self, mut entry: ManifestEntry) {
         if self.dry_run {
             return;
         }
-        if self.file.is_none() {
-            self.file = open_appending(&self.path);
+        entry.timestamp = Some(Utc::now());
+        entry.run_id.clone_from(&self.run_id);
+        let target_meta = std::fs::symlink_metadata(&entry.target).ok();
+        entry.size = target_meta.as_ref().map(|meta| meta.len());
+        entry.mtime_after = target_meta
+            .and_then(|meta| meta.modified().ok())
+            .map(DateTime::from);
+
+        let row = match self.format {
+            ManifestFormat::Csv => csv_row(&entry),
+            ManifestFormat::Jsonl => match serde_json::to_string(&entry) {
+                Ok(line) => line,
+                Err(_) => return,
+            },
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.is_none() {
+            *file = open_appending(&self.path, self.format);
         }
-        if let Some(file) = &mut self.file {
-            let row = [
-                Utc::now().to_rfc3339().as_str(),
-                action,
-                category,
-                source,
-                target,
-                date,
-                date_source,
-                reason,
-            ]
-            .iter()
-            .map(|f| csv_escape(f))
-            .collect::<Vec<_>>()
-            .join(",");
+        if let Some(file) = file.as_mut() {
             // A failed manifest write must not abort the sort itself.
             let _ = writeln!(file, "{row}");
         }
+        drop(file);
+        self.update_catalog(entry);
     }
+
+    fn update_catalog(&self, entry: ManifestEntry) {
+        let batch = self.catalog.get_or_init(|| {
+            let dir = catalog::catalog_dir(&self.path);
+            match Catalog::open(&dir) {
+                Ok(catalog) => Some(Mutex::new(CatalogBatch {
+                    catalog,
+                    pending: Vec::new(),
+                })),
+                Err(e) => {
+                    warn!("catalog not updated: {e:#}");
+                    None
+                }
+            }
+        });
+        if let Some(batch) = batch {
+            let mut batch = batch.lock().unwrap_or_else(|e| e.into_inner());
+            batch.pending.push(entry);
+            if batch.pending.len() >= CATALOG_BATCH {
+                self.flush_catalog(&mut batch);
+            }
+        }
+    }
+
+    fn flush_catalog(&self, batch: &mut CatalogBatch) {
+        let pending = std::mem::take(&mut batch.pending);
+        // Like the manifest, the catalog must not abort the sort.
+        let applied = batch.catalog.in_transaction(|catalog| {
+            for entry in &pending {
+                if let Err(e) = catalog.apply(entry, self.volume.as_ref()) {
+                    warn!("catalog not updated for '{}': {e:#}", entry.target);
+                }
+            }
+        });
+        if let Err(e) = applied {
+            warn!("catalog not updated: {e:#}");
+        }
+    }
+}
+
+impl Drop for ManifestWriter {
+    fn drop(&mut self) {
+        if let Some(Some(batch)) = self.catalog.take() {
+            let mut batch = batch.into_inner().unwrap_or_else(|e| e.into_inner());
+            self.flush_catalog(&mut batch);
+        }
+    }
+}
+
+fn csv_row(entry: &ManifestEntry) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    [
+        optional(entry.timestamp.map(|time| time.to_rfc3339())),
+        entry.action.clone(),
+        entry.category.clone(),
+        entry.source.clone(),
+        entry.target.clone(),
+        optional(entry.date.map(|date| date.to_string())),
+        optional(entry.date_source.map(|source| source.to_string())),
+        entry.reason.clone(),
+        entry.checksum.clone(),
+        optional(entry.mtime_before.map(|time| time.to_rfc3339())),
+        optional(entry.size.map(|size| size.to_string())),
+        entry.run_id.clone(),
+    ]
+    .iter()
+    .map(|f| csv_escape(f))
+    .collect::<Vec<_>>()
+    .join(",")
 }
 
-fn open_appending(path: &Path) -> Option<File> {
-    let is_new = !path.exists();
+/// Fail when rows cannot be appended to the manifest at `path`: under the
+/// header of an existing CSV manifest from an older version (like the eight
+/// columns of 1.0.0), they would no longer line up.
+pub fn check_header(path: &Path) -> anyhow::Result<()> {
+    if ManifestFormat::of(path) == ManifestFormat::Csv && !header_is_current(path) {
+        bail!(AppError::OutdatedManifest {
+            manifest: path.to_string_lossy().into_owned()
+        });
+    }
+    Ok(())
+}
+
+/// Whether the CSV manifest at `path` is missing, empty or has the header
+/// `MANIFEST_HEADER`.
+fn header_is_current(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return true;
+    };
+    let mut header = String::new();
+    let _ = BufReader::new(file).read_line(&mut header);
+    header.is_empty() || header.trim_end() == MANIFEST_HEADER
+}
+
+fn open_appending(path: &Path, format: ManifestFormat) -> Option<File> {
+    if format == ManifestFormat::Csv && !header_is_current(path) {
+        warn!(
+            "not appending to '{}': its header is outdated",
+            path.display()
+        );
+        return None;
+    }
+    let is_new = std::fs::metadata(path).map_or(true, |meta| meta.len() == 0);
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
         .open(path)
         .ok()?;
-    if is_new {
+    if is_new && format == ManifestFormat::Csv {
         writeln!(file, "{MANIFEST_HEADER}").ok()?;
     }
     Some(file)
 }
 
-/// Read all entries of a manifest file (header skipped).
+/// Read all entries of a manifest file, CSV (header skipped) or JSON Lines
+/// by its extension. Unreadable JSON lines are skipped.
 pub fn read_manifest(path: &Path) -> anyhow::Result<Vec<ManifestEntry>> {
     let content = std::fs::read_to_string(path)?;
-    let entries = content
-        .lines()
+    let lines = content.lines().filter(|line| !line.trim().is_empty());
+    if ManifestFormat::of(path) == ManifestFormat::Jsonl {
+        return Ok(lines
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect());
+    }
+    let entries = lines
         .skip(1) // header
-        .filter(|line| !line.trim().is_empty())
         .filter_map(|line| {
             let fields = parse_csv_line(line);
             // timestamp,action,category,source,target,...
             if fields.len() >= 5 {
+                let field = |index: usize| fields.get(index).cloned().unwrap_or_default();
+                let time = |index: usize| {
+                    DateTime::parse_from_rfc3339(&field(index))
+                        .ok()
+                        .map(|time| time.with_timezone(&Utc))
+                };
                 Some(ManifestEntry {
-                    action: fields[1].clone(),
-                    source: fields[3].clone(),
-                    target: fields[4].clone(),
+                    timestamp: time(0),
+                    action: field(1),
+                    category: field(2),
+                    source: field(3),
+                    target: field(4),
+                    date: field(5).parse().ok(),
+                    date_source: field(6).parse().ok(),
+                    reason: field(7),
+                    checksum: field(8),
+                    mtime_before: time(9),
+                    size: field(10).parse().ok(),
+                    run_id: field(11),
+                    ..ManifestEntry::default()
                 })
             } else {
                 None
@@ -112,7 +430,7 @@ pub fn read_manifest(path: &Path) -> anyhow::Result<Vec<ManifestEntry>> {
     Ok(entries)
 }
 
-fn csv_escape(field: &str) -> String {
+pub fn csv_escape(field: &str) -> String {
     if field.contains([',', '"', '\n']) {
         format!("\"{}\"", field.replace('"', "\"\""))
     } else {
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use tracing::warn;
+
+use super::checksum::file_checksum;
+use super::dir::scan_dir;
+use super::image::Image;
+use super::manifest::ManifestEntry;
+use super::verify::{normalize, Library};
+use super::{
+    collides_with_stored, correct_extension, decide_action, planned_target, space, MergeSource,
+    Plan, PlannedAction, PlannedItem, ProcessOptions,
+};
+
+/// Plan merging the sorted library in `library` into the one in `target`.
+/// The library is a source that is already dated: each file it holds is
+/// planned with the date, capture time and date source its manifest
+/// recorded, and unsorted and corrupt files keep their category, so the
+/// fallback chain does not run again. Files it holds without a manifest
+/// row are dated like any source file.
+///
+/// Content `target` already stores, or that comes earlier in the merge, is
+/// planned as a duplicate of that file whatever its name or folder; name
+/// clashes between different files follow `options.collision` when the
+/// plan is executed. Every item keeps the source the library recorded,
+/// which ends up in the `origin` of its manifest row.
+pub fn plan_merge(library: &Path, target: &Path, options: &ProcessOptions) -> anyhow::Result<Plan> {
+    let library = Library::open(library)?;
+    if target
+        .canonicalize()
+        .is_ok_and(|target| target == library.root)
+    {
+        bail!("cannot merge '{}' into itself", library.root.display());
+    }
+    let mut stored = StoredContent::of(target)?;
+
+    let mut files: Vec<(PathBuf, Option<&ManifestEntry>)> = Vec::new();
+    for (path, entry) in &library.held {
+        if path.is_file() {
+            files.push((path.clone(), Some(entry)));
+        } else {
+            warn!("merge: '{}' is recorded but gone", path.display());
+        }
+    }
+    let held: HashSet<&PathBuf> = library.held.keys().collect();
+    for entry in scan_dir(&library.root)? {
+        let path = normalize(entry.path());
+        if !held.contains(&path) {
+            files.push((path, None));
+        }
+    }
+
+    let mut items = Vec::with_capacity(files.len());
+    for (path, entry) in files {
+        let mut image = Image::new(path.clone(), target.to_path_buf());
+        correct_extension(&mut image, options.fix_extensions);
+        let action = entry
+            .and_then(recorded_action)
+            .unwrap_or_else(|| decide_action(&image));
+        let planned_target = planned_target(&image, &action, target, &options.pattern);
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        let checksum = match entry.filter(|entry| !entry.checksum.is_empty()) {
+            Some(entry) => entry.checksum.clone(),
+            None => file_checksum(&path)?,
+        };
+        let duplicate_of = stored.find(&checksum, size)?;
+        if duplicate_of.is_none() {
+            stored.add(planned_target.clone(), size, checksum);
+        }
+        let already_stored = duplicate_of.is_some()
+            || collides_with_stored(&action, &path, &planned_target, options.collision);
+        items.push(PlannedItem {
+            image,
+            action,
+            planned_target,
+            selected: true,
+            size,
+            already_stored,
+            source_root: library.root.clone(),
+            merge: Some(MergeSource {
+                origin: entry.map(|entry| entry.source.clone()),
+                duplicate_of,
+            }),
+        });
+    }
+
+    Ok(Plan {
+        sources: vec![library.root.clone()],
+        target: target.to_path_buf(),
+        items,
+        excluded: Vec::new(),
+        imported: Vec::new(),
+        scan_errors: Vec::new(),
+        available_space: space::available_space(target),
+        same_filesystem: space::same_filesystem(&library.root, target),
+    })
+}
+
+/// The routing a manifest row recorded; `None` for rows that lack what a
+/// sorted item needs.
+fn recorded_action(entry: &ManifestEntry) -> Option<PlannedAction> {
+    match entry.category.as_str() {
+        "sorted" => {
+            let date = entry.date?;
+            Some(PlannedAction::Sorted {
+                date,
+                datetime: entry
+                    .datetime
+                    .unwrap_or_else(|| date.and_time(chrono::NaiveTime::MIN)),
+                date_source: entry.date_source?,
+            })
+        }
+        "unsorted" => Some(PlannedAction::Unsorted {
+            reason: entry.reason.clone(),
+        }),
+        "corrupt" => Some(PlannedAction::Corrupt {
+            reason: entry.reason.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Content held by the target, found by size, then checksum. Checksums
+/// come from the target's manifests where recorded and are computed (once)
+/// for the other files of the same size.
+struct StoredContent {
+    by_size: HashMap<u64, Vec<PathBuf>>,
+    checksums: HashMap<PathBuf, String>,
+}
+
+impl StoredContent {
+    fn of(target: &Path) -> anyhow::Result<Self> {
+        let mut stored = Self {
+            by_size: HashMap::new(),
+            checksums: HashMap::new(),
+        };
+        if !target.is_dir() {
+            return Ok(stored);
+        }
+        let library = Library::open(target)?;
+        for (path, entry) in library.held {
+            if !entry.checksum.is_empty() {
+                stored.checksums.insert(path, entry.checksum);
+            }
+        }
+        for entry in scan_dir(&library.root)? {
+            let path = normalize(entry.path());
+            if let Ok(meta) = fs::metadata(&path) {
+                stored.by_size.entry(meta.len()).or_default().push(path);
+            }
+        }
+        Ok(stored)
+    }
+
+    fn find(&mut self, checksum: &str, size: u64) -> anyhow::Result<Option<PathBuf>> {
+        for path in self.by_size.get(&size).into_iter().flatten() {
+            let known = match self.checksums.get(path) {
+                Some(known) => known.clone(),
+                None => {
+                    let computed = file_checksum(path)?;
+                    self.checksums.insert(path.clone(), computed.clone());
+                    computed
+                }
+            };
+            if known == checksum {
+                return Ok(Some(path.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Register content the merge will store at `path`. A path already
+    /// taken gets a suffix when executed, so its final name is unknown and
+    /// the content is not registered.
+    fn add(&mut self, path: PathBuf, size: u64, checksum: String) {
+        if path.exists() || self.checksums.contains_key(&path) {
+            return;
+        }
+        self.by_size.entry(size).or_default().push(path.clone());
+        self.checksums.insert(path, checksum);
+    }
+}
@@ -1,36 +1,124 @@
+pub mod archive;
+pub mod card;
+pub mod catalog;
+pub mod checksum;
 pub mod config;
+pub mod control;
 pub mod dates;
 pub mod dir;
+pub mod explain;
 pub mod filename_date;
 pub mod image;
+pub mod journal;
+pub mod ledger;
 pub mod manifest;
+pub mod merge;
+pub mod preserve;
+pub mod relayout;
+pub mod revert;
+pub mod runs;
+pub mod space;
+pub mod spill;
+pub mod verify;
 pub mod video;
+pub mod watch;
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
 
 use anyhow::bail;
-use chrono::NaiveDate;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone as _, Utc};
 use rand::Rng as _;
 use rayon::prelude::*;
 use tracing::{debug, warn};
 
 use crate::error::AppError;
-use dir::scan_dir;
+use control::RunControl;
+use dir::{scan, Excluded, Scan, ScanError, ScanOptions};
 use image::{DateSource, Image};
-use manifest::ManifestWriter;
+use journal::{JournalItem, JournalWriter, JOURNAL_FILENAME};
+use ledger::{Imported, Ledger};
+use manifest::{ErrorKind, ManifestEntry, ManifestFormat, ManifestWriter};
+pub use revert::{revert, RevertFilter, RevertSummary};
+use serde::{Deserialize, Serialize};
+use space::SpaceCheck;
+use spill::SpilledPlan;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum TransferMode {
     /// Leave the source untouched — the safe default for recovered media.
     Copy,
     /// Remove the source after a successful transfer.
     Move,
+    /// Copy-on-write clone (Btrfs, XFS, ZFS, APFS): instant and takes no
+    /// extra space until either side changes. Falls back to a plain copy
+    /// when the filesystem cannot clone or source and target differ.
+    Reflink,
+    /// Hard link to the source: a space-free sorted view of a read-only
+    /// archive. Source and target must share a filesystem; the two names
+    /// share one inode, so editing either edits both.
+    Hardlink,
+    /// Symbolic link to the (absolute) source path, for browsable indexes.
+    /// Breaks when the source is moved or deleted.
+    Symlink,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+impl TransferMode {
+    /// Manifest action for a completed transfer; `revert` keys off it.
+    pub fn action(self) -> &'static str {
+        match self {
+            TransferMode::Copy => "copied",
+            TransferMode::Move => "moved",
+            TransferMode::Reflink => "reflinked",
+            TransferMode::Hardlink => "hardlinked",
+            TransferMode::Symlink => "symlinked",
+        }
+    }
+
+    /// The mode behind a manifest action; `None` for rows that transferred
+    /// nothing.
+    pub fn from_action(action: &str) -> Option<Self> {
+        [
+            TransferMode::Copy,
+            TransferMode::Move,
+            TransferMode::Reflink,
+            TransferMode::Hardlink,
+            TransferMode::Symlink,
+        ]
+        .into_iter()
+        .find(|mode| mode.action() == action)
+    }
+
+    /// Capitalized past tense for run summaries ("Copied 3 of 4 …").
+    pub fn past_tense(self) -> &'static str {
+        match self {
+            TransferMode::Copy => "Copied",
+            TransferMode::Move => "Moved",
+            TransferMode::Reflink => "Reflinked",
+            TransferMode::Hardlink => "Hardlinked",
+            TransferMode::Symlink => "Symlinked",
+        }
+    }
+}
+
+impl std::fmt::Display for TransferMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TransferMode::Copy => "copy",
+            TransferMode::Move => "move",
+            TransferMode::Reflink => "reflink",
+            TransferMode::Hardlink => "hardlink",
+            TransferMode::Symlink => "symlink",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
 pub enum CollisionPolicy {
     /// Append a random suffix and store both files.
     #[default]
@@ -42,7 +130,31 @@ pub enum CollisionPolicy {
     Dedupe,
 }
 
-#[derive(Clone)]
+/// Whether `execute` sets a sorted file's modified (and, where possible,
+/// birth) time to the capture time it was sorted by, so tools that sort by
+/// mtime agree with the folder layout.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum TouchPolicy {
+    /// Timestamps stay as transferred (see `ProcessOptions::preserve`).
+    #[default]
+    Off,
+    /// Only for dates from metadata (EXIF, GPS, video, filename).
+    HighConfidence,
+    /// Also for dates that came from file timestamps.
+    All,
+}
+
+impl TouchPolicy {
+    fn applies_to(self, date_source: DateSource) -> bool {
+        match self {
+            TouchPolicy::Off => false,
+            TouchPolicy::HighConfidence => !date_source.is_low_confidence(),
+            TouchPolicy::All => true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProcessOptions {
     /// Log what would happen without touching any file.
     pub dry_run: bool,
@@ -50,6 +162,33 @@ pub struct ProcessOptions {
     pub collision: CollisionPolicy,
     /// Folder layout below the target (see `config::render_pattern`).
     pub pattern: String,
+    /// Source metadata carried over to copies.
+    pub preserve: preserve::Preserve,
+    pub touch: TouchPolicy,
+    /// Items transferred in parallel; 1 executes strictly in plan order.
+    pub jobs: usize,
+    #[serde(default)]
+    pub manifest: ManifestFormat,
+    /// Which files of the source are taken.
+    #[serde(default)]
+    pub scan: ScanOptions,
+    /// Give targets the extension matching their content when it differs
+    /// from the source's; the manifest keeps the old one.
+    #[serde(default)]
+    pub fix_extensions: bool,
+    /// Leave out of the plan what the target's import ledger says an
+    /// earlier run already imported.
+    #[serde(default)]
+    pub incremental: bool,
+    /// The camera card the sources are on: its imports are also recorded
+    /// by volume UUID and path on the card, which recognises them wherever
+    /// the card is mounted.
+    #[serde(default)]
+    pub volume: Option<card::Volume>,
+    /// Cancel or pause the run from another thread. Not part of the
+    /// journal: a resumed run gets a fresh one.
+    #[serde(skip)]
+    pub control: RunControl,
 }
 
 impl Default for ProcessOptions {
@@ -59,6 +198,15 @@ impl Default for ProcessOptions {
             mode: TransferMode::Copy,
             collision: CollisionPolicy::Suffix,
             pattern: config::DEFAULT_PATTERN.to_string(),
+            preserve: preserve::Preserve::default(),
+            touch: TouchPolicy::Off,
+            jobs: 1,
+            manifest: ManifestFormat::Csv,
+            scan: ScanOptions::default(),
+            fix_extensions: false,
+            incremental: false,
+            volume: None,
+            control: RunControl::default(),
         }
     }
 }
@@ -70,10 +218,12 @@ impl Default for ProcessOptions {
 /// - otherwise the content signature decides: unrecognizable bytes are
 ///   carved garbage whose file dates mean nothing → corrupt/
 /// - recognizable content without any date → unsorted/
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum PlannedAction {
     Sorted {
         date: NaiveDate,
+        /// The capture time behind `date`; midnight for date-only sources.
+        datetime: NaiveDateTime,
         date_source: DateSource,
     },
     Unsorted {
@@ -94,14 +244,103 @@ pub struct PlannedItem {
     /// Frontends toggle this in the review step; deselected items are
     /// skipped entirely by `execute`.
     pub selected: bool,
+    /// Size of the source in bytes.
+    pub size: u64,
+    /// The target already holds this file (a collision that will be
+    /// skipped, or an identical duplicate), so it takes no space.
+    pub already_stored: bool,
+    /// The source directory (or archive) of the plan the file was found
+    /// in.
+    pub source_root: PathBuf,
+    /// Set for files merged in from another library; see `merge`.
+    pub merge: Option<MergeSource>,
+}
+
+/// Where a merged file comes from, kept with the planned item (and in the
+/// journal) so its manifest row carries it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergeSource {
+    /// The source the other library recorded for the file; `None` for
+    /// files it holds without a manifest row.
+    pub origin: Option<String>,
+    /// A file with the same content the target already holds (or will, from
+    /// earlier in the merge); the item is recorded as its duplicate.
+    pub duplicate_of: Option<PathBuf>,
 }
 
 /// Result of the read-only planning phase.
 #[derive(Clone)]
 pub struct Plan {
-    pub source: PathBuf,
+    pub sources: Vec<PathBuf>,
     pub target: PathBuf,
     pub items: Vec<PlannedItem>,
+    /// What the scan rules left out of the source, and why.
+    pub excluded: Vec<Excluded>,
+    /// Files an earlier run already imported (`incremental`); not dated.
+    pub imported: Vec<Imported>,
+    /// Paths of the source that could not be read; the plan does not
+    /// cover what lies below them.
+    pub scan_errors: Vec<ScanError>,
+    /// Free space on the target filesystem at planning time.
+    pub available_space: Option<u64>,
+    /// Every source shares the target's filesystem: moves are renames.
+    pub same_filesystem: bool,
+}
+
+impl Plan {
+    /// Bytes the selected items will take up on the target in `mode`.
+    /// Links, same-filesystem moves and files the target already holds need
+    /// none; clones are counted in full since reflink support is only known
+    /// once one is attempted.
+    pub fn bytes_needed(&self, mode: TransferMode) -> u64 {
+        if !self.takes_space(mode) {
+            return 0;
+        }
+        self.items
+            .iter()
+            .filter(|item| item.selected && !item.already_stored)
+            .map(|item| item.size)
+            .sum()
+    }
+
+    /// Whether transfers in `mode` take up space on the target.
+    fn takes_space(&self, mode: TransferMode) -> bool {
+        match mode {
+            TransferMode::Hardlink | TransferMode::Symlink => false,
+            TransferMode::Move => !self.same_filesystem,
+            _ => true,
+        }
+    }
+
+    /// Pre-flight check of the selection against the target's free space.
+    pub fn space_check(&self, mode: TransferMode) -> SpaceCheck {
+        SpaceCheck {
+            needed: self.bytes_needed(mode),
+            available: self.available_space,
+        }
+    }
+
+    /// `space_check` for a plan whose items are in `spilled`, reading them
+    /// one at a time.
+    pub fn spilled_space_check(
+        &self,
+        mode: TransferMode,
+        spilled: &SpilledPlan,
+    ) -> anyhow::Result<SpaceCheck> {
+        let mut needed = 0;
+        if self.takes_space(mode) {
+            for item in spilled.items()? {
+                let item = item?;
+                if item.selected && !item.already_stored {
+                    needed += item.size;
+                }
+            }
+        }
+        Ok(SpaceCheck {
+            needed,
+            available: self.available_space,
+        })
+    }
 }
 
 /// What `execute` did with a single planned item.
@@ -136,9 +375,27 @@ pub struct ProcessSummary {
     pub corrupt: usize,
     /// Transfer failed; file left at the source.
     pub failed: Vec<(String, String)>,
+    /// Id of the run in the manifest; see `revert`'s run filter.
+    pub run_id: String,
+    /// The run was cancelled before every item was reached. The journal is
+    /// kept, so `resume` can process the rest.
+    pub cancelled: bool,
+    /// Paths of the source the scan could not read (see `Plan::scan_errors`);
+    /// not known to `resume`.
+    pub scan_errors: usize,
 }
 
 impl ProcessSummary {
+    fn merge(&mut self, other: ProcessSummary) {
+        self.transferred += other.transferred;
+        self.low_confidence += other.low_confidence;
+        self.duplicates += other.duplicates;
+        self.collisions_skipped += other.collisions_skipped;
+        self.unsorted += other.unsorted;
+        self.corrupt += other.corrupt;
+        self.failed.extend(other.failed);
+    }
+
     pub fn total(&self) -> usize {
         self.transferred
             + self.duplicates
@@ -153,7 +410,8 @@ impl ProcessSummary {
 /// rayon — EXIF parsing dominates the runtime) and decide where each one
 /// would go. **Writes nothing** — the target directory is not even created.
 /// This is the safety property review UIs rely on: nothing is touched until
-/// the user confirms the plan with `execute`.
+/// the user confirms the plan with `execute`. Fails with
+/// `AppError::Cancelled` when `options.control` is cancelled.
 ///
 /// `on_progress` is `Fn + Sync` (unlike `process`'s `FnMut`) because it is
 /// called from inside the parallel iterator.
@@ -163,20 +421,73 @@ pub fn plan(
     options: &ProcessOptions,
     on_progress: impl Fn(usize, usize) + Sync,
 ) -> anyhow::Result<Plan> {
-    if !source.exists() {
-        bail!(AppError::InvalidSource {
-            expected: source.to_string_lossy().into_owned()
-        });
-    }
+    plan_sources(&[source.to_path_buf()], target, options, on_progress)
+}
+
+/// `plan` for several sources at once, so collisions and duplicates between
+/// them are handled like those within one. Every item keeps the source it
+/// was found in. A file reached through more than one source (one source
+/// inside another) is planned once, for the first.
+pub fn plan_sources(
+    sources: &[PathBuf],
+    target: &Path,
+    options: &ProcessOptions,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> anyhow::Result<Plan> {
+    plan_claimed(sources, target, options, |_| true, on_progress)
+}
 
-    let mut entries = scan_dir(source)?;
+/// `plan_sources` for the files (and archives) of the sources that `claim`
+/// accepts; it is asked once per path the scan takes and the ledger does
+/// not know, before any is dated.
+fn plan_claimed(
+    sources: &[PathBuf],
+    target: &Path,
+    options: &ProcessOptions,
+    mut claim: impl FnMut(&Path) -> bool,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> anyhow::Result<Plan> {
+    check_sources(sources)?;
+
+    let mut entries: Vec<(&PathBuf, ignore::DirEntry)> = Vec::new();
+    let mut archives: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut excluded = Vec::new();
+    let mut scan_errors = Vec::new();
+    let mut seen = HashSet::new();
+    for source in sources {
+        // An archive given as the source is planned like a directory.
+        if source.is_file() && archive::is_archive(source) {
+            if seen.insert(source.canonicalize()?) {
+                archives.push((source.clone(), source.clone()));
+            }
+            continue;
+        }
+        let found = scan(source, &options.scan)?;
+        for entry in found.files {
+            if seen.insert(
+                entry
+                    .path()
+                    .canonicalize()
+                    .unwrap_or_else(|_| entry.path().to_path_buf()),
+            ) {
+                entries.push((source, entry));
+            }
+        }
+        for path in found.archives {
+            if seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())) {
+                archives.push((source.clone(), path));
+            }
+        }
+        excluded.extend(found.excluded);
+        scan_errors.extend(found.errors);
+    }
 
     // Never re-sort files already inside the target tree — with the default
     // arguments the target directory lives inside the source directory.
     // A target that does not exist yet trivially contains no entries (and
     // must not be created here: planning is read-only).
     if let Ok(target_canon) = target.canonicalize() {
-        entries.retain(|entry| {
+        entries.retain(|(_, entry)| {
             entry
                 .path()
                 .canonicalize()
@@ -185,59 +496,320 @@ pub fn plan(
         });
     }
 
-    let total = entries.len();
+    // What the ledger knows is left out before anything is claimed.
+    let ledger = load_ledger(target, options)?;
+    let (mut imported, mut entries): (Vec<Imported>, Vec<_>) =
+        entries.into_par_iter().partition_map(|(source, entry)| {
+            let metadata = fs::metadata(entry.path()).ok();
+            match metadata.as_ref().and_then(|m| ledger.find(entry.path(), m)) {
+                Some(imported) => rayon::iter::Either::Left(imported),
+                None => {
+                    let size = metadata.map(|m| m.len()).unwrap_or(0);
+                    rayon::iter::Either::Right((source, entry, size))
+                }
+            }
+        });
+    entries.retain(|(_, entry, _)| claim(entry.path()));
+    archives.retain(|(_, archive)| claim(archive));
+
+    let total = entries.len() + archives.len();
     let counter = AtomicUsize::new(0);
 
-    let items: Vec<PlannedItem> = entries
+    let control = &options.control;
+    let mut items: Vec<PlannedItem> = entries
         .into_par_iter()
-        .map(|entry| {
+        .filter_map(|(source, entry, size)| {
+            if !control.checkpoint() {
+                return None;
+            }
             let image = Image::new(entry.into_path(), target.to_path_buf());
-            let action = decide_action(&image);
-            let planned_target = match &action {
-                PlannedAction::Sorted { date, .. } => image
-                    .target_dir_for(*date, &options.pattern)
-                    .join(image.target_filename()),
-                PlannedAction::Unsorted { .. } => {
-                    target.join("unsorted").join(image.target_filename())
-                }
-                PlannedAction::Corrupt { .. } => {
-                    target.join("corrupt").join(image.target_filename())
-                }
-            };
+            let item = plan_item(image, size, source, target, options);
             let done = counter.fetch_add(1, Ordering::Relaxed) + 1;
             on_progress(done, total);
-            PlannedItem {
-                image,
-                action,
-                planned_target,
-                selected: true,
-            }
+            Some(item)
         })
         .collect();
+    // Archives are read one after the other, after the plain files.
+    if !control.is_cancelled() {
+        let members = archive::plan_archives(
+            &archives,
+            target,
+            options,
+            &ledger,
+            &mut imported,
+            &mut scan_errors,
+            || {
+                let done = counter.fetch_add(1, Ordering::Relaxed) + 1;
+                on_progress(done, total);
+            },
+        );
+        match members {
+            Ok(members) => items.extend(members),
+            Err(_) if control.is_cancelled() => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if control.is_cancelled() {
+        bail!(AppError::Cancelled());
+    }
 
     Ok(Plan {
-        source: source.to_path_buf(),
+        sources: sources.to_vec(),
         target: target.to_path_buf(),
         items,
+        excluded,
+        imported,
+        scan_errors,
+        available_space: space::available_space(target),
+        same_filesystem: sources
+            .iter()
+            .all(|source| space::same_filesystem(source, target)),
+    })
+}
+
+/// `plan_sources` for trees too large to hold in memory: every file is
+/// dated as soon as the walk finds it, and its item goes to `on_item` right
+/// away, on the walker's threads and in no particular order (archives come
+/// last). The returned plan has everything but the items. `on_progress`
+/// gets the number of items so far, as the total is only known at the end.
+pub fn plan_streaming(
+    sources: &[PathBuf],
+    target: &Path,
+    options: &ProcessOptions,
+    on_item: impl Fn(PlannedItem) + Sync,
+    on_progress: impl Fn(usize) + Sync,
+) -> anyhow::Result<Plan> {
+    check_sources(sources)?;
+    let ledger = load_ledger(target, options)?;
+    let target_canon = target.canonicalize().ok();
+    // Only several sources can reach a file twice; one needs no record.
+    let seen = (sources.len() > 1).then(|| Mutex::new(HashSet::new()));
+    let first_time = |path: &Path| match &seen {
+        Some(seen) => seen
+            .lock()
+            .expect("mutex poisoned")
+            .insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())),
+        None => true,
+    };
+    let imported = Mutex::new(Vec::new());
+    let counter = AtomicUsize::new(0);
+    let control = &options.control;
+    let emit = |item: PlannedItem| {
+        on_item(item);
+        on_progress(counter.fetch_add(1, Ordering::Relaxed) + 1);
+    };
+
+    let mut archives: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut excluded = Vec::new();
+    let mut scan_errors = Vec::new();
+    for source in sources {
+        if source.is_file() && archive::is_archive(source) {
+            if first_time(source) {
+                archives.push((source.clone(), source.clone()));
+            }
+            continue;
+        }
+        let walked = dir::walk(source, &options.scan, |entry| {
+            if !control.checkpoint() {
+                return false;
+            }
+            // As in `plan_claimed`: never what the target already holds.
+            let in_target = target_canon.as_ref().is_some_and(|target| {
+                entry
+                    .path()
+                    .canonicalize()
+                    .is_ok_and(|path| path.starts_with(target))
+            });
+            if in_target || !first_time(entry.path()) {
+                return true;
+            }
+            let metadata = fs::metadata(entry.path()).ok();
+            if let Some(found) = metadata.as_ref().and_then(|m| ledger.find(entry.path(), m)) {
+                imported.lock().expect("mutex poisoned").push(found);
+                return true;
+            }
+            let size = metadata.map(|m| m.len()).unwrap_or(0);
+            let image = Image::new(entry.into_path(), target.to_path_buf());
+            emit(plan_item(image, size, source, target, options));
+            true
+        })?;
+        for path in walked.archives {
+            if first_time(&path) {
+                archives.push((source.clone(), path));
+            }
+        }
+        excluded.extend(walked.excluded);
+        scan_errors.extend(walked.errors);
+    }
+
+    let mut imported = imported.into_inner().expect("mutex poisoned");
+    for archive in &archives {
+        if control.is_cancelled() {
+            break;
+        }
+        let members = archive::plan_archives(
+            std::slice::from_ref(archive),
+            target,
+            options,
+            &ledger,
+            &mut imported,
+            &mut scan_errors,
+            || {},
+        );
+        match members {
+            Ok(members) => members.into_iter().for_each(&emit),
+            Err(_) if control.is_cancelled() => {}
+            Err(e) => return Err(e),
+        }
+    }
+    if control.is_cancelled() {
+        bail!(AppError::Cancelled());
+    }
+
+    Ok(Plan {
+        sources: sources.to_vec(),
+        target: target.to_path_buf(),
+        items: Vec::new(),
+        excluded,
+        imported,
+        scan_errors,
+        available_space: space::available_space(target),
+        same_filesystem: sources
+            .iter()
+            .all(|source| space::same_filesystem(source, target)),
     })
 }
 
+fn check_sources(sources: &[PathBuf]) -> anyhow::Result<()> {
+    if let Some(missing) = sources.iter().find(|source| !source.exists()) {
+        bail!(AppError::InvalidSource {
+            expected: missing.to_string_lossy().into_owned()
+        });
+    }
+    Ok(())
+}
+
+/// The target's import ledger with `incremental`, else an empty one.
+fn load_ledger(target: &Path, options: &ProcessOptions) -> anyhow::Result<Ledger> {
+    if options.incremental {
+        Ledger::load(target, options.volume.as_ref())
+    } else {
+        Ok(Ledger::default())
+    }
+}
+
+/// Plan one file of `source_root`: its target extension, the routing
+/// decision and its plain target.
+fn plan_item(
+    mut image: Image,
+    size: u64,
+    source_root: &Path,
+    target: &Path,
+    options: &ProcessOptions,
+) -> PlannedItem {
+    correct_extension(&mut image, options.fix_extensions);
+    // The manifest and catalog rows of a real run carry the facts; reading
+    // them along with the date spares `execute` a second read.
+    let action = if options.dry_run {
+        decide_action(&image)
+    } else {
+        let (dated, facts) = image.date_with_facts();
+        image.facts = Some(facts);
+        route(&image, dated)
+    };
+    let planned_target = planned_target(&image, &action, target, &options.pattern);
+    let already_stored = collides_with_stored(
+        &action,
+        &image.content_path(),
+        &planned_target,
+        options.collision,
+    );
+    PlannedItem {
+        image,
+        action,
+        planned_target,
+        selected: true,
+        size,
+        already_stored,
+        source_root: source_root.to_path_buf(),
+        merge: None,
+    }
+}
+
+/// Whether the collision policy will leave a sorted file out because the
+/// target already holds its plain target path.
+fn collides_with_stored(
+    action: &PlannedAction,
+    source: &Path,
+    planned_target: &Path,
+    collision: CollisionPolicy,
+) -> bool {
+    matches!(action, PlannedAction::Sorted { .. })
+        && planned_target.exists()
+        && match collision {
+            CollisionPolicy::Suffix => false,
+            CollisionPolicy::Skip => true,
+            CollisionPolicy::Dedupe => files_identical(source, planned_target).unwrap_or(false),
+        }
+}
+
+/// Give the target of `image` the extension its content calls for: always
+/// when the source has none, with `fix` also when the source's names
+/// another type.
+fn correct_extension(image: &mut Image, fix: bool) {
+    let source = &image.source_filetype;
+    if !source.is_empty() && !fix {
+        return;
+    }
+    if let Some(ext) = image.content_extension() {
+        if !dir::same_media_type(source, ext) {
+            image.target_filetype = ext.to_string();
+        }
+    }
+}
+
+/// The plain (unsuffixed) target path of a planned item.
+fn planned_target(image: &Image, action: &PlannedAction, target: &Path, pattern: &str) -> PathBuf {
+    match action {
+        PlannedAction::Sorted { date, .. } => image
+            .target_dir_for(*date, pattern)
+            .join(image.target_filename()),
+        PlannedAction::Unsorted { .. } => target.join("unsorted").join(image.target_filename()),
+        PlannedAction::Corrupt { .. } => target.join("corrupt").join(image.target_filename()),
+    }
+}
+
 fn decide_action(image: &Image) -> PlannedAction {
-    match image.extract_date() {
-        Ok((date, date_source)) if !date_source.is_low_confidence() => {
+    route(image, image.extract_datetime())
+}
+
+/// The routing decision for `image`, given what its dating found.
+fn route(image: &Image, dated: anyhow::Result<(NaiveDateTime, DateSource)>) -> PlannedAction {
+    match dated {
+        Ok((datetime, date_source)) if !date_source.is_low_confidence() => {
+            let date = datetime.date();
             debug!(
                 "File '{}' has date '{date}' from {date_source}",
                 image.source_full()
             );
-            PlannedAction::Sorted { date, date_source }
+            PlannedAction::Sorted {
+                date,
+                datetime,
+                date_source,
+            }
         }
-        Ok((date, date_source)) => {
-            if content_recognized(Path::new(&image.source_full())) {
+        Ok((datetime, date_source)) => {
+            let date = datetime.date();
+            if image_recognized(image) {
                 warn!(
                     "File '{}': no exif date, using {date_source} '{date}' (unreliable on recovered media)",
                     image.source_full()
                 );
-                PlannedAction::Sorted { date, date_source }
+                PlannedAction::Sorted {
+                    date,
+                    datetime,
+                    date_source,
+                }
             } else {
                 PlannedAction::Corrupt {
                     reason: "content not recognized as any known file type".to_string(),
@@ -245,7 +817,7 @@ fn decide_action(image: &Image) -> PlannedAction {
             }
         }
         Err(e) => {
-            if content_recognized(Path::new(&image.source_full())) {
+            if image_recognized(image) {
                 PlannedAction::Unsorted {
                     reason: format!("{e:#}"),
                 }
@@ -262,73 +834,488 @@ fn decide_action(image: &Image) -> PlannedAction {
 /// policy at execute time (files may have appeared between plan and
 /// execute), and append every decision to the manifest. `on_item` fires
 /// once per planned item, in order, with its outcome.
+///
+/// Progress is journaled in the target (see `journal`), so a run that is
+/// killed halfway can be continued with `resume`. Refuses to start while
+/// such an interrupted run is pending. Cancelling through
+/// `options.control` stops between items and leaves a journal the same way.
 pub fn execute(
+    mut plan: Plan,
+    options: &ProcessOptions,
+    on_item: impl FnMut(usize, &ItemOutcome),
+) -> anyhow::Result<ProcessSummary> {
+    let items = std::mem::take(&mut plan.items);
+    let journal_items: Vec<_> = items.iter().map(journal_item).map(Ok).collect();
+    let mut staging = archive::Staging::new(&plan.target, options);
+    for (index, item) in items.iter().enumerate() {
+        staging.plan(index, item, options);
+    }
+    let chunks = std::iter::once(Ok(items.into_iter().enumerate().collect()));
+    execute_chunks(plan, journal_items, chunks, staging, options, on_item)
+}
+
+/// Items of a spilled plan or a journal that `execute_spilled` and
+/// `resume` hold in memory at once.
+const EXECUTE_CHUNK: usize = 1024;
+
+/// Like `execute`, for a plan whose items are in `spilled` rather than in
+/// `plan.items`: they are journaled straight from the file, then read and
+/// executed `EXECUTE_CHUNK` at a time. Items aiming at the same target in
+/// different chunks run in plan order all the same, since chunks run one
+/// after the other.
+pub fn execute_spilled(
     plan: Plan,
+    spilled: &SpilledPlan,
     options: &ProcessOptions,
-    mut on_item: impl FnMut(usize, &ItemOutcome),
+    on_item: impl FnMut(usize, &ItemOutcome),
+) -> anyhow::Result<ProcessSummary> {
+    let journal_items = spilled
+        .items()?
+        .map(|item| item.map(|item| journal_item(&item)));
+    let mut staging = archive::Staging::new(&plan.target, options);
+    for (index, item) in spilled.items()?.enumerate() {
+        staging.plan(index, &item?, options);
+    }
+    let items = spilled
+        .items()?
+        .enumerate()
+        .map(|(index, item)| item.map(|item| (index, item)));
+    execute_chunks(
+        plan,
+        journal_items,
+        in_chunks(items),
+        staging,
+        options,
+        on_item,
+    )
+}
+
+/// `items`, `EXECUTE_CHUNK` at a time.
+fn in_chunks(
+    mut items: impl Iterator<Item = anyhow::Result<(usize, PlannedItem)>>,
+) -> impl Iterator<Item = anyhow::Result<Vec<(usize, PlannedItem)>>> {
+    std::iter::from_fn(move || {
+        let chunk = items
+            .by_ref()
+            .take(EXECUTE_CHUNK)
+            .collect::<anyhow::Result<Vec<_>>>();
+        match chunk {
+            Ok(chunk) if chunk.is_empty() => None,
+            chunk => Some(chunk),
+        }
+    })
+}
+
+/// What the journal keeps of `item`.
+fn journal_item(item: &PlannedItem) -> JournalItem {
+    JournalItem {
+        source: PathBuf::from(item.image.source_full()),
+        source_root: item.source_root.clone(),
+        action: item.action.clone(),
+        selected: item.selected,
+        merge: item.merge.clone(),
+    }
+}
+
+/// `execute` on the items of `plan` as `journal_items` and `chunks` list
+/// them, numbered in plan order.
+fn execute_chunks(
+    plan: Plan,
+    journal_items: impl IntoIterator<Item = anyhow::Result<JournalItem>>,
+    chunks: impl IntoIterator<Item = anyhow::Result<Vec<(usize, PlannedItem)>>>,
+    staging: archive::Staging,
+    options: &ProcessOptions,
+    on_item: impl FnMut(usize, &ItemOutcome),
 ) -> anyhow::Result<ProcessSummary> {
-    let Plan { target, items, .. } = plan;
+    let Plan {
+        sources,
+        target,
+        scan_errors,
+        ..
+    } = plan;
     fs::create_dir_all(&target)?;
 
-    let mut manifest = ManifestWriter::new(&target, options.dry_run);
-    let action_str = match options.mode {
-        TransferMode::Copy => "copied",
-        TransferMode::Move => "moved",
+    let journal_path = target.join(JOURNAL_FILENAME);
+    if !options.dry_run && journal_path.exists() {
+        bail!(AppError::InterruptedRun {
+            journal: journal_path.to_string_lossy().into_owned()
+        });
+    }
+    if !options.dry_run {
+        manifest::check_header(&target.join(options.manifest.filename()))?;
+    }
+    let run_id = manifest::new_run_id();
+    let journal =
+        JournalWriter::create_streamed(&target, &run_id, &sources, options, journal_items)?;
+    if !options.dry_run {
+        runs::record_started(&target, &run_id, &sources, options);
+    }
+
+    let summary = run_items(
+        chunks,
+        staging,
+        &target,
+        &run_id,
+        options,
+        journal,
+        &BTreeMap::new(),
+        on_item,
+    )?;
+    Ok(ProcessSummary {
+        scan_errors: scan_errors.len(),
+        ..summary
+    })
+}
+
+/// Continue the run that was interrupted in `target`, with the plan and
+/// options recorded in its journal: finished items are skipped, items
+/// caught mid-transfer are settled first (see `recover_item`), everything
+/// else is executed as usual. `on_item` fires for the remaining items only.
+/// The items are read back from the journal `EXECUTE_CHUNK` at a time.
+/// Returns the options of the original run along with the summary.
+/// `control` stands in for the original run's, which is not journaled.
+pub fn resume(
+    target: &Path,
+    control: &RunControl,
+    on_item: impl FnMut(usize, &ItemOutcome),
+) -> anyhow::Result<(ProcessOptions, ProcessSummary)> {
+    let journal_path = target.join(JOURNAL_FILENAME);
+    if !journal_path.exists() {
+        bail!("no interrupted run in '{}'", target.display());
+    }
+    let pending = journal::read_journal(&journal_path)?;
+    let options = ProcessOptions {
+        control: control.clone(),
+        ..pending.options.clone()
+    };
+    let base = &pending.target;
+    let items = || {
+        anyhow::Ok(pending.items()?.enumerate().map(|(index, item)| {
+            let item = item?;
+            let mut image = Image::new(item.source, base.clone());
+            correct_extension(&mut image, options.fix_extensions);
+            let planned_target = planned_target(&image, &item.action, base, &options.pattern);
+            let planned = PlannedItem {
+                image,
+                action: item.action,
+                planned_target,
+                selected: item.selected,
+                size: 0,
+                already_stored: false,
+                source_root: item.source_root,
+                merge: item.merge,
+            };
+            Ok((index, planned))
+        }))
     };
+    // Finished items are noted too, so that members sharing a name still
+    // line up with the archive.
+    let mut staging = archive::Staging::new(base, &options);
+    for item in items()? {
+        let (index, mut item) = item?;
+        item.selected &= !pending.done.contains(&index);
+        staging.plan(index, &item, &options);
+    }
+    let journal = JournalWriter::reopen(&journal_path)?;
+
+    let summary = run_items(
+        in_chunks(items()?.filter(|item| {
+            item.as_ref()
+                .map_or(true, |(index, _)| !pending.done.contains(index))
+        })),
+        staging,
+        base,
+        &pending.run_id,
+        &options,
+        journal,
+        &pending.started,
+        on_item,
+    )?;
+    Ok((options, summary))
+}
 
-    let mut summary = ProcessSummary::default();
-    for (index, item) in items.into_iter().enumerate() {
+/// Run `chunks` of items, each numbered by its place in the plan, one
+/// chunk after the other; `staging` extracts their archive members.
+#[allow(clippy::too_many_arguments)]
+fn run_items(
+    chunks: impl IntoIterator<Item = anyhow::Result<Vec<(usize, PlannedItem)>>>,
+    mut staging: archive::Staging,
+    target: &Path,
+    run_id: &str,
+    options: &ProcessOptions,
+    journal: JournalWriter,
+    started: &BTreeMap<usize, PathBuf>,
+    mut on_item: impl FnMut(usize, &ItemOutcome),
+) -> anyhow::Result<ProcessSummary> {
+    let manifest = ManifestWriter::new(target, options.manifest, options.dry_run, run_id)
+        .on_volume(options.volume.clone());
+    let action_str = options.mode.action();
+
+    // One item, start to finish; its counts go into `summary`.
+    let run_one = |index: usize, item: PlannedItem, summary: &mut ProcessSummary| {
         let outcome = if item.selected {
-            execute_item(item, &target, options, action_str, &mut manifest, &mut summary)
+            let recovered = started.get(&index).and_then(|claimed| {
+                recover_item(&item, claimed, options, action_str, &manifest, summary)
+            });
+            match recovered {
+                Some(outcome) => outcome,
+                None => execute_item(
+                    item,
+                    target,
+                    options,
+                    action_str,
+                    &manifest,
+                    summary,
+                    |claimed| journal.started(index, claimed),
+                ),
+            }
         } else {
             ItemOutcome::SkippedByUser
         };
-        on_item(index, &outcome);
+        journal.done(index);
+        outcome
+    };
+
+    let control = &options.control;
+    let mut summary = ProcessSummary {
+        run_id: run_id.to_string(),
+        ..ProcessSummary::default()
+    };
+    let pool = if options.jobs > 1 {
+        Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(options.jobs)
+                .build()?,
+        )
+    } else {
+        None
+    };
+    for chunk in chunks {
+        let mut items = chunk?;
+        staging.stage(&mut items, options)?;
+        match &pool {
+            None => {
+                for (index, item) in items {
+                    if !control.checkpoint() {
+                        summary.cancelled = true;
+                        break;
+                    }
+                    let outcome = run_one(index, item, &mut summary);
+                    on_item(index, &outcome);
+                }
+            }
+            Some(pool) => {
+                // Items aiming at the same plain target run in plan order on
+                // one worker, so suffixing and dedupe see each other's
+                // results exactly as in a sequential run. The key is
+                // case-folded because two names differing only in case are
+                // the same file on macOS and Windows.
+                let mut groups: Vec<Vec<(usize, PlannedItem)>> = Vec::new();
+                let mut group_of: HashMap<String, usize> = HashMap::new();
+                for (index, item) in items {
+                    let key = item.planned_target.to_string_lossy().to_lowercase();
+                    let group = *group_of.entry(key).or_insert_with(|| {
+                        groups.push(Vec::new());
+                        groups.len() - 1
+                    });
+                    groups[group].push((index, item));
+                }
+
+                let (tx, rx) = mpsc::channel();
+                let stopped_early = AtomicBool::new(false);
+                pool.in_place_scope(|scope| {
+                    for group in groups {
+                        let tx = tx.clone();
+                        let run_one = &run_one;
+                        let stopped_early = &stopped_early;
+                        scope.spawn(move |_| {
+                            for (index, item) in group {
+                                if !control.checkpoint() {
+                                    stopped_early.store(true, Ordering::Relaxed);
+                                    return;
+                                }
+                                let mut item_summary = ProcessSummary::default();
+                                let outcome = run_one(index, item, &mut item_summary);
+                                let _ = tx.send((index, outcome, item_summary));
+                            }
+                        });
+                    }
+                    drop(tx);
+                    // Outcomes arrive in completion order; each carries its
+                    // index.
+                    for (index, outcome, item_summary) in rx {
+                        summary.merge(item_summary);
+                        on_item(index, &outcome);
+                    }
+                });
+                summary.cancelled = stopped_early.into_inner();
+            }
+        }
+        if summary.cancelled {
+            break;
+        }
+    }
+    // A cancelled run stopped between items; its journal stays for `resume`.
+    if !summary.cancelled {
+        journal.finish();
+    }
+    if !options.dry_run {
+        runs::record_finished(target, run_id, &summary);
     }
 
     Ok(summary)
 }
 
+/// Settle an item whose transfer to `claimed` was interrupted. While the
+/// source still exists, whatever reached the target (an empty claim, a
+/// verified copy not yet journaled, a link) is rolled back together with
+/// any partial file, and `None` lets the item run again. Only a file with
+/// the source's content (or the empty claim) is removed: anything else is
+/// left alone and the item picks a fresh name. A missing source means a
+/// move completed before it was journaled: it is only recorded.
+fn recover_item(
+    item: &PlannedItem,
+    claimed: &Path,
+    options: &ProcessOptions,
+    action_str: &str,
+    manifest: &ManifestWriter,
+    summary: &mut ProcessSummary,
+) -> Option<ItemOutcome> {
+    let _ = fs::remove_file(image::partial_path(claimed));
+    let source_str = item.image.source_full();
+    let claimed_str = claimed.to_string_lossy().into_owned();
+
+    if archive::source_exists(Path::new(&source_str)) {
+        if fs::symlink_metadata(claimed).is_ok() {
+            // execute_item picks a fresh name next to whatever stays
+            if is_own_transfer(&item.image.content_path(), claimed) {
+                debug!("resume: rolling back interrupted transfer to '{claimed_str}'");
+                if let Err(e) = fs::remove_file(claimed) {
+                    warn!("resume: could not remove '{claimed_str}': {e}");
+                }
+            } else {
+                warn!("resume: '{claimed_str}' is not a copy of '{source_str}', leaving it alone");
+            }
+        }
+        return None;
+    }
+
+    let base = ManifestEntry {
+        origin: item.merge.as_ref().and_then(|merge| merge.origin.clone()),
+        ..manifest_base(&item.image, &item.action, options, manifest)
+    };
+    if !claimed.exists() {
+        let reason = "source and target both missing after the interrupted run".to_string();
+        warn!("resume: '{source_str}': {reason}");
+        summary.failed.push((source_str.clone(), reason.clone()));
+        manifest.record(ManifestEntry {
+            action: "failed".to_string(),
+            target: claimed_str,
+            reason: reason.clone(),
+            error_kind: Some(ErrorKind::NotFound),
+            ..base
+        });
+        return Some(ItemOutcome::Failed { reason });
+    }
+
+    debug!("resume: '{source_str}' already moved to '{claimed_str}'");
+    manifest.record(ManifestEntry {
+        action: action_str.to_string(),
+        target: claimed_str.clone(),
+        checksum: checksum::file_checksum(claimed).unwrap_or_default(),
+        ..base
+    });
+    Some(match &item.action {
+        PlannedAction::Sorted {
+            datetime,
+            date_source,
+            ..
+        } => {
+            // The move finished but its touch may not have.
+            if options.touch.applies_to(*date_source) {
+                let capture_time = capture_instant(*datetime, *date_source);
+                if let Err(e) = preserve::set_capture_time(claimed, capture_time) {
+                    warn!("Could not set capture time on '{claimed_str}': {e}");
+                }
+            }
+            summary.transferred += 1;
+            let low_confidence = date_source.is_low_confidence();
+            if low_confidence {
+                summary.low_confidence += 1;
+            }
+            ItemOutcome::Transferred {
+                target: claimed_str,
+                low_confidence,
+            }
+        }
+        PlannedAction::Unsorted { .. } => {
+            summary.unsorted += 1;
+            ItemOutcome::Unsorted
+        }
+        PlannedAction::Corrupt { .. } => {
+            summary.corrupt += 1;
+            ItemOutcome::Corrupt
+        }
+    })
+}
+
 fn execute_item(
     item: PlannedItem,
     target: &Path,
     options: &ProcessOptions,
     action_str: &str,
-    manifest: &mut ManifestWriter,
+    manifest: &ManifestWriter,
     summary: &mut ProcessSummary,
+    on_start: impl FnOnce(&Path),
 ) -> ItemOutcome {
+    let base = ManifestEntry {
+        origin: item.merge.as_ref().and_then(|merge| merge.origin.clone()),
+        ..manifest_base(&item.image, &item.action, options, manifest)
+    };
+    if let Some(stored) = item.merge.and_then(|merge| merge.duplicate_of) {
+        summary.duplicates += 1;
+        let stored = stored.to_string_lossy().into_owned();
+        manifest.record(ManifestEntry {
+            action: "duplicate".to_string(),
+            target: stored.clone(),
+            reason: "identical content already stored".to_string(),
+            duplicate_of: Some(stored),
+            ..base
+        });
+        return ItemOutcome::Duplicate;
+    }
     let mut image = item.image;
     match item.action {
-        PlannedAction::Sorted { date, date_source } => {
+        PlannedAction::Sorted {
+            date,
+            datetime,
+            date_source,
+        } => {
             // Collision handling on the plain (unsuffixed) target path.
             let plain_path = &item.planned_target;
             match options.collision {
                 CollisionPolicy::Skip if plain_path.exists() => {
                     summary.collisions_skipped += 1;
-                    manifest.record(
-                        "collision_skipped",
-                        "sorted",
-                        &image.source_full(),
-                        &plain_path.to_string_lossy(),
-                        &date.to_string(),
-                        &date_source.to_string(),
-                        "target exists, --on-collision skip",
-                    );
+                    manifest.record(ManifestEntry {
+                        action: "collision_skipped".to_string(),
+                        target: plain_path.to_string_lossy().into_owned(),
+                        reason: "target exists, --on-collision skip".to_string(),
+                        ..base
+                    });
                     return ItemOutcome::CollisionSkipped;
                 }
                 CollisionPolicy::Dedupe if plain_path.exists() => {
-                    match files_identical(Path::new(&image.source_full()), plain_path) {
+                    match files_identical(&image.content_path(), plain_path) {
                         Ok(true) => {
                             summary.duplicates += 1;
-                            manifest.record(
-                                "duplicate",
-                                "sorted",
-                                &image.source_full(),
-                                &plain_path.to_string_lossy(),
-                                &date.to_string(),
-                                &date_source.to_string(),
-                                "identical content already stored",
-                            );
+                            let plain_str = plain_path.to_string_lossy().into_owned();
+                            manifest.record(ManifestEntry {
+                                action: "duplicate".to_string(),
+                                target: plain_str.clone(),
+                                reason: "identical content already stored".to_string(),
+                                duplicate_of: Some(plain_str),
+                                ..base
+                            });
                             return ItemOutcome::Duplicate;
                         }
                         Ok(false) => {} // different content → suffix below
@@ -348,22 +1335,36 @@ fn execute_item(
                     image.target_filename = target_filename;
                     let source_str = image.source_full();
                     let target_str = image.target_full();
-                    match image.transfer_to_target(options.mode, options.dry_run) {
-                        Ok(()) => {
+                    // Links share the source's inode (or resolve to it), so
+                    // touching them would rewrite the source's timestamps.
+                    let touch = options.touch.applies_to(date_source)
+                        && (image.is_archive_member()
+                            || !matches!(
+                                options.mode,
+                                TransferMode::Hardlink | TransferMode::Symlink
+                            ));
+                    let (action_str, result) = transfer(image, options, action_str, on_start);
+                    match result {
+                        Ok(checksum) => {
                             summary.transferred += 1;
                             let low_confidence = date_source.is_low_confidence();
                             if low_confidence {
                                 summary.low_confidence += 1;
                             }
-                            manifest.record(
-                                action_str,
-                                "sorted",
-                                &source_str,
-                                &target_str,
-                                &date.to_string(),
-                                &date_source.to_string(),
-                                "",
-                            );
+                            if touch && !options.dry_run {
+                                let capture_time = capture_instant(datetime, date_source);
+                                if let Err(e) =
+                                    preserve::set_capture_time(Path::new(&target_str), capture_time)
+                                {
+                                    warn!("Could not set capture time on '{target_str}': {e}");
+                                }
+                            }
+                            manifest.record(ManifestEntry {
+                                action: action_str.to_string(),
+                                target: target_str.clone(),
+                                checksum: checksum.unwrap_or_default(),
+                                ..base
+                            });
                             ItemOutcome::Transferred {
                                 target: target_str,
                                 low_confidence,
@@ -373,15 +1374,13 @@ fn execute_item(
                             let reason = format!("{e:#}");
                             warn!("Failed to transfer '{source_str}': {reason}");
                             summary.failed.push((source_str.clone(), reason.clone()));
-                            manifest.record(
-                                "failed",
-                                "sorted",
-                                &source_str,
-                                &target_str,
-                                &date.to_string(),
-                                &date_source.to_string(),
-                                &reason,
-                            );
+                            manifest.record(ManifestEntry {
+                                action: "failed".to_string(),
+                                target: target_str,
+                                reason: reason.clone(),
+                                error_kind: Some(ErrorKind::of(&e)),
+                                ..base
+                            });
                             ItemOutcome::Failed { reason }
                         }
                     }
@@ -414,15 +1413,20 @@ fn execute_item(
             );
             let source_str = image.source_full();
             let target_str = image.target_full();
-            match image.transfer_to_target(options.mode, options.dry_run) {
-                Ok(()) => {
+            let (action_str, result) = transfer(image, options, action_str, on_start);
+            match result {
+                Ok(checksum) => {
+                    manifest.record(ManifestEntry {
+                        action: action_str.to_string(),
+                        target: target_str,
+                        checksum: checksum.unwrap_or_default(),
+                        ..base
+                    });
                     if category == "unsorted" {
                         summary.unsorted += 1;
-                        manifest.record(action_str, category, &source_str, &target_str, "", "", &reason);
                         ItemOutcome::Unsorted
                     } else {
                         summary.corrupt += 1;
-                        manifest.record(action_str, category, &source_str, &target_str, "", "", &reason);
                         ItemOutcome::Corrupt
                     }
                 }
@@ -430,15 +1434,13 @@ fn execute_item(
                     let reason = format!("{e:#}");
                     warn!("Failed to transfer '{source_str}': {reason}");
                     summary.failed.push((source_str.clone(), reason.clone()));
-                    manifest.record(
-                        "failed",
-                        category,
-                        &source_str,
-                        &target_str,
-                        "",
-                        "",
-                        &reason,
-                    );
+                    manifest.record(ManifestEntry {
+                        action: "failed".to_string(),
+                        target: target_str,
+                        reason: reason.clone(),
+                        error_kind: Some(ErrorKind::of(&e)),
+                        ..base
+                    });
                     ItemOutcome::Failed { reason }
                 }
             }
@@ -446,6 +1448,96 @@ fn execute_item(
     }
 }
 
+/// Transfer `image` as `options` say. Archive members, extracted before the
+/// run (see `archive::stage`), are moved into place from there and recorded
+/// as copies with the checksum of what was stored: their archive keeps them.
+/// `on_start` sees the target before it is claimed (see
+/// `Image::transfer_claiming`). Returns the manifest action along with the
+/// result.
+fn transfer<'a>(
+    image: Image,
+    options: &ProcessOptions,
+    action_str: &'a str,
+    on_start: impl FnOnce(&Path),
+) -> (&'a str, anyhow::Result<Option<String>>) {
+    if !image.is_archive_member() {
+        return (
+            action_str,
+            image.transfer_claiming(options.mode, options.preserve, options.dry_run, on_start),
+        );
+    }
+    let target = image.target_full();
+    let result = image
+        .transfer_claiming(
+            TransferMode::Move,
+            options.preserve,
+            options.dry_run,
+            on_start,
+        )
+        .and_then(|checksum| match checksum {
+            None if !options.dry_run => Ok(Some(checksum::file_checksum(Path::new(&target))?)),
+            checksum => Ok(checksum),
+        });
+    (TransferMode::Copy.action(), result)
+}
+
+/// The manifest fields every row about `image` shares: source, category,
+/// the dating decision, the collision policy, the source's modified time
+/// before anything touched it and, where `manifest` wants them, its
+/// `MediaFacts` — as read by planning, or read now for items planned
+/// without them.
+fn manifest_base(
+    image: &Image,
+    action: &PlannedAction,
+    options: &ProcessOptions,
+    manifest: &ManifestWriter,
+) -> ManifestEntry {
+    let mut entry = if manifest.wants_facts() {
+        let facts = image.facts.clone().unwrap_or_else(|| image.media_facts());
+        ManifestEntry::with_facts(facts)
+    } else {
+        ManifestEntry::default()
+    };
+    entry.source = image.source_full();
+    if image.target_filetype != image.source_filetype {
+        entry.original_extension = Some(image.source_filetype.clone());
+    }
+    entry.collision = Some(options.collision);
+    entry.mtime_before = fs::metadata(image.content_path())
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::from);
+    match action {
+        PlannedAction::Sorted {
+            date,
+            datetime,
+            date_source,
+        } => {
+            entry.category = "sorted".to_string();
+            entry.date = Some(*date);
+            entry.datetime = Some(*datetime);
+            entry.date_source = Some(*date_source);
+            // The EXIF offset only applies to EXIF times.
+            if date_source.is_utc() {
+                entry.time_zone = Some("UTC".to_string());
+            } else if *date_source == DateSource::Filename {
+                entry.time_zone = None;
+            }
+        }
+        PlannedAction::Unsorted { reason } => {
+            entry.category = "unsorted".to_string();
+            entry.reason.clone_from(reason);
+            entry.time_zone = None;
+        }
+        PlannedAction::Corrupt { reason } => {
+            entry.category = "corrupt".to_string();
+            entry.reason.clone_from(reason);
+            entry.time_zone = None;
+        }
+    }
+    entry
+}
+
 /// One-shot convenience used by the CLI: plan, then execute everything.
 /// `on_progress` is called after each processed file with (done, total).
 pub fn process(
@@ -459,6 +1551,22 @@ pub fn process(
     execute(plan, options, |index, _| on_progress(index + 1, total))
 }
 
+/// The instant a resolved capture time stands for: EXIF and filename times
+/// are the camera's wall clock and read as local time, video and file
+/// timestamps are UTC.
+fn capture_instant(datetime: NaiveDateTime, date_source: DateSource) -> std::time::SystemTime {
+    if date_source.is_utc() {
+        Utc.from_utc_datetime(&datetime).into()
+    } else {
+        Local
+            .from_local_datetime(&datetime)
+            .earliest()
+            .map(Into::into)
+            // a wall-clock time skipped by a DST switch: close enough as UTC
+            .unwrap_or_else(|| Utc.from_utc_datetime(&datetime).into())
+    }
+}
+
 /// True when the first bytes carry any known file signature. Carved files
 /// (PhotoRec output) frequently have an image extension but garbage content.
 /// Deliberately conservative: only signature-less content is called corrupt.
@@ -472,6 +1580,14 @@ fn content_recognized(path: &Path) -> bool {
     infer::get(&buf[..n]).is_some()
 }
 
+/// `content_recognized` for the content of `image`.
+fn image_recognized(image: &Image) -> bool {
+    match &image.head {
+        Some(head) => infer::get(head).is_some(),
+        None => content_recognized(&image.content_path()),
+    }
+}
+
 /// Byte-compare two files (size first, then streaming chunks).
 fn files_identical(a: &Path, b: &Path) -> std::io::Result<bool> {
     if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
@@ -493,6 +1609,25 @@ fn files_identical(a: &Path, b: &Path) -> std::io::Result<bool> {
     }
 }
 
+/// Whether `claimed` holds what an interrupted transfer of `source` left
+/// there: the empty claim file, or the same size and checksum (a complete
+/// copy, a clone or a link resolving to the source).
+fn is_own_transfer(source: &Path, claimed: &Path) -> bool {
+    let (Ok(source_meta), Ok(claimed_meta)) = (fs::metadata(source), fs::metadata(claimed)) else {
+        return false;
+    };
+    if claimed_meta.len() == 0 {
+        return true;
+    }
+    if claimed_meta.len() != source_meta.len() {
+        return false;
+    }
+    matches!(
+        (checksum::file_checksum(source), checksum::file_checksum(claimed)),
+        (Ok(a), Ok(b)) if a == b
+    )
+}
+
 /// Pick a filename that does not exist in `dir`, appending a random suffix
 /// on collision (same scheme as `Image::set_target`).
 fn unique_name_in(dir: &Path, stem: &str, ext: &str) -> String {
@@ -503,59 +1638,3 @@ fn unique_name_in(dir: &Path, stem: &str, ext: &str) -> String {
     }
     name
 }
-
-/// Undo a previous run from its manifest: copied files are deleted from the
-/// target (only while the source still exists — never the last copy), moved
-/// files are moved back. Returns (reverted, skipped).
-pub fn revert(manifest_path: &Path, dry_run: bool) -> anyhow::Result<(usize, usize)> {
-    let entries = manifest::read_manifest(manifest_path)?;
-    let mut reverted = 0;
-    let mut skipped = 0;
-
-    for entry in entries.iter().rev() {
-        let target = Path::new(&entry.target);
-        let source = Path::new(&entry.source);
-        match entry.action.as_str() {
-            "copied" => {
-                if target.exists() && source.exists() {
-                    debug!("revert: remove copy {}", entry.target);
-                    if !dry_run {
-                        fs::remove_file(target)?;
-                    }
-                    reverted += 1;
-                } else {
-                    warn!(
-                        "revert: skipping '{}' (copy or original missing)",
-                        entry.target
-                    );
-                    skipped += 1;
-                }
-            }
-            "moved" => {
-                if target.exists() && !source.exists() {
-                    debug!("revert: move {} back to {}", entry.target, entry.source);
-                    if !dry_run {
-                        if let Some(parent) = source.parent() {
-                            fs::create_dir_all(parent)?;
-                        }
-                        if fs::rename(target, source).is_err() {
-                            fs::copy(target, source)?;
-                            fs::remove_file(target)?;
-                        }
-                    }
-                    reverted += 1;
-                } else {
-                    warn!(
-                        "revert: skipping '{}' (already restored or target missing)",
-                        entry.target
-                    );
-                    skipped += 1;
-                }
-            }
-            // duplicate / collision_skipped / failed touched nothing
-            _ => {}
-        }
-    }
-
-    Ok((reverted, skipped))
-}
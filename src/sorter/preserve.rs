@@ -0,0 +1,168 @@
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Which source metadata a copy carries over (see `transfer_to_target`).
+/// Renames and links need none of this: they keep the source's inode.
+///
+/// ```toml
+/// preserve = ["times", "mode", "xattrs"]
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Preserve {
+    /// Modified and access time, plus birth time where the platform can set
+    /// it (macOS, Windows; Linux has no API for it).
+    pub times: bool,
+    /// Permission bits.
+    pub mode: bool,
+    /// Owner and group. Only possible as root; otherwise skipped quietly.
+    pub owner: bool,
+    /// Extended attributes such as `user.xdg.origin.url`,
+    /// `com.apple.quarantine` or `security.selinux` (the latter needs
+    /// privileges). Unix only.
+    pub xattrs: bool,
+}
+
+impl Default for Preserve {
+    /// Times only: the mtime is the last date signal outside of EXIF
+    /// (PhotoRec stamps the capture date into it).
+    fn default() -> Self {
+        Self {
+            times: true,
+            mode: false,
+            owner: false,
+            xattrs: false,
+        }
+    }
+}
+
+impl Preserve {
+    pub const ALL: Preserve = Preserve {
+        times: true,
+        mode: true,
+        owner: true,
+        xattrs: true,
+    };
+    pub const NONE: Preserve = Preserve {
+        times: false,
+        mode: false,
+        owner: false,
+        xattrs: false,
+    };
+
+    /// Build from names as used in the config file and on the command line:
+    /// "times", "mode", "owner", "xattrs", "all", "none".
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Self> {
+        let mut preserve = Self::NONE;
+        for name in names {
+            match name {
+                "times" => preserve.times = true,
+                "mode" => preserve.mode = true,
+                "owner" => preserve.owner = true,
+                "xattrs" => preserve.xattrs = true,
+                "all" => preserve = Self::ALL,
+                "none" => {}
+                other => anyhow::bail!("unknown preserve entry '{other}'"),
+            }
+        }
+        Ok(preserve)
+    }
+}
+
+/// Carry the selected metadata from `source` over to `target`. Best effort
+/// per attribute: a filesystem that cannot hold an xattr or a non-root user
+/// that cannot chown must not fail a transfer whose content is intact.
+///
+/// Order matters: ownership before permissions (chown clears setuid bits),
+/// permissions last (a read-only mode would block the writes before it).
+pub fn apply(source: &Path, source_meta: &fs::Metadata, target: &Path, preserve: Preserve) {
+    #[cfg(unix)]
+    if preserve.xattrs {
+        copy_xattrs(source, target);
+    }
+
+    if preserve.times {
+        if let Err(e) = set_times(source_meta, target) {
+            debug!("Could not preserve times on {}: {e}", target.to_string_lossy());
+        }
+    }
+
+    #[cfg(unix)]
+    if preserve.owner {
+        use std::os::unix::fs::MetadataExt;
+        if let Err(e) = std::os::unix::fs::chown(target, Some(source_meta.uid()), Some(source_meta.gid()))
+        {
+            debug!("Could not preserve owner on {}: {e}", target.to_string_lossy());
+        }
+    }
+
+    if preserve.mode {
+        if let Err(e) = fs::set_permissions(target, source_meta.permissions()) {
+            debug!("Could not preserve mode on {}: {e}", target.to_string_lossy());
+        }
+    }
+}
+
+fn set_times(source_meta: &fs::Metadata, target: &Path) -> std::io::Result<()> {
+    let mut times = fs::FileTimes::new();
+    if let Ok(accessed) = source_meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = source_meta.modified() {
+        times = times.set_modified(modified);
+    }
+    #[cfg(target_os = "macos")]
+    if let Ok(created) = source_meta.created() {
+        use std::os::macos::fs::FileTimesExt;
+        times = times.set_created(created);
+    }
+    #[cfg(windows)]
+    if let Ok(created) = source_meta.created() {
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(created);
+    }
+    File::options().write(true).open(target)?.set_times(times)
+}
+
+#[cfg(unix)]
+fn copy_xattrs(source: &Path, target: &Path) {
+    let names = match xattr::list(source) {
+        Ok(names) => names,
+        Err(e) => {
+            debug!("Could not list xattrs of {}: {e}", source.to_string_lossy());
+            return;
+        }
+    };
+    for name in names {
+        let result = xattr::get(source, &name)
+            .and_then(|value| xattr::set(target, &name, &value.unwrap_or_default()));
+        if let Err(e) = result {
+            debug!(
+                "Could not preserve xattr {} on {}: {e}",
+                name.to_string_lossy(),
+                target.to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Set the modified time, and the birth time where the platform allows it,
+/// to `time` (`--touch`). The access time is left alone.
+pub fn set_capture_time(target: &Path, time: SystemTime) -> std::io::Result<()> {
+    #[allow(unused_mut)]
+    let mut times = fs::FileTimes::new().set_modified(time);
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::FileTimesExt;
+        times = times.set_created(time);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(time);
+    }
+    File::options().write(true).open(target)?.set_times(times)
+}
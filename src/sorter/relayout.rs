@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+
+use super::image::{DateSource, Image};
+use super::manifest::{self, ManifestWriter};
+use super::verify::Library;
+use super::{runs, ProcessOptions, ProcessSummary, TransferMode};
+
+/// One file `relayout` moves (or would move, in a dry run).
+pub struct Relocation {
+    pub from: PathBuf,
+    pub to_dir: PathBuf,
+    pub date: NaiveDate,
+    pub date_source: DateSource,
+}
+
+/// What `relayout` did to a library.
+#[derive(Default)]
+pub struct RelayoutReport {
+    pub relocations: Vec<Relocation>,
+    /// Sorted files already in their folder under the new pattern.
+    pub unchanged: usize,
+    /// Recorded files that are gone; left to `verify`.
+    pub missing: Vec<PathBuf>,
+    /// Sorted files without a recorded date whose date could not be read
+    /// again either; left where they are.
+    pub undated: Vec<PathBuf>,
+    /// Moves that failed, with the reason; those files stay where they are.
+    pub failed: Vec<(PathBuf, String)>,
+    /// Run id the moves were recorded under; `None` for a dry run or when
+    /// nothing moved.
+    pub run_id: Option<String>,
+    /// The manifest the moves were recorded in.
+    pub manifest: Option<PathBuf>,
+}
+
+/// Move every sorted file of the library in `target` into the folder
+/// `pattern` renders for its date, keeping its name. Dates come from the
+/// manifest row that stored the file, or are read again from files
+/// recorded without one. Unsorted and corrupt files stay put.
+///
+/// The moves are recorded in the library's manifest under a new run, kept
+/// in the runs file with `pattern` as its layout, so `revert --last`
+/// restores the previous layout and `verify` checks against the new one.
+pub fn relayout(target: &Path, pattern: &str, dry_run: bool) -> anyhow::Result<RelayoutReport> {
+    let library = Library::open(target)?;
+    let mut report = RelayoutReport::default();
+    for (path, entry) in &library.held {
+        if entry.category != "sorted" {
+            continue;
+        }
+        if fs::symlink_metadata(path).is_err() {
+            report.missing.push(path.clone());
+            continue;
+        }
+        let image = Image::new(path.clone(), library.root.clone());
+        let dated = match (entry.date, entry.date_source) {
+            (Some(date), Some(date_source)) => Some((date, date_source)),
+            _ => image
+                .extract_datetime()
+                .ok()
+                .map(|(datetime, date_source)| (datetime.date(), date_source)),
+        };
+        let Some((date, date_source)) = dated else {
+            report.undated.push(path.clone());
+            continue;
+        };
+        let to_dir = image.target_dir_for(date, pattern);
+        if path.parent() == Some(to_dir.as_path()) {
+            report.unchanged += 1;
+            continue;
+        }
+        report.relocations.push(Relocation {
+            from: path.clone(),
+            to_dir,
+            date,
+            date_source,
+        });
+    }
+    if dry_run || report.relocations.is_empty() {
+        return Ok(report);
+    }
+
+    let run_id = manifest::new_run_id();
+    let options = ProcessOptions {
+        mode: TransferMode::Move,
+        pattern: pattern.to_string(),
+        manifest: library.manifest_format(),
+        ..ProcessOptions::default()
+    };
+    let record = ManifestWriter::new(&library.root, options.manifest, false, &run_id);
+    manifest::check_header(record.path())?;
+    runs::record_started(
+        &library.root,
+        &run_id,
+        std::slice::from_ref(&library.root),
+        &options,
+    );
+    let mut moved = 0;
+    for relocation in &report.relocations {
+        match library.move_file(
+            &record,
+            &relocation.from,
+            &relocation.to_dir,
+            (relocation.date, relocation.date_source),
+            &format!("relayout to {pattern}"),
+        ) {
+            Ok(_) => moved += 1,
+            Err(e) => report
+                .failed
+                .push((relocation.from.clone(), format!("{e:#}"))),
+        }
+    }
+    runs::record_finished(
+        &library.root,
+        &run_id,
+        &ProcessSummary {
+            transferred: moved,
+            failed: report
+                .failed
+                .iter()
+                .map(|(path, reason)| (path.display().to_string(), reason.clone()))
+                .collect(),
+            run_id: run_id.clone(),
+            ..ProcessSummary::default()
+        },
+    );
+    report.run_id = Some(run_id);
+    report.manifest = Some(record.path().to_path_buf());
+    Ok(report)
+}
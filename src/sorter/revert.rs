@@ -0,0 +1,332 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use chrono::NaiveDate;
+use globset::GlobMatcher;
+use tracing::{debug, warn};
+
+use super::archive;
+use super::checksum::file_checksum;
+use super::image::{self, Image};
+use super::manifest::{self, ManifestEntry, ManifestFormat, ManifestWriter};
+use super::preserve::Preserve;
+use super::{files_identical, TransferMode};
+
+/// Actions in a revert's own manifest are the undone action with this
+/// prefix (`reverted_copied`, …), on the paths of the row that was undone.
+/// Reverting such a row redoes the original transfer.
+pub(super) const REVERTED_PREFIX: &str = "reverted_";
+
+/// Which manifest rows `revert` acts on. Every criterion that is set must
+/// match; the default matches everything.
+#[derive(Default)]
+pub struct RevertFilter {
+    /// Only rows of this run (see `ProcessSummary::run_id`).
+    pub run: Option<String>,
+    /// Capture dates from `from` to `to`, inclusive. Rows without a date
+    /// (unsorted, corrupt) never match a date range.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only these categories (sorted, unsorted, corrupt); empty for all.
+    pub categories: Vec<String>,
+    /// Only rows whose source or target path matches.
+    pub path: Option<GlobMatcher>,
+}
+
+impl RevertFilter {
+    fn matches(&self, entry: &ManifestEntry) -> bool {
+        if self.run.as_ref().is_some_and(|run| *run != entry.run_id) {
+            return false;
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let Some(date) = entry.date else {
+                return false;
+            };
+            if self.from.is_some_and(|from| date < from) || self.to.is_some_and(|to| date > to) {
+                return false;
+            }
+        }
+        if !self.categories.is_empty() && !self.categories.contains(&entry.category) {
+            return false;
+        }
+        self.path
+            .as_ref()
+            .is_none_or(|glob| glob.is_match(&entry.source) || glob.is_match(&entry.target))
+    }
+}
+
+/// Outcome of a `revert`.
+#[derive(Default, Debug)]
+pub struct RevertSummary {
+    pub reverted: usize,
+    /// Rows left alone: already undone, the original missing, or changed
+    /// since the run.
+    pub skipped: usize,
+    /// Of the skipped, targets that are no longer what the run wrote (size
+    /// or checksum differ). Revert never deletes or moves those.
+    pub modified: Vec<String>,
+    /// Directories removed because the revert left them empty.
+    pub removed_dirs: usize,
+    /// The revert's own manifest; reverting it redoes what was undone.
+    /// `None` on dry runs and when nothing was reverted.
+    pub manifest: Option<PathBuf>,
+}
+
+/// Id of the newest run recorded in the manifest at `manifest_path`.
+pub fn last_run(manifest_path: &Path) -> anyhow::Result<Option<String>> {
+    let entries = manifest::read_manifest(manifest_path)?;
+    Ok(entries
+        .into_iter()
+        .rev()
+        .map(|entry| entry.run_id)
+        .find(|id| !id.is_empty()))
+}
+
+/// What happened to one manifest row.
+enum Step {
+    Done,
+    Skipped(&'static str),
+    Modified,
+    /// Rows that touched nothing (duplicate, collision_skipped, failed).
+    Ignored,
+}
+
+/// Undo a previous run from its manifest, newest row first: copied,
+/// reflinked and hardlinked files are deleted from the target (only while
+/// the source still exists — never the last copy), symlinks are removed,
+/// moved files are moved back. A file is only deleted or moved while its
+/// size and checksum (where recorded) still match the manifest; anything
+/// changed since the run is left alone. Directories below the manifest's
+/// directory that end up empty are removed.
+///
+/// Only rows matching `filter` are considered. Every undone row is recorded
+/// in `exif-sorter-revert-<run id>.csv` (`.jsonl` for JSON Lines
+/// manifests) next to the manifest, so reverting that file redoes the
+/// transfers.
+pub fn revert(
+    manifest_path: &Path,
+    filter: &RevertFilter,
+    dry_run: bool,
+) -> anyhow::Result<RevertSummary> {
+    let entries = manifest::read_manifest(manifest_path)?;
+    let root = manifest_path.parent().unwrap_or(Path::new("."));
+    let run_id = manifest::new_run_id();
+    let extension = match ManifestFormat::of(manifest_path) {
+        ManifestFormat::Csv => "csv",
+        ManifestFormat::Jsonl => "jsonl",
+    };
+    let record_path = root.join(format!("exif-sorter-revert-{run_id}.{extension}"));
+    let record = ManifestWriter::at(record_path.clone(), dry_run, &run_id);
+    let mut summary = RevertSummary::default();
+
+    for entry in entries.iter().rev().filter(|entry| filter.matches(entry)) {
+        let step = match entry.action.strip_prefix(REVERTED_PREFIX) {
+            Some(action) => redo(entry, action, &record, dry_run)?,
+            None => undo(entry, &record, dry_run)?,
+        };
+        match step {
+            Step::Done => {
+                summary.reverted += 1;
+                if !dry_run && !entry.action.starts_with(REVERTED_PREFIX) {
+                    summary.removed_dirs += remove_empty_dirs(Path::new(&entry.target), root);
+                }
+            }
+            Step::Skipped(reason) => {
+                warn!("revert: skipping '{}' ({reason})", entry.target);
+                summary.skipped += 1;
+            }
+            Step::Modified => {
+                warn!(
+                    "revert: skipping '{}' (changed since the run)",
+                    entry.target
+                );
+                summary.skipped += 1;
+                summary.modified.push(entry.target.clone());
+            }
+            Step::Ignored => {}
+        }
+    }
+
+    if !dry_run && record_path.exists() {
+        summary.manifest = Some(record_path);
+    }
+    Ok(summary)
+}
+
+/// Reverse one transfer. The row is recorded once the transfer is undone.
+fn undo(entry: &ManifestEntry, record: &ManifestWriter, dry_run: bool) -> anyhow::Result<Step> {
+    let Some(mode) = TransferMode::from_action(&entry.action) else {
+        return Ok(Step::Ignored);
+    };
+    let target = Path::new(&entry.target);
+    let source = Path::new(&entry.source);
+    match mode {
+        // A hard link is just a second name for the source's inode, so it
+        // is reverted exactly like a copy.
+        TransferMode::Copy | TransferMode::Reflink | TransferMode::Hardlink => {
+            if !(target.exists() && archive::source_exists(source)) {
+                return Ok(Step::Skipped("copy or original missing"));
+            }
+            // Rows without a checksum are compared with the original.
+            let unchanged = matches_record(entry, target)
+                && (!entry.checksum.is_empty() || files_identical(source, target).unwrap_or(false));
+            if !unchanged {
+                return Ok(Step::Modified);
+            }
+            debug!("revert: remove copy {}", entry.target);
+            if !dry_run {
+                fs::remove_file(target)?;
+            }
+            record_reverted(record, entry);
+        }
+        TransferMode::Symlink => {
+            // Only ever remove the link itself, never what it points to;
+            // `exists()` would follow the link, so inspect it directly.
+            let is_link = fs::symlink_metadata(target)
+                .map(|meta| meta.file_type().is_symlink())
+                .unwrap_or(false);
+            if !is_link {
+                return Ok(Step::Skipped("symlink missing or replaced"));
+            }
+            if !matches_record(entry, target) {
+                return Ok(Step::Modified);
+            }
+            debug!("revert: remove symlink {}", entry.target);
+            if !dry_run {
+                fs::remove_file(target)?;
+            }
+            record_reverted(record, entry);
+        }
+        TransferMode::Move => {
+            if !target.exists() || source.exists() {
+                return Ok(Step::Skipped("already restored or target missing"));
+            }
+            if !matches_record(entry, target) {
+                return Ok(Step::Modified);
+            }
+            debug!("revert: move {} back to {}", entry.target, entry.source);
+            if !dry_run {
+                if let Some(parent) = source.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let expected = (!entry.checksum.is_empty()).then_some(entry.checksum.as_str());
+                image::move_verified(target, source, expected, Preserve::default())
+                    .with_context(|| format!("cannot move '{}' back", entry.target))?;
+                if let Some(mtime) = entry.mtime_before {
+                    if let Err(e) = fs::File::options()
+                        .write(true)
+                        .open(source)
+                        .and_then(|file| file.set_modified(mtime.into()))
+                    {
+                        warn!("revert: could not restore mtime of '{}': {e}", entry.source);
+                    }
+                }
+            }
+            record_reverted(record, entry);
+        }
+    }
+    Ok(Step::Done)
+}
+
+/// Transfer a reverted row's source to its target again, recorded under
+/// the original action.
+fn redo(
+    entry: &ManifestEntry,
+    action: &str,
+    record: &ManifestWriter,
+    dry_run: bool,
+) -> anyhow::Result<Step> {
+    let Some(mode) = TransferMode::from_action(action) else {
+        return Ok(Step::Ignored);
+    };
+    let target = Path::new(&entry.target);
+    let source = Path::new(&entry.source);
+    if !archive::source_exists(source) {
+        return Ok(Step::Skipped("original missing"));
+    }
+    if fs::symlink_metadata(target).is_ok() {
+        return Ok(Step::Skipped("target exists"));
+    }
+    // Archive members are extracted again, checked, then moved into place.
+    let extracted = match archive::split(source) {
+        Some((archive, name)) => Some(archive::extract_member(
+            &archive,
+            &name,
+            &std::env::temp_dir(),
+        )?),
+        None => None,
+    };
+    let (source, mode) = match &extracted {
+        Some(spool) => (spool.path(), TransferMode::Move),
+        None => (source, mode),
+    };
+    // The recorded size of a symlink is that of the link.
+    if mode != TransferMode::Symlink && !matches_record(entry, source) {
+        return Ok(Step::Modified);
+    }
+    debug!("revert: {mode} {} to {} again", entry.source, entry.target);
+    if dry_run {
+        return Ok(Step::Done);
+    }
+
+    let (Some(dir), Some(stem)) = (target.parent(), target.file_stem()) else {
+        return Ok(Step::Skipped("target path has no file name"));
+    };
+    let mut image = Image::new(source.to_path_buf(), dir.to_path_buf());
+    image.target_filename = stem.to_string_lossy().into_owned();
+    image.target_filetype = target
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let checksum = image
+        .transfer_to_target(mode, Preserve::default(), false)
+        .with_context(|| format!("cannot restore '{}'", entry.target))?;
+    record.record(ManifestEntry {
+        action: action.to_string(),
+        reason: String::new(),
+        checksum: checksum.unwrap_or_else(|| entry.checksum.clone()),
+        ..entry.clone()
+    });
+    Ok(Step::Done)
+}
+
+fn record_reverted(record: &ManifestWriter, entry: &ManifestEntry) {
+    record.record(ManifestEntry {
+        action: format!("{REVERTED_PREFIX}{}", entry.action),
+        reason: String::new(),
+        ..entry.clone()
+    });
+}
+
+/// Whether `path` still has the size and checksum the row recorded. Rows
+/// from before sizes or checksums were recorded pass on what they have.
+pub(super) fn matches_record(entry: &ManifestEntry, path: &Path) -> bool {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    if entry.size.is_some_and(|size| size != meta.len()) {
+        return false;
+    }
+    entry.checksum.is_empty()
+        || file_checksum(path).is_ok_and(|checksum| checksum == entry.checksum)
+}
+
+/// Remove the directories above `removed` that are now empty, up to but
+/// not including `root`. Returns how many went.
+pub(super) fn remove_empty_dirs(removed: &Path, root: &Path) -> usize {
+    // The manifest may record relative paths, the user may pass either.
+    let Ok(root) = root.canonicalize() else {
+        return 0;
+    };
+    let mut count = 0;
+    let mut dir = removed.parent().and_then(|dir| dir.canonicalize().ok());
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(&root) || fs::remove_dir(&current).is_err() {
+            break;
+        }
+        count += 1;
+        dir = current.parent().map(Path::to_path_buf);
+    }
+    count
+}
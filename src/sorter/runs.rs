@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{ProcessOptions, ProcessSummary};
+
+pub const RUNS_FILENAME: &str = "exif-sorter-runs.jsonl";
+
+/// One line of `{target}/exif-sorter-runs.jsonl`. A run appends `Started`
+/// when it begins and `Finished` when it ends (again after a `resume`), so
+/// a run that was killed shows up without an end.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RunEvent {
+    Started {
+        id: String,
+        version: String,
+        started: DateTime<Utc>,
+        sources: Vec<PathBuf>,
+        target: PathBuf,
+        options: ProcessOptions,
+    },
+    Finished {
+        id: String,
+        finished: DateTime<Utc>,
+        totals: RunTotals,
+    },
+}
+
+/// The counts of a `ProcessSummary`, as kept in the runs file.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RunTotals {
+    pub transferred: usize,
+    pub low_confidence: usize,
+    pub duplicates: usize,
+    pub collisions_skipped: usize,
+    pub unsorted: usize,
+    pub corrupt: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+impl RunTotals {
+    /// Items the run decided on, as in `ProcessSummary::total`.
+    pub fn total(&self) -> usize {
+        self.transferred
+            + self.duplicates
+            + self.collisions_skipped
+            + self.unsorted
+            + self.corrupt
+            + self.failed
+    }
+}
+
+impl From<&ProcessSummary> for RunTotals {
+    fn from(summary: &ProcessSummary) -> Self {
+        Self {
+            transferred: summary.transferred,
+            low_confidence: summary.low_confidence,
+            duplicates: summary.duplicates,
+            collisions_skipped: summary.collisions_skipped,
+            unsorted: summary.unsorted,
+            corrupt: summary.corrupt,
+            failed: summary.failed.len(),
+            cancelled: summary.cancelled,
+        }
+    }
+}
+
+/// A run as read back from the runs file.
+pub struct RunInfo {
+    pub id: String,
+    /// Version of exif-sorter that started the run.
+    pub version: String,
+    pub started: DateTime<Utc>,
+    /// `None` while the run is in progress or after it was killed.
+    pub finished: Option<DateTime<Utc>>,
+    pub sources: Vec<PathBuf>,
+    pub target: PathBuf,
+    pub options: ProcessOptions,
+    /// Of the latest end; a resumed run reports only what `resume` did.
+    pub totals: Option<RunTotals>,
+}
+
+pub fn record_started(target_dir: &Path, id: &str, sources: &[PathBuf], options: &ProcessOptions) {
+    append(
+        target_dir,
+        &RunEvent::Started {
+            id: id.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started: Utc::now(),
+            sources: sources.to_vec(),
+            target: target_dir.to_path_buf(),
+            options: options.clone(),
+        },
+    );
+}
+
+pub fn record_finished(target_dir: &Path, id: &str, summary: &ProcessSummary) {
+    append(
+        target_dir,
+        &RunEvent::Finished {
+            id: id.to_string(),
+            finished: Utc::now(),
+            totals: summary.into(),
+        },
+    );
+}
+
+fn append(target_dir: &Path, event: &RunEvent) {
+    let path = target_dir.join(RUNS_FILENAME);
+    // Like the manifest, the runs file is bookkeeping: failing to write it
+    // must not abort the sort itself.
+    let written = serde_json::to_string(event)
+        .map_err(std::io::Error::other)
+        .and_then(|line| {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            writeln!(file, "{line}")
+        });
+    if let Err(e) = written {
+        warn!("could not record run in '{}': {e}", path.display());
+    }
+}
+
+/// All runs recorded in `target_dir`, oldest first. No runs file means no
+/// runs; unreadable lines are skipped.
+pub fn read_runs(target_dir: &Path) -> anyhow::Result<Vec<RunInfo>> {
+    let path = target_dir.join(RUNS_FILENAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("cannot read runs file {}", path.display()))?;
+    let mut runs: Vec<RunInfo> = Vec::new();
+    let mut index_of: HashMap<String, usize> = HashMap::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(RunEvent::Started {
+                id,
+                version,
+                started,
+                sources,
+                target,
+                options,
+            }) => {
+                index_of.insert(id.clone(), runs.len());
+                runs.push(RunInfo {
+                    id,
+                    version,
+                    started,
+                    finished: None,
+                    sources,
+                    target,
+                    options,
+                    totals: None,
+                });
+            }
+            Ok(RunEvent::Finished {
+                id,
+                finished,
+                totals,
+            }) => {
+                if let Some(run) = index_of.get(&id).map(|&index| &mut runs[index]) {
+                    run.finished = Some(finished);
+                    run.totals = Some(totals);
+                }
+            }
+            Err(_) => warn!("ignoring unreadable line in '{}'", path.display()),
+        }
+    }
+    Ok(runs)
+}
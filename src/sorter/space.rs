@@ -0,0 +1,47 @@
+use std::path::Path;
+
+/// Bytes a run needs on the target filesystem against what it offers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpaceCheck {
+    pub needed: u64,
+    /// Space available to unprivileged users (statvfs `f_bavail`, so root
+    /// reserve is not counted); `None` when the filesystem could not be
+    /// queried. Per-user quotas are not visible here.
+    pub available: Option<u64>,
+}
+
+impl SpaceCheck {
+    /// An unknown amount of free space is not treated as a shortage.
+    pub fn is_sufficient(&self) -> bool {
+        self.available
+            .is_none_or(|available| self.needed <= available)
+    }
+}
+
+/// Free space on the filesystem `target` will live on. The target does not
+/// have to exist yet (planning never creates it): the nearest existing
+/// ancestor is queried instead.
+pub fn available_space(target: &Path) -> Option<u64> {
+    let existing = target.ancestors().find(|dir| dir.exists())?;
+    fs4::available_space(existing).ok()
+}
+
+/// Whether `source` and (the nearest existing ancestor of) `target` are on
+/// the same filesystem, i.e. a move is a rename that needs no space.
+/// Unknown on non-unix platforms, which conservatively reads as `false`.
+pub fn same_filesystem(source: &Path, target: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt as _;
+        let device = |path: &Path| {
+            let existing = path.ancestors().find(|dir| dir.exists())?;
+            std::fs::metadata(existing).ok().map(|meta| meta.dev())
+        };
+        matches!((device(source), device(target)), (Some(a), Some(b)) if a == b)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (source, target);
+        false
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+
+use super::image::{Image, MediaFacts};
+use super::{PlannedAction, PlannedItem};
+
+/// The first line of a plan file.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    sources: Vec<PathBuf>,
+    target: PathBuf,
+}
+
+/// One line per planned item: what it takes to rebuild it without dating
+/// the file again.
+#[derive(Serialize, Deserialize)]
+struct SpilledItem {
+    source: PathBuf,
+    source_root: PathBuf,
+    action: PlannedAction,
+    planned_target: PathBuf,
+    /// Differs from the source's extension when the content asked for
+    /// another one.
+    target_filetype: String,
+    size: u64,
+    already_stored: bool,
+    selected: bool,
+    /// What planning read of the metadata, for the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    facts: Option<MediaFacts>,
+}
+
+/// Writes a plan to a JSON Lines file item by item as `plan_streaming`
+/// hands them out, from any thread, so the plan never has to fit in memory.
+/// Remembers where each item's line starts, for `SpilledPlan::page`.
+pub struct PlanSpill {
+    path: PathBuf,
+    state: Mutex<SpillState>,
+}
+
+struct SpillState {
+    file: BufWriter<File>,
+    /// Bytes written so far.
+    position: u64,
+    offsets: Vec<u64>,
+    /// The first write that failed; `finish` returns it.
+    error: Option<anyhow::Error>,
+}
+
+impl PlanSpill {
+    /// Start the plan file at `path` for a plan of `sources` into `target`.
+    pub fn create(path: &Path, sources: &[PathBuf], target: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("cannot create plan file {}", path.display()))?;
+        let header = serde_json::to_string(&Header {
+            sources: sources.to_vec(),
+            target: target.to_path_buf(),
+        })?;
+        let mut file = BufWriter::new(file);
+        writeln!(file, "{header}")?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: Mutex::new(SpillState {
+                file,
+                position: header.len() as u64 + 1,
+                offsets: Vec::new(),
+                error: None,
+            }),
+        })
+    }
+
+    pub fn push(&self, item: PlannedItem) {
+        let spilled = SpilledItem {
+            source: PathBuf::from(item.image.source_full()),
+            target_filetype: item.image.target_filetype,
+            facts: item.image.facts,
+            source_root: item.source_root,
+            action: item.action,
+            planned_target: item.planned_target,
+            size: item.size,
+            already_stored: item.already_stored,
+            selected: item.selected,
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.error.is_some() {
+            return;
+        }
+        let written = serde_json::to_string(&spilled)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| {
+                writeln!(state.file, "{line}")?;
+                Ok(line.len() as u64 + 1)
+            });
+        match written {
+            Ok(len) => {
+                let start = state.position;
+                state.offsets.push(start);
+                state.position += len;
+            }
+            Err(e) => state.error = Some(e),
+        }
+    }
+
+    /// Flush the file and open it for reading.
+    pub fn finish(self) -> anyhow::Result<SpilledPlan> {
+        let state = self.state.into_inner().unwrap_or_else(|e| e.into_inner());
+        if let Some(e) = state.error {
+            return Err(e.context(format!("cannot write plan file {}", self.path.display())));
+        }
+        state.file.into_inner().map_err(|e| e.into_error())?;
+        let target = SpilledPlan::read_header(&self.path)?.target;
+        Ok(SpilledPlan {
+            path: self.path,
+            target,
+            offsets: state.offsets,
+        })
+    }
+}
+
+/// A plan spilled to disk by `PlanSpill`, read a page at a time: only the
+/// offsets of its lines are held in memory.
+pub struct SpilledPlan {
+    path: PathBuf,
+    target: PathBuf,
+    offsets: Vec<u64>,
+}
+
+impl SpilledPlan {
+    /// Open the plan file at `path`, reading it once to index its lines.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let target = Self::read_header(path)?.target;
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut offsets = Vec::new();
+        let mut position = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line)? as u64;
+            if len == 0 {
+                break;
+            }
+            // The header is not an item.
+            if position > 0 {
+                offsets.push(position);
+            }
+            position += len;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            target,
+            offsets,
+        })
+    }
+
+    fn read_header(path: &Path) -> anyhow::Result<Header> {
+        let file = File::open(path)
+            .with_context(|| format!("cannot open plan file {}", path.display()))?;
+        let mut header = String::new();
+        BufReader::new(file).read_line(&mut header)?;
+        serde_json::from_str(&header)
+            .with_context(|| format!("{} is not a plan file", path.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Number of planned items.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Up to `count` items from the `start`th on; fewer at the end.
+    pub fn page(&self, start: usize, count: usize) -> anyhow::Result<Vec<PlannedItem>> {
+        let Some(&offset) = self.offsets.get(start) else {
+            return Ok(Vec::new());
+        };
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+        reader
+            .lines()
+            .take(count.min(self.len() - start))
+            .map(|line| self.item(&line?))
+            .collect()
+    }
+
+    /// Every item, in file order, read as it is consumed.
+    pub fn items(&self) -> anyhow::Result<impl Iterator<Item = anyhow::Result<PlannedItem>> + '_> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.read_line(&mut String::new())?;
+        Ok(reader.lines().map(|line| self.item(&line?)))
+    }
+
+    fn item(&self, line: &str) -> anyhow::Result<PlannedItem> {
+        let spilled: SpilledItem = serde_json::from_str(line)
+            .with_context(|| format!("damaged line in plan file {}", self.path.display()))?;
+        let mut image = Image::new(spilled.source, self.target.clone());
+        image.target_filetype = spilled.target_filetype;
+        image.facts = spilled.facts;
+        Ok(PlannedItem {
+            image,
+            action: spilled.action,
+            planned_target: spilled.planned_target,
+            selected: spilled.selected,
+            size: spilled.size,
+            already_stored: spilled.already_stored,
+            source_root: spilled.source_root,
+            merge: None,
+        })
+    }
+}
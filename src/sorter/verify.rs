@@ -0,0 +1,316 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use chrono::{DateTime, NaiveDate};
+
+use super::dir::scan_dir;
+use super::image::{DateSource, Image};
+use super::manifest::{self, ManifestEntry, ManifestFormat, ManifestWriter};
+use super::revert::{matches_record, remove_empty_dirs, REVERTED_PREFIX};
+use super::runs::{self, RunInfo};
+use super::{unique_name_in, TransferMode};
+
+/// A file whose folder no longer matches the date it resolves to today.
+pub struct Misfiled {
+    pub path: PathBuf,
+    /// The folder the date resolved today puts it in.
+    pub expected_dir: PathBuf,
+    pub date: NaiveDate,
+    pub date_source: DateSource,
+}
+
+/// What `verify` found in a library.
+#[derive(Default)]
+pub struct VerifyReport {
+    /// Files the manifests say the library holds.
+    pub checked: usize,
+    /// Recorded files that are gone.
+    pub missing: Vec<PathBuf>,
+    /// Recorded files whose size or checksum differs from the manifest.
+    pub changed: Vec<PathBuf>,
+    /// Media files in the library that no manifest mentions.
+    pub untracked: Vec<PathBuf>,
+    pub misfiled: Vec<Misfiled>,
+    /// Of the misfiled, how many `fix` moved.
+    pub fixed: usize,
+    /// Run id the fixes were recorded under; `None` when nothing moved.
+    pub fix_run_id: Option<String>,
+    /// The manifest the fixes were recorded in.
+    pub manifest: Option<PathBuf>,
+}
+
+impl VerifyReport {
+    /// Problems left after any fixes.
+    pub fn problems(&self) -> usize {
+        self.missing.len() + self.changed.len() + self.untracked.len() + self.misfiled.len()
+            - self.fixed
+    }
+}
+
+/// Audit the library in `target` against its manifests (and those of
+/// reverts run in it): report recorded files that are missing or changed,
+/// media files no manifest knows, and files in a folder other than the one
+/// their date resolves to today, for instance after the fallback chain
+/// improved. Only metadata-derived dates are trusted for that last check;
+/// a copy's file timestamps are those of the copy.
+///
+/// Folders are computed with the pattern of the run that stored each file,
+/// else of the newest recorded run, else `default_pattern`. With `fix`, misfiled files
+/// are moved to their folder and every move is recorded in the manifest
+/// under a fresh run id, so `revert --run` can undo the fixes.
+pub fn verify(target: &Path, default_pattern: &str, fix: bool) -> anyhow::Result<VerifyReport> {
+    let library = Library::open(target)?;
+    let root = &library.root;
+    // Fix rows belong to no recorded run; the newest run's layout is the
+    // library's.
+    let library_pattern = library
+        .runs
+        .last()
+        .map_or(default_pattern, |run| run.options.pattern.as_str());
+    let patterns: HashMap<&str, &str> = library
+        .runs
+        .iter()
+        .map(|run| (run.id.as_str(), run.options.pattern.as_str()))
+        .collect();
+
+    let mut report = VerifyReport {
+        checked: library.held.len(),
+        ..VerifyReport::default()
+    };
+    for (path, entry) in &library.held {
+        if fs::symlink_metadata(path).is_err() {
+            report.missing.push(path.clone());
+            continue;
+        }
+        if !matches_record(entry, path) {
+            report.changed.push(path.clone());
+            continue;
+        }
+        if !matches!(entry.category.as_str(), "sorted" | "unsorted") {
+            continue;
+        }
+        let pattern = patterns
+            .get(entry.run_id.as_str())
+            .copied()
+            .unwrap_or(library_pattern);
+        let image = Image::new(path.clone(), root.clone());
+        if let Ok((datetime, date_source)) = image.extract_datetime() {
+            let date = datetime.date();
+            let expected_dir = image.target_dir_for(date, pattern);
+            if !date_source.is_low_confidence() && path.parent() != Some(expected_dir.as_path()) {
+                report.misfiled.push(Misfiled {
+                    path: path.clone(),
+                    expected_dir,
+                    date,
+                    date_source,
+                });
+            }
+        }
+    }
+
+    let known: HashSet<&PathBuf> = library.held.keys().collect();
+    report.untracked = scan_dir(root)?
+        .into_iter()
+        .map(|entry| normalize(entry.path()))
+        .filter(|path| !known.contains(path))
+        .collect();
+    report.untracked.sort();
+
+    if fix && !report.misfiled.is_empty() {
+        let run_id = manifest::new_run_id();
+        let record = ManifestWriter::new(root, library.manifest_format(), false, &run_id);
+        manifest::check_header(record.path())?;
+        for misfiled in &report.misfiled {
+            library.move_file(
+                &record,
+                &misfiled.path,
+                &misfiled.expected_dir,
+                (misfiled.date, misfiled.date_source),
+                "verify --fix: folder did not match the date",
+            )?;
+            report.fixed += 1;
+        }
+        report.fix_run_id = Some(run_id);
+        report.manifest = Some(record.path().to_path_buf());
+    }
+    Ok(report)
+}
+
+/// A target directory as its manifests describe it.
+pub(super) struct Library {
+    /// The target, canonical.
+    pub root: PathBuf,
+    /// The runs recorded in the target, oldest first.
+    pub runs: Vec<RunInfo>,
+    /// Targets as the recorded runs named them, longest first, so a nested
+    /// target wins over its parent.
+    run_targets: Vec<PathBuf>,
+    /// The files the library holds according to its manifests, by
+    /// normalized path, with the row that put each there.
+    pub held: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl Library {
+    pub fn open(target: &Path) -> anyhow::Result<Self> {
+        let Ok(root) = target.canonicalize() else {
+            bail!("'{}' is not a directory", target.display());
+        };
+        let runs = runs::read_runs(&root)?;
+        let mut run_targets: Vec<PathBuf> = runs.iter().map(|run| run.target.clone()).collect();
+        run_targets.sort_by_key(|path| std::cmp::Reverse(path.as_os_str().len()));
+        run_targets.dedup();
+        let held = library_state(&root, &run_targets)?;
+        Ok(Self {
+            root,
+            runs,
+            run_targets,
+            held,
+        })
+    }
+
+    /// The format of the manifest the library already keeps.
+    pub fn manifest_format(&self) -> ManifestFormat {
+        if !self.root.join(manifest::MANIFEST_FILENAME).exists()
+            && self.root.join(manifest::MANIFEST_JSONL_FILENAME).exists()
+        {
+            ManifestFormat::Jsonl
+        } else {
+            ManifestFormat::Csv
+        }
+    }
+
+    /// Move the held file at `path` into `dir`, keeping its name unless
+    /// taken, record the move in `record` and drop directories it leaves
+    /// empty. Returns where the file went.
+    pub fn move_file(
+        &self,
+        record: &ManifestWriter,
+        path: &Path,
+        dir: &Path,
+        (date, date_source): (NaiveDate, DateSource),
+        reason: &str,
+    ) -> anyhow::Result<PathBuf> {
+        let (Some(stem), Some(ext)) = (path.file_stem(), path.extension()) else {
+            bail!("'{}' has no file extension", path.display());
+        };
+        let ext = ext.to_string_lossy();
+        fs::create_dir_all(dir)?;
+        let name = unique_name_in(dir, &stem.to_string_lossy(), &ext);
+        let moved_to = dir.join(format!("{name}.{ext}"));
+        let mtime_before = fs::symlink_metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .map(DateTime::from);
+        fs::rename(path, &moved_to)?;
+        // Both paths in the form the run recorded, so the manifest and the
+        // catalog see a move from the path they know.
+        let held_entry = self.held.get(path);
+        let run_target = held_entry.and_then(|entry| {
+            self.run_targets
+                .iter()
+                .find(|run_target| Path::new(&entry.target).starts_with(run_target))
+        });
+        let recorded = |path: &Path| match (run_target, path.strip_prefix(&self.root)) {
+            (Some(run_target), Ok(below)) => run_target.join(below),
+            _ => path.to_path_buf(),
+        };
+        record.record(ManifestEntry {
+            action: TransferMode::Move.action().to_string(),
+            category: "sorted".to_string(),
+            source: recorded(path).to_string_lossy().into_owned(),
+            target: recorded(&moved_to).to_string_lossy().into_owned(),
+            date: Some(date),
+            date_source: Some(date_source),
+            reason: reason.to_string(),
+            checksum: held_entry
+                .map(|entry| entry.checksum.clone())
+                .unwrap_or_default(),
+            mtime_before,
+            ..ManifestEntry::default()
+        });
+        remove_empty_dirs(path, &self.root);
+        Ok(moved_to)
+    }
+}
+
+/// The files the library holds according to its manifests. Rows of all
+/// manifests are replayed in time order: transfers add their target,
+/// reverted transfers remove it, and a move out of a library path (a
+/// `verify --fix` or `relayout`) removes that path until it is reverted,
+/// which brings back the row it had.
+fn library_state(
+    root: &Path,
+    run_targets: &[PathBuf],
+) -> anyhow::Result<BTreeMap<PathBuf, ManifestEntry>> {
+    let locate = |recorded: &str| locate(Path::new(recorded), root, run_targets);
+    let mut entries = Vec::new();
+    for dir_entry in fs::read_dir(root)? {
+        let path = dir_entry?.path();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let is_manifest = name == manifest::MANIFEST_FILENAME
+            || name == manifest::MANIFEST_JSONL_FILENAME
+            || (name.starts_with("exif-sorter-revert-")
+                && (name.ends_with(".csv") || name.ends_with(".jsonl")));
+        if is_manifest {
+            entries.extend(manifest::read_manifest(&path)?);
+        }
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let mut held = BTreeMap::new();
+    // Rows moved away from inside the library, by the path they moved to.
+    let mut moved_from: HashMap<PathBuf, ManifestEntry> = HashMap::new();
+    for entry in entries {
+        let target = locate(&entry.target);
+        if let Some(action) = entry.action.strip_prefix(REVERTED_PREFIX) {
+            held.remove(&target);
+            // An undone move inside the library puts the file back.
+            let source = locate(&entry.source);
+            if action == TransferMode::Move.action() && source.starts_with(root) {
+                let previous = moved_from.remove(&target).unwrap_or(entry);
+                held.insert(source, previous);
+            }
+            continue;
+        }
+        let Some(mode) = TransferMode::from_action(&entry.action) else {
+            continue;
+        };
+        if mode == TransferMode::Move {
+            if let Some(previous) = held.remove(&locate(&entry.source)) {
+                moved_from.insert(target.clone(), previous);
+            }
+        }
+        held.insert(target, entry);
+    }
+    Ok(held)
+}
+
+/// Where a path recorded by a run is now. Paths below the target as the
+/// run named it (possibly relative to its working directory) are rebased
+/// onto `root`; anything else is taken as it is.
+fn locate(recorded: &Path, root: &Path, run_targets: &[PathBuf]) -> PathBuf {
+    run_targets
+        .iter()
+        .find_map(|run_target| recorded.strip_prefix(run_target).ok())
+        .map(|below| root.join(below))
+        .unwrap_or_else(|| normalize(recorded))
+}
+
+/// An absolute path with its directory resolved, so paths recorded
+/// relative to another working directory or through a symlinked target
+/// compare equal. Files that are gone keep their lexical form.
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (
+        absolute.parent().map(Path::canonicalize),
+        absolute.file_name(),
+    ) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => absolute,
+    }
+}
@@ -1,8 +1,8 @@
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 
 /// Seconds between the MP4/QuickTime epoch (1904-01-01) and the unix epoch.
 const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
@@ -19,10 +19,25 @@ pub fn is_video_extension(ext: &str) -> bool {
 /// missing or the creation time is unset (cameras that never had a clock
 /// write 0, i.e. 1904-01-01 — filtered by the caller's plausibility check).
 pub fn creation_date(path: &Path) -> Option<NaiveDate> {
+    creation_time(path).map(|time| time.date())
+}
+
+/// Like `creation_date`, with the time of day. The container stores UTC.
+pub fn creation_time(path: &Path) -> Option<NaiveDateTime> {
     let mut file = File::open(path).ok()?;
     let len = file.metadata().ok()?.len();
-    let (moov_start, moov_size) = find_box(&mut file, 0, len, b"moov")?;
-    let (mvhd_start, mvhd_size) = find_box(&mut file, moov_start, moov_start + moov_size, b"mvhd")?;
+    read_creation_time(&mut file, len)
+}
+
+/// Like `creation_time`, from the first bytes of a file. Containers that
+/// keep `moov` after the media data have nothing to find there.
+pub fn creation_time_in(head: &[u8]) -> Option<NaiveDateTime> {
+    read_creation_time(&mut Cursor::new(head), head.len() as u64)
+}
+
+fn read_creation_time(file: &mut (impl Read + Seek), len: u64) -> Option<NaiveDateTime> {
+    let (moov_start, moov_size) = find_box(file, 0, len, b"moov")?;
+    let (mvhd_start, mvhd_size) = find_box(file, moov_start, moov_start + moov_size, b"mvhd")?;
     if mvhd_size < 12 {
         return None;
     }
@@ -45,12 +60,17 @@ pub fn creation_date(path: &Path) -> Option<NaiveDate> {
     };
 
     let unix = seconds_since_1904 - MP4_EPOCH_OFFSET;
-    Some(chrono::NaiveDateTime::from_timestamp_opt(unix, 0)?.date())
+    NaiveDateTime::from_timestamp_opt(unix, 0)
 }
 
 /// Walk sibling boxes in `[offset, end)` and return (content_start,
 /// content_size) of the first box named `name`.
-fn find_box(file: &mut File, mut offset: u64, end: u64, name: &[u8; 4]) -> Option<(u64, u64)> {
+fn find_box(
+    file: &mut (impl Read + Seek),
+    mut offset: u64,
+    end: u64,
+    name: &[u8; 4],
+) -> Option<(u64, u64)> {
     while offset + 8 <= end {
         file.seek(SeekFrom::Start(offset)).ok()?;
         let mut header = [0u8; 8];
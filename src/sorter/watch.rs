@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Context as _;
+use notify::event::{AccessKind, AccessMode, CreateKind, ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher as _};
+use tracing::{debug, info, warn};
+
+use crate::error::AppError;
+
+use super::journal::JOURNAL_FILENAME;
+use super::{execute, plan_claimed, resume, ProcessOptions, ProcessSummary};
+
+/// Locked in the target while a watcher sorts a batch, so watchers sharing a
+/// target take turns.
+pub const LOCK_FILENAME: &str = "exif-sorter-watch.lock";
+
+/// How often pending files are looked at.
+const TICK: Duration = Duration::from_millis(250);
+
+/// Files locked (and so open) at once; a larger batch is sorted in turns.
+const BATCH_LIMIT: usize = 512;
+
+/// What `watch` reports as it goes.
+pub enum WatchEvent {
+    /// The sources are watched; files that arrive from now on are sorted.
+    Watching,
+    /// The run a previous watcher left unfinished was resumed.
+    Resumed(ProcessSummary),
+    /// A batch of complete files was sorted.
+    Sorted(ProcessSummary),
+    /// A batch could not be planned or executed. Its files are tried again
+    /// when they change, or on the next start.
+    Failed(String),
+}
+
+/// A file (or directory) that changed and is not taken yet.
+struct Pending {
+    /// Size and modified time when last looked at.
+    stamp: Option<(u64, SystemTime)>,
+    /// When it last changed.
+    since: Instant,
+    /// Closed after writing, or moved into place, since it last changed.
+    closed: bool,
+}
+
+/// Sort what arrives in `sources` into `target` until `options.control` is
+/// cancelled, with `options` (always incremental, so nothing is sorted
+/// twice).
+///
+/// A file is taken once it has been closed after writing, or moved into the
+/// source, and its size and modified time stayed the same for `settle`;
+/// every file taken in one look is planned and executed as one run, which
+/// appends to the manifest. Files nobody closes (hard links, a writer that
+/// died) wait for the next start. There, a run a previous watcher left
+/// unfinished is resumed first, and once `settle` has passed what arrived
+/// while none ran is sorted.
+///
+/// Each file is locked (`flock`) while its batch runs, so two watchers of
+/// the same source never process the same file, and the target's
+/// `LOCK_FILENAME` is, so watchers of one target take turns. Cancelling
+/// stops between two files, leaving the journal for the next start.
+pub fn watch(
+    sources: &[PathBuf],
+    target: &Path,
+    options: &ProcessOptions,
+    settle: Duration,
+    mut on_event: impl FnMut(WatchEvent),
+) -> anyhow::Result<()> {
+    let sources = sources
+        .iter()
+        .map(|source| {
+            source.canonicalize().map_err(|_| AppError::InvalidSource {
+                expected: source.to_string_lossy().into_owned(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    fs::create_dir_all(target)?;
+    let target = target.canonicalize()?;
+    let options = ProcessOptions {
+        incremental: true,
+        ..options.clone()
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for source in &sources {
+        watcher
+            .watch(source, RecursiveMode::Recursive)
+            .with_context(|| format!("cannot watch '{}'", source.display()))?;
+    }
+    info!("watching {sources:?}");
+    on_event(WatchEvent::Watching);
+
+    if target.join(JOURNAL_FILENAME).exists() {
+        let resumed = take_turn(&target).and_then(|_turn| {
+            resume(&target, &options.control, |_, _| {}).map(|(_, summary)| summary)
+        });
+        match resumed {
+            Ok(summary) => on_event(WatchEvent::Resumed(summary)),
+            Err(e) => on_event(WatchEvent::Failed(format!("{e:#}"))),
+        }
+    }
+
+    let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+    let mut sweep_at = Some(Instant::now() + settle);
+    let mut last_look = Instant::now();
+    while !options.control.is_cancelled() {
+        match rx.recv_timeout(TICK) {
+            Ok(Ok(event)) => note(&mut pending, event, &target),
+            Ok(Err(e)) => warn!("watch error: {e}"),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_look.elapsed() < TICK {
+            continue;
+        }
+        last_look = Instant::now();
+
+        // What arrived before the watch began, or was left by a batch that
+        // was too large: everything no event is pending for is complete.
+        if sweep_at.is_some_and(|at| at <= Instant::now()) {
+            sweep_at = None;
+            let pending = &pending;
+            let more = sort_batch(
+                &sources,
+                &target,
+                &options,
+                |path| !pending.contains_key(path),
+                &mut on_event,
+            );
+            if more {
+                sweep_at = Some(Instant::now());
+            }
+            continue;
+        }
+        let ready = take_ready(&mut pending, settle);
+        if !ready.is_empty() {
+            debug!("{} paths complete", ready.len());
+            let pending = &pending;
+            let more = sort_batch(
+                &sources,
+                &target,
+                &options,
+                |path| {
+                    ready.contains(path)
+                        || (!pending.contains_key(path)
+                            && path.ancestors().any(|dir| ready.contains(dir)))
+                },
+                &mut on_event,
+            );
+            if more {
+                sweep_at = Some(Instant::now());
+            }
+        }
+    }
+    info!("watch stopped");
+    Ok(())
+}
+
+/// Track the paths of `event` that a writer finished or is still writing.
+fn note(pending: &mut HashMap<PathBuf, Pending>, event: notify::Event, target: &Path) {
+    let closed = match event.kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both)) => true,
+        // Directories count when moved in whole; made here, their files
+        // come with events of their own.
+        EventKind::Create(CreateKind::Folder) => return,
+        EventKind::Modify(ModifyKind::Name(_) | ModifyKind::Metadata(_)) => return,
+        EventKind::Create(_) | EventKind::Modify(_) => false,
+        _ => return,
+    };
+    // A rename reports where from, then where to.
+    let paths = match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => &event.paths[1..],
+        _ => &event.paths[..],
+    };
+    for path in paths.iter().filter(|path| !path.starts_with(target)) {
+        let file = pending.entry(path.clone()).or_insert(Pending {
+            stamp: None,
+            since: Instant::now(),
+            closed: false,
+        });
+        file.since = Instant::now();
+        file.closed = closed;
+    }
+}
+
+/// The pending paths that are complete: closed and unchanged for `settle`.
+/// Paths that are gone are forgotten.
+fn take_ready(pending: &mut HashMap<PathBuf, Pending>, settle: Duration) -> HashSet<PathBuf> {
+    let mut ready = HashSet::new();
+    pending.retain(|path, file| {
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        let stamp = (
+            metadata.len(),
+            metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        );
+        if file.stamp != Some(stamp) {
+            file.stamp = Some(stamp);
+            file.since = Instant::now();
+            return true;
+        }
+        if file.closed && file.since.elapsed() >= settle {
+            ready.insert(path.clone());
+            return false;
+        }
+        true
+    });
+    ready
+}
+
+/// Plan and execute the files of `sources` that `claim` accepts and no
+/// other watcher has locked, up to `BATCH_LIMIT` of them, reporting the run
+/// unless there was nothing to do. `true` when files were left for another
+/// batch and this one got somewhere, so that one will too.
+fn sort_batch(
+    sources: &[PathBuf],
+    target: &Path,
+    options: &ProcessOptions,
+    mut claim: impl FnMut(&Path) -> bool,
+    on_event: &mut impl FnMut(WatchEvent),
+) -> bool {
+    let mut locks = Vec::new();
+    let mut full = false;
+    let sorted = take_turn(target).and_then(|_turn| {
+        let plan = plan_claimed(
+            sources,
+            target,
+            options,
+            |path| {
+                if locks.len() >= BATCH_LIMIT {
+                    full = true;
+                    return false;
+                }
+                claim(path) && lock(path, &mut locks)
+            },
+            |_, _| {},
+        )?;
+        if plan.items.is_empty() {
+            return Ok(None);
+        }
+        execute(plan, options, |_, _| {}).map(Some)
+    });
+    match sorted {
+        Ok(Some(summary)) => {
+            let stored = summary.total() - summary.collisions_skipped - summary.failed.len();
+            let more = full && stored > 0 && !summary.cancelled;
+            on_event(WatchEvent::Sorted(summary));
+            more
+        }
+        Ok(None) => false,
+        Err(e) if options.control.is_cancelled() => {
+            debug!("batch stopped: {e:#}");
+            false
+        }
+        Err(e) => {
+            on_event(WatchEvent::Failed(format!("{e:#}")));
+            false
+        }
+    }
+}
+
+/// Wait for the target's lock; held until the returned file is dropped.
+fn take_turn(target: &Path) -> anyhow::Result<File> {
+    let path = target.join(LOCK_FILENAME);
+    let file =
+        File::create(&path).with_context(|| format!("cannot create lock '{}'", path.display()))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Lock the file at `path` for this batch, adding the lock to `locks`;
+/// `false` if another watcher holds it.
+#[cfg(unix)]
+fn lock(path: &Path, locks: &mut Vec<File>) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    if file.try_lock().is_err() {
+        debug!("'{}' is locked by another watcher", path.display());
+        return false;
+    }
+    locks.push(file);
+    true
+}
+
+/// Elsewhere a lock would keep the batch itself from reading the file; the
+/// target's lock has to do.
+#[cfg(not(unix))]
+fn lock(_path: &Path, _locks: &mut Vec<File>) -> bool {
+    true
+}
@@ -3,12 +3,13 @@ use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver};
 
 use crossterm::event::{KeyCode, KeyEvent};
+use indicatif::HumanBytes;
 use ratatui::backend::Backend;
 use ratatui::prelude::Terminal;
 use ratatui::widgets::TableState;
 
 use crate::sorter::{ItemOutcome, Plan, ProcessOptions, ProcessSummary, TransferMode};
-use crate::worker::{self, WorkerEvent};
+use crate::worker::{self, WorkerEvent, WorkerHandle};
 
 use super::{events, ui};
 
@@ -52,6 +53,8 @@ pub struct App {
     pub(crate) source_dir: String,
     pub(crate) target_dir: String,
     pub(crate) transfer_mode: TransferMode,
+    /// Leave out what earlier runs already imported into the target (`i`).
+    pub(crate) incremental: bool,
     pub(crate) error: Option<String>,
     /// Display copy of the plan; a clone is handed to the execute worker.
     pub(crate) plan: Option<Plan>,
@@ -60,7 +63,16 @@ pub struct App {
     pub(crate) progress: Option<(usize, usize)>,
     pub(crate) summary: Option<ProcessSummary>,
     pub(crate) table: TableState,
+    /// Enter was pressed once despite the space warning; the next Enter
+    /// starts anyway.
+    space_confirmed: bool,
+    /// The running scan or execute was paused with `p`.
+    pub(crate) paused: bool,
+    /// The review lists what the scan rules left out, and what was already
+    /// imported, instead of the plan (`x`).
+    pub(crate) show_excluded: bool,
     rx: Option<Receiver<WorkerEvent>>,
+    worker: Option<WorkerHandle>,
 }
 
 impl App {
@@ -73,13 +85,18 @@ impl App {
             source_dir,
             target_dir,
             transfer_mode: TransferMode::Copy,
+            incremental: false,
             error: None,
             plan: None,
             outcomes: Vec::new(),
             progress: None,
             summary: None,
             table: TableState::default(),
+            space_confirmed: false,
+            paused: false,
+            show_excluded: false,
             rx: None,
+            worker: None,
         }
     }
 
@@ -117,14 +134,12 @@ impl App {
                     self.table.select(Some(0));
                 }
                 self.plan = Some(plan);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.screen = Screen::Review;
             }
             WorkerEvent::PlanFailed(reason) => {
                 self.error = Some(reason);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.screen = Screen::Setup;
             }
             WorkerEvent::ItemDone { index, outcome } => {
@@ -140,8 +155,11 @@ impl App {
                         *slot = Some(outcome);
                     }
                 }
+                // Parallel runs report items in completion order, so count
+                // them instead of trusting the index.
                 let total = self.plan.as_ref().map(|p| p.items.len()).unwrap_or(0);
-                self.progress = Some((index + 1, total));
+                let done = self.progress.map_or(0, |(done, _)| done) + 1;
+                self.progress = Some((done.min(total), total));
             }
             // Stay on the review screen so the status column and the summary
             // line remain visible; the user can adjust the selection and run
@@ -150,31 +168,55 @@ impl App {
                 self.deselect_completed();
                 self.summary = Some(summary);
                 self.error = None;
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
                 self.screen = Screen::Review;
             }
             WorkerEvent::ExecuteFailed(reason) => {
                 self.error = Some(reason);
-                self.progress = None;
-                self.rx = None;
+                self.worker_stopped();
+                self.screen = Screen::Review;
+            }
+            WorkerEvent::Paused(paused) => self.paused = paused,
+            WorkerEvent::Cancelled(None) => {
+                self.worker_stopped();
+                self.screen = Screen::Setup;
+            }
+            // Like `Finished`: what was done stays marked and deselected,
+            // so ↵ processes the rest.
+            WorkerEvent::Cancelled(Some(summary)) => {
+                self.deselect_completed();
+                self.summary = Some(summary);
+                self.error = None;
+                self.worker_stopped();
                 self.screen = Screen::Review;
             }
         }
     }
 
+    fn worker_stopped(&mut self) {
+        self.progress = None;
+        self.paused = false;
+        self.rx = None;
+        self.worker = None;
+    }
+
     /// Handle events like key presses
     pub(crate) fn handle_event(&mut self, event: KeyEvent) {
         match self.screen {
             Screen::Setup => self.handle_setup_key(event),
-            // Workers run detached; quitting mid-scan is safe (planning is
-            // read-only), quitting mid-execute is deliberately not offered.
-            Screen::Scanning => {
-                if matches!(event.code, KeyCode::Char('q') | KeyCode::Esc) {
-                    self.should_exit = true;
-                }
-            }
-            Screen::Executing => {}
+            // Quitting mid-scan is safe (planning is read-only); a running
+            // execute can only be stopped between two files.
+            Screen::Scanning => match event.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.should_exit = true,
+                KeyCode::Char('p') => self.toggle_pause(),
+                KeyCode::Char('c') => self.cancel(),
+                _ => {}
+            },
+            Screen::Executing => match event.code {
+                KeyCode::Char('p') => self.toggle_pause(),
+                KeyCode::Char('c') | KeyCode::Esc => self.cancel(),
+                _ => {}
+            },
             Screen::Review => self.handle_review_key(event),
         }
     }
@@ -186,6 +228,8 @@ impl App {
                 KeyCode::Char('1') => self.focus = SetupFocus::Source,
                 KeyCode::Char('2') => self.focus = SetupFocus::Target,
                 KeyCode::Char('m') => self.toggle_mode(),
+                KeyCode::Char('l') => self.cycle_link_mode(),
+                KeyCode::Char('i') => self.incremental = !self.incremental,
                 KeyCode::Char('s') | KeyCode::Enter => self.start_scan(),
                 _ => {}
             },
@@ -207,6 +251,9 @@ impl App {
     }
 
     fn handle_review_key(&mut self, event: KeyEvent) {
+        if event.code != KeyCode::Enter {
+            self.space_confirmed = false;
+        }
         match event.code {
             KeyCode::Char('q') | KeyCode::Esc => self.should_exit = true,
             KeyCode::Char('j') | KeyCode::Down => self.select_next(),
@@ -214,16 +261,30 @@ impl App {
             KeyCode::Char(' ') => self.toggle_current(),
             KeyCode::Char('a') => self.toggle_all(),
             KeyCode::Char('m') => self.toggle_mode(),
+            KeyCode::Char('l') => self.cycle_link_mode(),
             KeyCode::Char('s') => self.start_scan(),
+            KeyCode::Char('x') => self.show_excluded = !self.show_excluded,
             KeyCode::Enter => self.start_execute(),
             _ => {}
         }
     }
 
+    /// `m` flips between the two everyday modes; from a link mode it lands
+    /// on Move, so a second press is always back to the safe Copy.
     fn toggle_mode(&mut self) {
         self.transfer_mode = match self.transfer_mode {
-            TransferMode::Copy => TransferMode::Move,
             TransferMode::Move => TransferMode::Copy,
+            _ => TransferMode::Move,
+        };
+    }
+
+    /// `l` steps through the clone/link modes and back to Copy.
+    fn cycle_link_mode(&mut self) {
+        self.transfer_mode = match self.transfer_mode {
+            TransferMode::Copy | TransferMode::Move => TransferMode::Reflink,
+            TransferMode::Reflink => TransferMode::Hardlink,
+            TransferMode::Hardlink => TransferMode::Symlink,
+            TransferMode::Symlink => TransferMode::Copy,
         };
     }
 
@@ -316,9 +377,22 @@ impl App {
             .unwrap_or(0)
     }
 
+    fn toggle_pause(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.set_paused(!self.paused);
+        }
+    }
+
+    fn cancel(&mut self) {
+        if let Some(worker) = &self.worker {
+            worker.cancel();
+        }
+    }
+
     fn options(&self) -> ProcessOptions {
         ProcessOptions {
             mode: self.transfer_mode,
+            incremental: self.incremental,
             ..ProcessOptions::default()
         }
     }
@@ -330,21 +404,33 @@ impl App {
         self.plan = None;
         self.summary = None;
         self.outcomes.clear();
+        self.show_excluded = false;
         self.progress = Some((0, 0));
         self.screen = Screen::Scanning;
-        worker::spawn_plan(
-            PathBuf::from(&self.source_dir),
+        self.worker = Some(worker::spawn_plan(
+            worker::split_sources(&self.source_dir),
             PathBuf::from(&self.target_dir),
             self.options(),
             tx,
             || {},
-        );
+        ));
     }
 
     fn start_execute(&mut self) {
         let Some(plan) = self.plan.clone() else {
             return;
         };
+        let space = plan.space_check(self.transfer_mode);
+        if !space.is_sufficient() && !self.space_confirmed {
+            self.error = Some(format!(
+                "Not enough space on the target: needs {} but only {} free — press ↵ again to start anyway",
+                HumanBytes(space.needed),
+                HumanBytes(space.available.unwrap_or_default())
+            ));
+            self.space_confirmed = true;
+            return;
+        }
+        self.space_confirmed = false;
         let (tx, rx) = mpsc::channel();
         self.rx = Some(rx);
         self.error = None;
@@ -352,7 +438,7 @@ impl App {
         // earlier stay marked done in the status column.
         self.progress = Some((0, plan.items.len()));
         self.screen = Screen::Executing;
-        worker::spawn_execute(plan, self.options(), tx, || {});
+        self.worker = Some(worker::spawn_execute(plan, self.options(), tx, || {}));
     }
 }
 
@@ -373,17 +459,26 @@ mod tests {
                     PathBuf::from(format!("/tmp/src/photo{i}.jpg")),
                     PathBuf::from("/tmp/dst"),
                 ),
+                source_root: PathBuf::from("/tmp/src"),
                 action: PlannedAction::Unsorted {
                     reason: "test".into(),
                 },
                 planned_target: PathBuf::from(format!("/tmp/dst/unsorted/photo{i}.jpg")),
                 selected: true,
+                size: 1024,
+                already_stored: false,
+                merge: None,
             })
             .collect();
         Plan {
-            source: PathBuf::from("/tmp/src"),
+            sources: vec![PathBuf::from("/tmp/src")],
             target: PathBuf::from("/tmp/dst"),
             items,
+            excluded: Vec::new(),
+            imported: Vec::new(),
+            scan_errors: Vec::new(),
+            available_space: None,
+            same_filesystem: false,
         }
     }
 
@@ -458,6 +553,33 @@ mod tests {
         assert_eq!(app.selected_count(), 1, "transferred item auto-deselected");
     }
 
+    #[test]
+    fn cancelled_run_returns_to_review_with_the_rest_selected() {
+        let mut app = app();
+        app.apply_event(WorkerEvent::PlanReady(plan_with(2)));
+        app.screen = Screen::Executing;
+        app.apply_event(WorkerEvent::Paused(true));
+        assert!(app.paused);
+        app.apply_event(WorkerEvent::ItemDone {
+            index: 0,
+            outcome: ItemOutcome::Transferred {
+                target: "t".into(),
+                low_confidence: false,
+            },
+        });
+
+        app.apply_event(WorkerEvent::Cancelled(Some(ProcessSummary {
+            transferred: 1,
+            cancelled: true,
+            ..ProcessSummary::default()
+        })));
+
+        assert_eq!(app.screen, Screen::Review);
+        assert!(!app.paused);
+        assert_eq!(app.selected_count(), 1, "only the unprocessed item is left");
+        assert!(app.summary.as_ref().is_some_and(|s| s.cancelled));
+    }
+
     #[test]
     fn completed_items_cannot_be_reselected() {
         // Guards the recovery-critical property: once a file is copied, no
@@ -559,4 +681,34 @@ mod tests {
         app.handle_event(KeyEvent::from(KeyCode::Char('m')));
         assert_eq!(app.transfer_mode, TransferMode::Copy);
     }
+
+    #[test]
+    fn start_on_a_too_small_target_needs_a_second_enter() {
+        let mut app = app();
+        let mut plan = plan_with(2);
+        plan.available_space = Some(1024);
+        plan.target = testdir::testdir!();
+        app.apply_event(WorkerEvent::PlanReady(plan));
+
+        app.handle_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.screen, Screen::Review);
+        assert!(app.error.as_deref().unwrap().contains("Not enough space"));
+
+        app.handle_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.screen, Screen::Executing);
+    }
+
+    #[test]
+    fn link_key_cycles_through_link_modes_back_to_copy() {
+        let mut app = app();
+        for expected in [
+            TransferMode::Reflink,
+            TransferMode::Hardlink,
+            TransferMode::Symlink,
+            TransferMode::Copy,
+        ] {
+            app.handle_event(KeyEvent::from(KeyCode::Char('l')));
+            assert_eq!(app.transfer_mode, expected);
+        }
+    }
 }
@@ -12,9 +12,10 @@ use app::App;
 use crate::cli::args::Args;
 
 pub async fn run_tui(args: Args) -> Result<()> {
+    let sources = crate::worker::join_sources(&crate::cli::source_dirs(&args, None))?;
     let terminal = init()?;
 
-    App::new(args.source_dir, args.target_dir).run(terminal)?;
+    App::new(sources, args.target_dir).run(terminal)?;
 
     restore()?;
 
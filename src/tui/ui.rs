@@ -1,3 +1,4 @@
+use indicatif::HumanBytes;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::prelude::Stylize;
 use ratatui::style::{Color, Modifier, Style};
@@ -28,7 +29,7 @@ pub fn draw(frame: &mut Frame, app: &App) {
         frame,
         source_area,
         "1",
-        " Source directory",
+        " Source directories (separated like PATH)",
         &app.source_dir,
         app.screen == Screen::Setup && app.focus == SetupFocus::Source,
     );
@@ -46,6 +47,7 @@ pub fn draw(frame: &mut Frame, app: &App) {
         Screen::Scanning => draw_placeholder(frame, content_area, "Scanning source directory…"),
         // The review table stays visible during execution so its Status
         // column can update live.
+        Screen::Review if app.show_excluded => draw_excluded(frame, content_area, app),
         Screen::Review | Screen::Executing => draw_review_table(frame, content_area, app),
     }
 
@@ -94,6 +96,11 @@ fn draw_setup_content(frame: &mut Frame, area: Rect, app: &App) {
             mode_span(app.transfer_mode),
             "  (files are copied by default — the source stays untouched)".into(),
         ]),
+        Line::from(if app.incremental {
+            "Files already imported into the target are skipped."
+        } else {
+            "Files already imported into the target are sorted again."
+        }),
         Line::from(""),
         Line::from("Press [s] to scan. Nothing is written before you confirm the plan."),
     ];
@@ -118,6 +125,15 @@ fn mode_span(mode: TransferMode) -> Span<'static> {
     match mode {
         TransferMode::Copy => Span::styled("COPY", Style::default().fg(Color::Green).bold()),
         TransferMode::Move => Span::styled("MOVE", Style::default().fg(Color::Red).bold()),
+        TransferMode::Reflink => {
+            Span::styled("REFLINK", Style::default().fg(Color::Green).bold())
+        }
+        TransferMode::Hardlink => {
+            Span::styled("HARDLINK", Style::default().fg(Color::Yellow).bold())
+        }
+        TransferMode::Symlink => {
+            Span::styled("SYMLINK", Style::default().fg(Color::Yellow).bold())
+        }
     }
 }
 
@@ -132,11 +148,78 @@ fn draw_placeholder(frame: &mut Frame, area: Rect, text: &str) {
         .render(area, frame.buffer_mut());
 }
 
+fn draw_excluded(frame: &mut Frame, area: Rect, app: &App) {
+    let (excluded, imported, errors) = match &app.plan {
+        Some(plan) => (
+            &plan.excluded[..],
+            &plan.imported[..],
+            &plan.scan_errors[..],
+        ),
+        None => (&[][..], &[][..], &[][..]),
+    };
+    if excluded.is_empty() && imported.is_empty() && errors.is_empty() {
+        draw_placeholder(frame, area, "The scan rules left nothing out.");
+        return;
+    }
+    // Unreadable paths first: the plan silently lacks whatever is below them.
+    let error_rows = errors.iter().map(|error| {
+        Row::new(vec![
+            Cell::from(error.path.to_string_lossy().into_owned()),
+            Cell::from(Span::styled(
+                format!("unreadable: {}", error.message),
+                Style::default().fg(Color::Red),
+            )),
+        ])
+    });
+    let rule_rows = excluded.iter().map(|excluded| {
+        Row::new(vec![
+            Cell::from(excluded.path.to_string_lossy().into_owned()),
+            Cell::from(excluded.rule.to_string()),
+        ])
+    });
+    let imported_rows = imported.iter().map(|file| {
+        Row::new(vec![
+            Cell::from(file.path.to_string_lossy().into_owned()),
+            Cell::from(format!("already imported: {}", file.target.display())),
+        ])
+    });
+    let rows = error_rows.chain(rule_rows).chain(imported_rows);
+    let header = ["Left out", "Rule"]
+        .into_iter()
+        .map(|h| Cell::from(Text::from(h)))
+        .collect::<Row>()
+        .style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+        .height(1);
+    let widths = [Constraint::Fill(2), Constraint::Fill(1)];
+    Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::new()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(format!(
+                    " {} paths left out by scan rules, {} already imported, {} unreadable ",
+                    excluded.len(),
+                    imported.len(),
+                    errors.len()
+                )),
+        )
+        .render(area, frame.buffer_mut());
+}
+
 fn draw_review_table(frame: &mut Frame, area: Rect, app: &App) {
     let Some(plan) = &app.plan else {
         draw_placeholder(frame, area, "No plan — press [s] to scan.");
         return;
     };
+    if plan.items.is_empty() && !plan.imported.is_empty() {
+        let message = format!(
+            "Nothing new: all {} files were already imported — [x] lists them.",
+            plan.imported.len()
+        );
+        draw_placeholder(frame, area, &message);
+        return;
+    }
     if plan.items.is_empty() {
         draw_placeholder(frame, area, "No supported media files found in the source.");
         return;
@@ -154,7 +237,8 @@ fn draw_review_table(frame: &mut Frame, area: Rect, app: &App) {
         .style(header_style)
         .height(1);
 
-    let source_prefix = plan.source.to_string_lossy().into_owned();
+    // With several sources, rows keep the name of the source they came from.
+    let several_sources = plan.sources.len() > 1;
     let target_prefix = plan.target.to_string_lossy().into_owned();
 
     let rows = plan.items.iter().enumerate().map(|(i, item)| {
@@ -167,9 +251,13 @@ fn draw_review_table(frame: &mut Frame, area: Rect, app: &App) {
         };
         // Always render via source_full(): Image.source_path alone is only
         // the parent directory.
-        let source = relative_to(&item.image.source_full(), &source_prefix);
+        let root = match item.source_root.parent() {
+            Some(parent) if several_sources => parent,
+            _ => &item.source_root,
+        };
+        let source = relative_to(&item.image.source_full(), &root.to_string_lossy());
         let (date_cell, via_cell) = match &item.action {
-            PlannedAction::Sorted { date, date_source } => {
+            PlannedAction::Sorted { date, date_source, .. } => {
                 let color = if date_source.is_low_confidence() {
                     Color::Yellow
                 } else {
@@ -210,15 +298,54 @@ fn draw_review_table(frame: &mut Frame, area: Rect, app: &App) {
         Constraint::Fill(10),
         Constraint::Length(2),
     ];
+    let space = plan.space_check(app.transfer_mode);
+    let space_text = match space.available {
+        Some(available) => format!(
+            "needs {} of {} free ",
+            HumanBytes(space.needed),
+            HumanBytes(available)
+        ),
+        None => format!("needs {} ", HumanBytes(space.needed)),
+    };
+    let space_style = if space.is_sufficient() {
+        Style::default()
+    } else {
+        Style::default().fg(Color::Red).bold()
+    };
+    let title = Title::from(vec![
+        Span::raw(format!(
+            " {} files, {} selected — mode: {:?} — ",
+            plan.items.len(),
+            app.selected_count(),
+            app.transfer_mode
+        )),
+        Span::styled(space_text, space_style),
+    ]);
+    let title = match (
+        plan.excluded.len(),
+        plan.imported.len(),
+        plan.scan_errors.len(),
+    ) {
+        (0, 0, 0) => title,
+        (excluded, imported, errors) => {
+            let mut spans = title.content.spans;
+            if excluded > 0 {
+                spans.push(Span::raw(format!("— {excluded} left out by scan rules ")));
+            }
+            if imported > 0 {
+                spans.push(Span::raw(format!("— {imported} already imported ")));
+            }
+            if errors > 0 {
+                spans.push(Span::styled(
+                    format!("— {errors} unreadable [x] "),
+                    Style::default().fg(Color::Red).bold(),
+                ));
+            }
+            Title::from(spans)
+        }
+    };
     let table = Table::new(rows, widths)
-        .block(
-            Block::new().borders(Borders::ALL).title(format!(
-                " {} files, {} selected — mode: {:?} ",
-                plan.items.len(),
-                app.selected_count(),
-                app.transfer_mode
-            )),
-        )
+        .block(Block::new().borders(Borders::ALL).title(title))
         .header(header)
         .highlight_style(selected_style)
         .highlight_spacing(HighlightSpacing::Always);
@@ -249,7 +376,7 @@ fn status_symbol(outcome: Option<&ItemOutcome>) -> Cell<'static> {
     }
 }
 
-fn relative_to<'a>(path: &'a str, prefix: &str) -> String {
+fn relative_to(path: &str, prefix: &str) -> String {
     path.strip_prefix(prefix)
         .map(|p| p.trim_start_matches('/').to_string())
         .unwrap_or_else(|| path.to_string())
@@ -281,10 +408,7 @@ fn draw_status(frame: &mut Frame, area: Rect, app: &App) {
     }
 
     if let Some(summary) = &app.summary {
-        let verb = match app.transfer_mode {
-            TransferMode::Copy => "Copied",
-            TransferMode::Move => "Moved",
-        };
+        let verb = app.transfer_mode.past_tense();
         let mut spans = vec![Span::styled(
             format!(" {verb} {}/{}", summary.transferred, summary.total()),
             Style::default().fg(Color::Green),
@@ -313,6 +437,18 @@ fn draw_status(frame: &mut Frame, area: Rect, app: &App) {
                 Style::default().fg(Color::Red),
             ));
         }
+        if summary.scan_errors > 0 {
+            spans.push(Span::styled(
+                format!(" · {} unreadable", summary.scan_errors),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        if summary.cancelled {
+            spans.push(Span::styled(
+                " · stopped early",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
         spans.push(Span::styled(
             "  — adjust selection and press ↵ to continue",
             Style::default().fg(Color::DarkGray),
@@ -331,13 +467,31 @@ fn draw_actions(frame: &mut Frame, area: Rect, app: &App) {
             " edit dirs ".into(),
             key("m"),
             " copy/move ".into(),
+            key("l"),
+            " link modes ".into(),
+            key("i"),
+            " incremental ".into(),
             key("s"),
             " scan ".into(),
             key("q"),
             " quit ".into(),
         ]
         .into(),
-        Screen::Scanning => vec!["scanning… ".into(), key("q"), " quit ".into()].into(),
+        Screen::Scanning => vec![
+            if app.paused {
+                "paused "
+            } else {
+                "scanning… "
+            }
+            .into(),
+            key("p"),
+            if app.paused { " continue " } else { " pause " }.into(),
+            key("c"),
+            " cancel ".into(),
+            key("q"),
+            " quit ".into(),
+        ]
+        .into(),
         Screen::Review => vec![
             key("␣"),
             " toggle ".into(),
@@ -345,15 +499,36 @@ fn draw_actions(frame: &mut Frame, area: Rect, app: &App) {
             " all/none ".into(),
             key("m"),
             " copy/move ".into(),
+            key("l"),
+            " link modes ".into(),
             key("↵"),
             " start ".into(),
             key("s"),
             " rescan ".into(),
+            key("x"),
+            if app.show_excluded {
+                " plan "
+            } else {
+                " left out "
+            }
+            .into(),
             key("q"),
             " quit ".into(),
         ]
         .into(),
-        Screen::Executing => vec!["processing — please wait…".into()].into(),
+        Screen::Executing => vec![
+            if app.paused {
+                "paused "
+            } else {
+                "processing… "
+            }
+            .into(),
+            key("p"),
+            if app.paused { " continue " } else { " pause " }.into(),
+            key("c"),
+            " stop after current file ".into(),
+        ]
+        .into(),
     };
 
     Paragraph::new(actions)
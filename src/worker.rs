@@ -6,12 +6,17 @@
 //! send: the TUI passes a no-op (its 16 ms input poll doubles as a tick),
 //! the GUI passes `ctx.request_repaint()` so frames are only drawn when
 //! something changed.
+//!
+//! Each spawn returns a `WorkerHandle` for cancelling or pausing the run;
+//! both take effect between two files.
 
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
 use std::thread;
 
-use crate::sorter::{self, ItemOutcome, Plan, ProcessOptions, ProcessSummary};
+use crate::error::AppError;
+use crate::sorter::control::RunControl;
+use crate::sorter::{self, journal, ItemOutcome, Plan, ProcessOptions, ProcessSummary};
 
 pub enum WorkerEvent {
     ScanProgress { done: usize, total: usize },
@@ -20,30 +25,88 @@ pub enum WorkerEvent {
     ItemDone { index: usize, outcome: ItemOutcome },
     Finished(ProcessSummary),
     ExecuteFailed(String),
+    /// The run was paused (`true`) or picked up again.
+    Paused(bool),
+    /// The run stopped early on request: the partial summary of an execute,
+    /// `None` for a scan.
+    Cancelled(Option<ProcessSummary>),
 }
 
-/// Run `sorter::plan` on a background thread, streaming progress events.
+/// Cancels or pauses the run a worker was spawned for.
+#[derive(Clone)]
+pub struct WorkerHandle {
+    control: RunControl,
+    tx: Sender<WorkerEvent>,
+}
+
+impl WorkerHandle {
+    fn new(options: &ProcessOptions, tx: &Sender<WorkerEvent>) -> Self {
+        Self {
+            control: options.control.clone(),
+            tx: tx.clone(),
+        }
+    }
+
+    /// The worker answers with `Cancelled` once the file in flight is done.
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        if paused {
+            self.control.pause();
+        } else {
+            self.control.resume();
+        }
+        let _ = self.tx.send(WorkerEvent::Paused(paused));
+    }
+}
+
+/// The source directories of the setup screens' source field, which lists
+/// them like `PATH` does (`:`-separated, `;` on Windows).
+pub fn split_sources(field: &str) -> Vec<PathBuf> {
+    std::env::split_paths(field)
+        .filter(|path| !path.as_os_str().is_empty())
+        .collect()
+}
+
+/// The source field listing `sources`; fails when one contains the
+/// separator and could not be told apart from the others.
+pub fn join_sources(sources: &[PathBuf]) -> anyhow::Result<String> {
+    match std::env::join_paths(sources) {
+        Ok(joined) => Ok(joined.to_string_lossy().into_owned()),
+        Err(e) => anyhow::bail!("cannot list the sources in one field: {e}"),
+    }
+}
+
+/// Run `sorter::plan_sources` on a background thread, streaming progress
+/// events.
 pub fn spawn_plan(
-    source: PathBuf,
+    sources: Vec<PathBuf>,
     target: PathBuf,
     options: ProcessOptions,
     tx: Sender<WorkerEvent>,
     notify: impl Fn() + Send + Sync + 'static,
-) {
+) -> WorkerHandle {
+    let handle = WorkerHandle::new(&options, &tx);
     thread::spawn(move || {
         let progress_tx = tx.clone();
-        let result = sorter::plan(&source, &target, &options, |done, total| {
+        let result = sorter::plan_sources(&sources, &target, &options, |done, total| {
             // A dropped receiver (UI closed) is not an error worth handling.
             let _ = progress_tx.send(WorkerEvent::ScanProgress { done, total });
             notify();
         });
         let event = match result {
             Ok(plan) => WorkerEvent::PlanReady(plan),
+            Err(e) if matches!(e.downcast_ref(), Some(AppError::Cancelled())) => {
+                WorkerEvent::Cancelled(None)
+            }
             Err(e) => WorkerEvent::PlanFailed(format!("{e:#}")),
         };
         let _ = tx.send(event);
         notify();
     });
+    handle
 }
 
 /// Run `sorter::execute` on a background thread, streaming one event per
@@ -53,8 +116,10 @@ pub fn spawn_execute(
     options: ProcessOptions,
     tx: Sender<WorkerEvent>,
     notify: impl Fn() + Send + 'static,
-) {
+) -> WorkerHandle {
+    let handle = WorkerHandle::new(&options, &tx);
     thread::spawn(move || {
+        let target = plan.target.clone();
         let result = sorter::execute(plan, &options, |index, outcome| {
             let _ = tx.send(WorkerEvent::ItemDone {
                 index,
@@ -63,12 +128,19 @@ pub fn spawn_execute(
             notify();
         });
         let event = match result {
+            // The UIs continue by running the plan again, not with `resume`,
+            // so the journal would only block that.
+            Ok(summary) if summary.cancelled => {
+                journal::discard(&target);
+                WorkerEvent::Cancelled(Some(summary))
+            }
             Ok(summary) => WorkerEvent::Finished(summary),
             Err(e) => WorkerEvent::ExecuteFailed(format!("{e:#}")),
         };
         let _ = tx.send(event);
         notify();
     });
+    handle
 }
 
 #[cfg(test)]
@@ -89,7 +161,7 @@ mod tests {
 
         // when
         spawn_plan(
-            tmp.clone(),
+            vec![tmp.clone()],
             tmp.join("sorted"),
             ProcessOptions::default(),
             tx,
@@ -119,7 +191,7 @@ mod tests {
 
         // when
         spawn_plan(
-            PathBuf::from("/this/does/not/exist"),
+            vec![PathBuf::from("/this/does/not/exist")],
             PathBuf::from("/tmp/never"),
             ProcessOptions::default(),
             tx,
@@ -137,4 +209,29 @@ mod tests {
             _ => panic!("expected PlanFailed"),
         }
     }
+
+    #[test]
+    fn cancelled_spawn_plan_reports_cancelled() {
+        // given
+        let tmp =
+            std::env::temp_dir().join(format!("exif-sorter-worker-cancel-{}", std::process::id()));
+        std::fs::create_dir_all(&tmp).unwrap();
+        std::fs::write(tmp.join("a.jpg"), b"x").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let options = ProcessOptions::default();
+        options.control.cancel();
+
+        // when
+        spawn_plan(vec![tmp.clone()], tmp.join("sorted"), options, tx, || {});
+
+        // then
+        match rx
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("worker sent nothing")
+        {
+            WorkerEvent::Cancelled(None) => {}
+            _ => panic!("expected Cancelled"),
+        }
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
 }
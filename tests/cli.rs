@@ -213,6 +213,128 @@ fn should_revert_a_copy_run_from_manifest() {
     assert!(testfile.exists(), "revert must never touch the source");
 }
 
+#[test]
+fn should_revert_a_symlink_run_without_touching_the_source() {
+    // Reverting a symlinked index must remove only the links; following
+    // them would delete the originals the index points at.
+    // given
+    let root: PathBuf = testdir::testdir!();
+    let target = root.join("sorted");
+    let testfile = root.join("dateTimeOriginal.png");
+    let _ = fs::copy(
+        Path::new("tests/data/dateTimeOriginal.png"),
+        testfile.as_path(),
+    );
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "cli",
+            "-s",
+            root.as_path().to_string_lossy().as_str(),
+            "-t",
+            target.as_path().to_string_lossy().as_str(),
+            "--mode",
+            "symlink",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Symlinked 1 of 1"));
+    let link = target.join("1991/1991-01-01/dateTimeOriginal.png");
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+
+    // when
+    let manifest = target.join("exif-sorter-manifest.csv");
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args(["revert", "-m", manifest.to_string_lossy().as_str()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reverted 1"));
+
+    // then
+    assert!(fs::symlink_metadata(&link).is_err(), "revert must remove the link");
+    assert!(testfile.exists(), "revert must never touch the link's source");
+}
+
+#[test]
+fn should_list_runs_and_revert_only_the_last_one() {
+    // given: the same source copied twice into one target
+    let root: PathBuf = testdir::testdir!();
+    let source = root.join("source");
+    let target = root.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy(
+        Path::new("tests/data/dateTimeOriginal.png"),
+        source.join("dateTimeOriginal.png"),
+    )
+    .unwrap();
+    for _ in 0..2 {
+        Command::cargo_bin("exif-sorter")
+            .unwrap()
+            .args([
+                "cli",
+                "-s",
+                source.to_string_lossy().as_str(),
+                "-t",
+                target.to_string_lossy().as_str(),
+            ])
+            .assert()
+            .success();
+    }
+
+    // when
+    let output = Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args(["-t", target.to_string_lossy().as_str(), "runs", "list"])
+        .output()
+        .unwrap();
+
+    // then: two finished runs, oldest first
+    let listing = String::from_utf8(output.stdout).unwrap();
+    let ids: Vec<&str> = listing
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+    assert_eq!(ids.len(), 2, "{listing}");
+    assert!(listing.contains("finished: 1 of 1 transferred"));
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "-t",
+            target.to_string_lossy().as_str(),
+            "runs",
+            "show",
+            ids[0],
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("copied"))
+        .stdout(predicate::str::contains("1991-01-01/dateTimeOriginal.png"));
+
+    // when
+    let manifest = target.join("exif-sorter-manifest.csv");
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "revert",
+            "-m",
+            manifest.to_string_lossy().as_str(),
+            "--last",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reverted 1"));
+
+    // then: the first run's copy is still there
+    assert!(target.join("1991/1991-01-01/dateTimeOriginal.png").exists());
+    assert_eq!(
+        fs::read_dir(target.join("1991/1991-01-01"))
+            .unwrap()
+            .count(),
+        1
+    );
+}
+
 #[test]
 fn should_apply_custom_folder_pattern() {
     // given
@@ -242,6 +364,104 @@ fn should_apply_custom_folder_pattern() {
     assert!(target.join("1991/01/dateTimeOriginal.png").exists());
 }
 
+#[test]
+fn should_explain_the_date_and_history_of_a_sorted_file() {
+    // given
+    let root: PathBuf = testdir::testdir!();
+    let source = root.join("source");
+    let target = root.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy(
+        Path::new("tests/data/dateTimeOriginal.png"),
+        source.join("dateTimeOriginal.png"),
+    )
+    .unwrap();
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "cli",
+            "-s",
+            source.to_string_lossy().as_str(),
+            "-t",
+            target.to_string_lossy().as_str(),
+        ])
+        .assert()
+        .success();
+    let sorted = target.join("1991/1991-01-01/dateTimeOriginal.png");
+
+    // when / then: the chosen date, the outranked file dates and the copy
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args(["explain", sorted.to_string_lossy().as_str()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "→ 1991-01-01 00:00:00  EXIF DateTimeOriginal",
+        ))
+        .stdout(predicate::str::contains("file modified date"))
+        .stdout(predicate::str::contains("not used"))
+        .stdout(predicate::str::contains(
+            "Routing  sorted by EXIF DateTimeOriginal 1991-01-01",
+        ))
+        .stdout(predicate::str::contains("History:"))
+        .stdout(predicate::str::contains("copied"));
+}
+
+#[test]
+fn should_query_the_catalog_as_csv_and_json() {
+    // given
+    let root: PathBuf = testdir::testdir!();
+    let source = root.join("source");
+    let target = root.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy(
+        Path::new("tests/data/dateTimeOriginal.png"),
+        source.join("dateTimeOriginal.png"),
+    )
+    .unwrap();
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "cli",
+            "-s",
+            source.to_string_lossy().as_str(),
+            "-t",
+            target.to_string_lossy().as_str(),
+        ])
+        .assert()
+        .success();
+
+    // when / then
+    Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "-t",
+            target.to_string_lossy().as_str(),
+            "query",
+            "--year",
+            "1991",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("path,source,category"))
+        .stdout(predicate::str::contains("1991-01-01/dateTimeOriginal.png"));
+    let output = Command::cargo_bin("exif-sorter")
+        .unwrap()
+        .args([
+            "-t",
+            target.to_string_lossy().as_str(),
+            "query",
+            "--low-confidence",
+            "--format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records, serde_json::json!([]));
+}
+
 #[test]
 fn should_read_settings_from_config_file() {
     // given: a config file selecting a custom pattern
@@ -385,3 +605,29 @@ fn should_rename_duplicates_instead_of_overwriting() {
     assert_eq!(count, 2);
 }
 
+
+#[test]
+fn should_count_the_paths_it_could_not_scan() {
+    // given: one readable photo and a followed symlink to nowhere
+    let root: PathBuf = testdir::testdir!();
+    let source = root.join("source");
+    let target = root.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("photo.png")).unwrap();
+    std::os::unix::fs::symlink(root.join("gone"), source.join("broken.jpg")).unwrap();
+
+    // when / then: the count matches the list below it
+    let mut cmd = Command::cargo_bin("exif-sorter").unwrap();
+    cmd.args([
+        "cli",
+        "--follow-symlinks",
+        "-s",
+        source.to_string_lossy().as_str(),
+        "-t",
+        target.to_string_lossy().as_str(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Could not scan 1 paths"))
+    .stdout(predicate::str::contains("broken.jpg"));
+}
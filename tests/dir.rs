@@ -1,4 +1,7 @@
-use exif_sorter::sorter::dir::scan_dir;
+use std::path::Path;
+
+use exif_sorter::error::AppError;
+use exif_sorter::sorter::dir::{scan, scan_dir, ExclusionRule, ScanOptions};
 
 #[test]
 fn scan_finds_uppercase_extensions() {
@@ -20,3 +23,200 @@ fn scan_finds_uppercase_extensions() {
     // then
     assert_eq!(entries.len(), 5, "expected all media files regardless of extension case");
 }
+
+#[test]
+fn scan_reports_the_rule_that_left_each_path_out() {
+    // given
+    let tmp = testdir::testdir!();
+    for dir in [
+        "trip/day1",
+        "Thumbnails",
+        ".cache",
+        "ignored_by_git",
+        "drafts",
+    ] {
+        std::fs::create_dir_all(tmp.join(dir)).unwrap();
+    }
+    for file in [
+        "top.jpg",
+        "clip.mov",
+        ".hidden.jpg",
+        "trip/a.jpg",
+        "trip/day1/deep.jpg",
+        "Thumbnails/t.jpg",
+        ".cache/c.jpg",
+        "ignored_by_git/g.jpg",
+        "drafts/d.jpg",
+    ] {
+        std::fs::write(tmp.join(file), b"x").unwrap();
+    }
+    // Photos in a git-ignored directory are still photos.
+    std::fs::write(tmp.join(".gitignore"), "ignored_by_git/\n").unwrap();
+    std::fs::write(tmp.join(".exifsorterignore"), "drafts/\n").unwrap();
+    let options = ScanOptions {
+        exclude: vec!["*.mov".to_string(), "Thumbnails".to_string()],
+        max_depth: Some(1),
+        ..ScanOptions::default()
+    };
+
+    // when
+    let scan = scan(&tmp, &options).unwrap();
+
+    // then
+    let mut found: Vec<String> = scan
+        .files
+        .iter()
+        .map(|entry| relative(&tmp, entry.path()))
+        .collect();
+    found.sort();
+    assert_eq!(found, ["ignored_by_git/g.jpg", "top.jpg", "trip/a.jpg"]);
+    let rules: Vec<(String, ExclusionRule)> = scan
+        .excluded
+        .iter()
+        .map(|excluded| (relative(&tmp, &excluded.path), excluded.rule.clone()))
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            (".cache".to_string(), ExclusionRule::Hidden),
+            (".hidden.jpg".to_string(), ExclusionRule::Hidden),
+            (
+                "Thumbnails".to_string(),
+                ExclusionRule::Excluded("Thumbnails".to_string())
+            ),
+            (
+                "clip.mov".to_string(),
+                ExclusionRule::Excluded("*.mov".to_string())
+            ),
+            (
+                "drafts".to_string(),
+                ExclusionRule::IgnoreFile {
+                    file: tmp.join(".exifsorterignore"),
+                    pattern: "drafts/".to_string()
+                }
+            ),
+            ("trip/day1".to_string(), ExclusionRule::MaxDepth(1)),
+        ]
+    );
+}
+
+#[test]
+fn scan_include_hidden_and_symlink_rules() {
+    // given
+    let tmp = testdir::testdir!();
+    let outside = tmp.join("outside");
+    let source = tmp.join("source");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::create_dir_all(source.join(".hidden")).unwrap();
+    std::fs::write(outside.join("linked.jpg"), b"x").unwrap();
+    std::fs::write(source.join("a.jpg"), b"x").unwrap();
+    std::fs::write(source.join("b.png"), b"x").unwrap();
+    std::fs::write(source.join(".hidden/h.jpg"), b"x").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&outside, source.join("link")).unwrap();
+
+    // when
+    let default = scan(&source, &ScanOptions::default()).unwrap();
+    let widened = scan(
+        &source,
+        &ScanOptions {
+            include: vec!["*.jpg".to_string()],
+            hidden: true,
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        },
+    )
+    .unwrap();
+
+    // then
+    assert_eq!(default.files.len(), 2);
+    #[cfg(unix)]
+    assert!(default
+        .excluded
+        .iter()
+        .any(|excluded| excluded.rule == ExclusionRule::Symlink));
+    let mut found: Vec<String> = widened
+        .files
+        .iter()
+        .map(|entry| relative(&source, entry.path()))
+        .collect();
+    found.sort();
+    #[cfg(unix)]
+    assert_eq!(found, [".hidden/h.jpg", "a.jpg", "link/linked.jpg"]);
+    assert!(widened
+        .excluded
+        .iter()
+        .any(|excluded| excluded.rule == ExclusionRule::NotIncluded
+            && excluded.path.ends_with("b.png")));
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(unix)]
+#[test]
+fn scan_collects_unreadable_paths_and_strict_fails_on_them() {
+    // given: a symlink to nowhere, followed
+    let tmp = testdir::testdir!();
+    std::fs::write(tmp.join("a.jpg"), b"x").unwrap();
+    std::os::unix::fs::symlink(tmp.join("gone"), tmp.join("broken.jpg")).unwrap();
+    let options = ScanOptions {
+        follow_symlinks: true,
+        ..ScanOptions::default()
+    };
+
+    // when
+    let scan_result = scan(&tmp, &options).unwrap();
+    let strict = scan(
+        &tmp,
+        &ScanOptions {
+            strict: true,
+            ..options
+        },
+    );
+
+    // then: the rest is scanned, the broken link is reported with its path
+    assert_eq!(scan_result.files.len(), 1);
+    assert_eq!(scan_result.errors.len(), 1);
+    assert!(scan_result.errors[0].path.ends_with("broken.jpg"));
+    let error = strict.err().expect("a strict scan fails");
+    assert!(matches!(
+        error.downcast_ref::<AppError>(),
+        Some(AppError::ScanFailed { path, .. }) if path.ends_with("broken.jpg")
+    ));
+}
+
+#[test]
+fn detect_content_takes_media_whatever_its_extension() {
+    // given: PhotoRec-style names, with no or the wrong extension
+    let tmp = testdir::testdir!();
+    std::fs::copy("tests/data/dateTimeOriginal.png", tmp.join("f1234567.dat")).unwrap();
+    std::fs::copy("tests/data/dateTimeOriginal.png", tmp.join("f1234568")).unwrap();
+    std::fs::write(tmp.join("notes.txt"), b"not media").unwrap();
+    std::fs::write(tmp.join("a.jpg"), b"x").unwrap();
+
+    // when
+    let by_extension = scan(&tmp, &ScanOptions::default()).unwrap();
+    let by_content = scan(
+        &tmp,
+        &ScanOptions {
+            detect_content: true,
+            ..ScanOptions::default()
+        },
+    )
+    .unwrap();
+
+    // then: the extension still counts; content adds what it misses
+    assert_eq!(by_extension.files.len(), 1);
+    let mut names: Vec<_> = by_content
+        .files
+        .iter()
+        .map(|entry| relative(&tmp, entry.path()))
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.jpg", "f1234567.dat", "f1234568"]);
+}
@@ -1,7 +1,9 @@
 use std::path::{Path, PathBuf};
 
 use chrono::NaiveDate;
-use exif_sorter::sorter::image::{DateSource, Image};
+use exif_sorter::sorter::checksum::file_checksum;
+use exif_sorter::sorter::image::{partial_path, DateSource, Image};
+use exif_sorter::sorter::preserve::Preserve;
 use exif_sorter::sorter::TransferMode;
 
 /// Write a minimal TIFF stream containing only the given EXIF fields.
@@ -363,7 +365,7 @@ fn transfer_to_target_returns_error_when_source_does_not_exist() {
     image.target_filename = "ghost".to_string();
     image.target_filetype = "jpg".to_string();
 
-    let result = image.transfer_to_target(TransferMode::Move, false);
+    let result = image.transfer_to_target(TransferMode::Move, Preserve::default(), false);
     assert!(
         result.is_err(),
         "transfer_to_target returned Ok even though source did not exist"
@@ -389,7 +391,7 @@ fn transfer_to_target_copy_keeps_source() {
     image.target_filetype = "jpg".to_string();
 
     image
-        .transfer_to_target(TransferMode::Copy, false)
+        .transfer_to_target(TransferMode::Copy, Preserve::default(), false)
         .expect("copy failed");
 
     assert!(source_file.exists(), "copy mode must not remove the source");
@@ -397,6 +399,117 @@ fn transfer_to_target_copy_keeps_source() {
     assert_eq!(std::fs::read(&copied).unwrap(), b"image data");
 }
 
+// ---- Verified copies: never a truncated file at the final name ----
+
+#[test]
+fn transfer_to_target_copy_returns_verified_checksum_and_no_partial_file() {
+    // given
+    let tmp = testdir::testdir!();
+    let source_file = tmp.join("photo.jpg");
+    std::fs::write(&source_file, b"image data").unwrap();
+    let target_dir = tmp.join("sorted");
+    let mut image = Image::new(source_file.clone(), target_dir.clone());
+    image.target_filename = "photo".to_string();
+    image.target_filetype = "jpg".to_string();
+
+    // when
+    let checksum = image
+        .transfer_to_target(TransferMode::Copy, Preserve::default(), false)
+        .expect("copy failed");
+
+    // then: checksum of the content, only the final file in the directory
+    assert_eq!(
+        checksum.as_deref(),
+        Some(file_checksum(&source_file).unwrap().as_str())
+    );
+    let names: Vec<_> = std::fs::read_dir(&target_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["photo.jpg".to_string()]);
+}
+
+#[test]
+fn transfer_to_target_replaces_partial_file_left_by_a_crash() {
+    // A crash mid-copy leaves `.photo.jpg.exif-sorter-part` behind. The
+    // next transfer to the same name must not be confused by its content.
+    // given
+    let tmp = testdir::testdir!();
+    let source_file = tmp.join("photo.jpg");
+    std::fs::write(&source_file, b"image data").unwrap();
+    let target_dir = tmp.join("sorted");
+    std::fs::create_dir_all(&target_dir).unwrap();
+    let partial = partial_path(&target_dir.join("photo.jpg"));
+    std::fs::write(&partial, b"truncated garbage from a previous crash").unwrap();
+    let mut image = Image::new(source_file, target_dir.clone());
+    image.target_filename = "photo".to_string();
+    image.target_filetype = "jpg".to_string();
+
+    // when
+    image
+        .transfer_to_target(TransferMode::Copy, Preserve::default(), false)
+        .expect("copy failed");
+
+    // then
+    assert_eq!(std::fs::read(target_dir.join("photo.jpg")).unwrap(), b"image data");
+    assert!(!partial.exists(), "partial file must be renamed into place");
+}
+
+// ---- Link modes: the source is shared, never touched ----
+
+#[test]
+fn transfer_to_target_link_modes_keep_source_and_share_content() {
+    // Reflink falls back to a plain copy where the filesystem cannot clone
+    // (tmpfs, ext4), so every mode must end with identical content at the
+    // target and the source in place.
+    for mode in [
+        TransferMode::Reflink,
+        TransferMode::Hardlink,
+        TransferMode::Symlink,
+    ] {
+        let tmp = testdir::testdir!();
+        let source_dir = tmp.join(format!("source-{mode:?}"));
+        let target_dir = tmp.join(format!("sorted-{mode:?}"));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        let source_file = source_dir.join("photo.jpg");
+        std::fs::write(&source_file, b"image data").unwrap();
+
+        let mut image = Image::new(source_file.clone(), target_dir.clone());
+        image.target_filename = "photo".to_string();
+        image.target_filetype = "jpg".to_string();
+        image.transfer_to_target(mode, Preserve::default(), false).expect("transfer failed");
+
+        let target_file = target_dir.join("photo.jpg");
+        assert!(source_file.exists(), "{mode:?} must not remove the source");
+        assert_eq!(std::fs::read(&target_file).unwrap(), b"image data");
+        let is_symlink = std::fs::symlink_metadata(&target_file)
+            .unwrap()
+            .file_type()
+            .is_symlink();
+        assert_eq!(is_symlink, mode == TransferMode::Symlink);
+    }
+}
+
+#[test]
+fn transfer_to_target_hardlink_shares_the_inode() {
+    use std::os::unix::fs::MetadataExt;
+    let tmp = testdir::testdir!();
+    let source_file = tmp.join("photo.jpg");
+    std::fs::write(&source_file, b"image data").unwrap();
+    let target_dir = tmp.join("sorted");
+
+    let mut image = Image::new(source_file.clone(), target_dir.clone());
+    image.target_filename = "photo".to_string();
+    image.target_filetype = "jpg".to_string();
+    image
+        .transfer_to_target(TransferMode::Hardlink, Preserve::default(), false)
+        .expect("hardlink failed");
+
+    let source_ino = std::fs::metadata(&source_file).unwrap().ino();
+    let target_ino = std::fs::metadata(target_dir.join("photo.jpg")).unwrap().ino();
+    assert_eq!(source_ino, target_ino, "hardlink must not duplicate data");
+}
+
 // ---- Timestamp preservation: sorting must not destroy the mtime signal ----
 
 #[test]
@@ -419,7 +532,7 @@ fn transfer_to_target_preserves_modified_time() {
         image.target_filename = "photo".to_string();
         image.target_filetype = "jpg".to_string();
 
-        image.transfer_to_target(mode, false).expect("transfer failed");
+        image.transfer_to_target(mode, Preserve::default(), false).expect("transfer failed");
 
         let meta = std::fs::metadata(target_dir.join("photo.jpg")).unwrap();
         let mtime = filetime::FileTime::from_last_modification_time(&meta);
@@ -431,6 +544,40 @@ fn transfer_to_target_preserves_modified_time() {
     }
 }
 
+#[test]
+fn transfer_to_target_preserves_mode_and_xattrs_when_asked() {
+    // NAS copies lost permissions and extended attributes; with the full
+    // preserve set both survive the copy path.
+    use std::os::unix::fs::PermissionsExt;
+    // given
+    let tmp = testdir::testdir!();
+    let source_file = tmp.join("photo.jpg");
+    std::fs::write(&source_file, b"image data").unwrap();
+    std::fs::set_permissions(&source_file, std::fs::Permissions::from_mode(0o600)).unwrap();
+    // Not every test filesystem supports user xattrs; only assert if it does.
+    let has_xattr = xattr::set(&source_file, "user.xdg.origin.url", b"https://example.org").is_ok();
+    let target_dir = tmp.join("sorted");
+    let mut image = Image::new(source_file, target_dir.clone());
+    image.target_filename = "photo".to_string();
+    image.target_filetype = "jpg".to_string();
+
+    // when
+    image
+        .transfer_to_target(TransferMode::Copy, Preserve::ALL, false)
+        .expect("copy failed");
+
+    // then
+    let target_file = target_dir.join("photo.jpg");
+    let mode = std::fs::metadata(&target_file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    if has_xattr {
+        assert_eq!(
+            xattr::get(&target_file, "user.xdg.origin.url").unwrap().as_deref(),
+            Some(&b"https://example.org"[..])
+        );
+    }
+}
+
 // ---- TOCTOU: target path can be claimed between set_target and move_to_target ----
 
 #[test]
@@ -464,7 +611,7 @@ fn transfer_to_target_does_not_overwrite_file_claimed_after_set_target() {
 
     image.target_dir = chosen_dir;
     image.target_filename = chosen_filename;
-    let _ = image.transfer_to_target(TransferMode::Move, false);
+    let _ = image.transfer_to_target(TransferMode::Move, Preserve::default(), false);
 
     let content = std::fs::read(&claimed).unwrap();
     assert_eq!(
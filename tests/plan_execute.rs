@@ -1,15 +1,33 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use chrono::{Local, TimeZone};
+use exif_sorter::error::AppError;
+use exif_sorter::sorter::card::{check_imports, find_cards, remove_verified, Volume};
+use exif_sorter::sorter::catalog::{Catalog, CatalogQuery};
+use exif_sorter::sorter::checksum::file_checksum;
+use exif_sorter::sorter::control::RunControl;
+use exif_sorter::sorter::dir::scan_dir;
+use exif_sorter::sorter::image::{partial_path, DateSource};
+use exif_sorter::sorter::journal::{JournalItem, JournalWriter, JOURNAL_FILENAME};
+use exif_sorter::sorter::manifest::{read_manifest, ManifestFormat};
+use exif_sorter::sorter::merge::plan_merge;
+use exif_sorter::sorter::relayout::relayout;
+use exif_sorter::sorter::runs::read_runs;
+use exif_sorter::sorter::spill::{PlanSpill, SpilledPlan};
+use exif_sorter::sorter::verify::verify;
+use exif_sorter::sorter::watch::{watch, WatchEvent};
 use exif_sorter::sorter::{
-    execute, plan, process, ItemOutcome, PlannedAction, ProcessOptions,
+    execute, execute_spilled, plan, plan_sources, plan_streaming, process, resume, revert,
+    CollisionPolicy, ItemOutcome, Plan, PlannedAction, ProcessOptions, RevertFilter, TouchPolicy,
+    TransferMode,
 };
 
 /// Source dir with one file per routing category: an EXIF-dated PNG
 /// (Sorted, high confidence), a PNG-signature file without any metadata
 /// (recognized content, low-confidence Sorted via file dates) and garbage
 /// bytes behind a .jpg extension (Corrupt).
-fn build_mixed_source(root: &PathBuf) {
+fn build_mixed_source(root: &Path) {
     // EXIF-dated (1991-01-01, from the repo fixture)
     fs::copy(
         "tests/data/dateTimeOriginal.png",
@@ -205,3 +223,1539 @@ fn collision_appearing_between_plan_and_execute_is_still_handled() {
         .count();
     assert_eq!(siblings, 2, "expected original plus suffixed transfer");
 }
+
+#[test]
+fn execute_records_the_verified_checksum_in_the_manifest() {
+    // The manifest checksum is what later integrity checks compare the
+    // target against, so it must describe the bytes actually stored.
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("photo.png")).unwrap();
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then
+    let entries = read_manifest(&target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].checksum,
+        file_checksum(&target.join("1991/1991-01-01/photo.png")).unwrap()
+    );
+}
+
+#[test]
+fn touch_sets_the_capture_time_and_revert_restores_the_original_mtime() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    let photo = source.join("photo.png");
+    fs::copy("tests/data/dateTimeOriginal.png", &photo).unwrap();
+    let original_mtime = fs::metadata(&photo).unwrap().modified().unwrap();
+    let options = ProcessOptions {
+        mode: TransferMode::Move,
+        touch: TouchPolicy::HighConfidence,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let PlannedAction::Sorted { datetime, .. } = the_plan.items[0].action else {
+        panic!("EXIF-dated fixture must be sorted");
+    };
+
+    // when
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then: EXIF times are camera wall-clock, i.e. local time
+    let moved = target.join("1991/1991-01-01/photo.png");
+    let expected: std::time::SystemTime = Local
+        .from_local_datetime(&datetime)
+        .earliest()
+        .unwrap()
+        .into();
+    assert_eq!(fs::metadata(&moved).unwrap().modified().unwrap(), expected);
+
+    // when
+    let manifest = target.join("exif-sorter-manifest.csv");
+    revert(&manifest, &RevertFilter::default(), false).unwrap();
+
+    // then
+    assert_eq!(
+        fs::metadata(&photo).unwrap().modified().unwrap(),
+        original_mtime
+    );
+}
+
+/// Journal `plan` as `execute` would and stop as if killed while the first
+/// item was being copied: its claim and partial file are on disk, nothing
+/// is marked done.
+fn interrupt_during_first_item(plan: &Plan, options: &ProcessOptions) -> PathBuf {
+    fs::create_dir_all(&plan.target).unwrap();
+    let items = plan
+        .items
+        .iter()
+        .map(|item| JournalItem {
+            source: PathBuf::from(item.image.source_full()),
+            source_root: item.source_root.clone(),
+            action: item.action.clone(),
+            selected: item.selected,
+            merge: None,
+        })
+        .collect();
+    let journal =
+        JournalWriter::create(&plan.target, "run", &plan.sources, options, items).unwrap();
+    let claimed = plan.items[0].planned_target.clone();
+    journal.started(0, &claimed);
+    fs::create_dir_all(claimed.parent().unwrap()).unwrap();
+    fs::write(&claimed, b"").unwrap();
+    fs::write(partial_path(&claimed), b"half a fi").unwrap();
+    claimed
+}
+
+#[test]
+fn resume_rolls_back_the_interrupted_item_and_finishes_the_run() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let first_source = PathBuf::from(the_plan.items[0].image.source_full());
+    let claimed = interrupt_during_first_item(&the_plan, &options);
+
+    // when: a fresh run must not silently start over
+    let fresh = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let refused = execute(fresh, &options, |_, _| {});
+
+    // then
+    assert!(refused.is_err());
+
+    // when
+    let mut resumed = Vec::new();
+    let (_, summary) = resume(&target, &RunControl::default(), |index, _| {
+        resumed.push(index)
+    })
+    .unwrap();
+
+    // then: every item ran once, the half-written copy was replaced
+    assert_eq!(resumed, vec![0, 1, 2]);
+    assert_eq!(summary.total(), 3);
+    assert_eq!(
+        fs::read(&claimed).unwrap(),
+        fs::read(&first_source).unwrap()
+    );
+    assert!(!partial_path(&claimed).exists());
+    assert!(!target.join(JOURNAL_FILENAME).exists());
+    let entries = read_manifest(&target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), 3);
+}
+
+#[test]
+fn resume_records_a_move_that_finished_before_it_was_journaled() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("photo.png")).unwrap();
+    let options = ProcessOptions {
+        mode: TransferMode::Move,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let claimed = interrupt_during_first_item(&the_plan, &options);
+    // the rename went through, the process died before journaling it
+    fs::rename(source.join("photo.png"), &claimed).unwrap();
+
+    // when
+    let (_, summary) = resume(&target, &RunControl::default(), |_, _| {}).unwrap();
+
+    // then
+    assert_eq!(summary.transferred, 1);
+    assert!(claimed.exists());
+    let entries = read_manifest(&target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, "moved");
+    assert_eq!(entries[0].target, claimed.to_string_lossy());
+}
+
+#[test]
+fn resume_leaves_a_claimed_path_alone_when_it_holds_someone_elses_file() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("photo.png")).unwrap();
+    let options = ProcessOptions {
+        mode: TransferMode::Move,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let claimed = interrupt_during_first_item(&the_plan, &options);
+    // the only copy of another photo took the name meanwhile
+    fs::write(&claimed, b"someone else's photo").unwrap();
+
+    // when
+    let (_, summary) = resume(&target, &RunControl::default(), |_, _| {}).unwrap();
+
+    // then: it survives, the item went to a fresh name
+    assert_eq!(summary.transferred, 1);
+    assert_eq!(fs::read(&claimed).unwrap(), b"someone else's photo");
+    let entries = read_manifest(&target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_ne!(entries[0].target, claimed.to_string_lossy());
+}
+
+#[test]
+fn resume_refuses_a_journal_whose_plan_lacks_its_run_id() {
+    // given: an interrupted run whose plan line lost a field
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    interrupt_during_first_item(&the_plan, &options);
+    let journal = target.join(JOURNAL_FILENAME);
+    let content = fs::read_to_string(&journal).unwrap();
+    let (header, rest) = content.split_once('\n').unwrap();
+    let mut header: serde_json::Value = serde_json::from_str(header).unwrap();
+    header.as_object_mut().unwrap().remove("run_id");
+    fs::write(&journal, format!("{header}\n{rest}")).unwrap();
+
+    // when
+    let resumed = resume(&target, &RunControl::default(), |_, _| {});
+
+    // then
+    assert!(resumed.is_err());
+}
+
+#[test]
+fn plan_totals_the_space_each_mode_needs() {
+    // given: one file the target already holds, one it does not
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("stored.png")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("new.png")).unwrap();
+    let size = fs::metadata(source.join("new.png")).unwrap().len();
+    fs::create_dir_all(target.join("1991/1991-01-01")).unwrap();
+    fs::copy(
+        "tests/data/dateTimeOriginal.png",
+        target.join("1991/1991-01-01/stored.png"),
+    )
+    .unwrap();
+    let options = ProcessOptions {
+        collision: CollisionPolicy::Dedupe,
+        ..ProcessOptions::default()
+    };
+
+    // when
+    let mut the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // then: the identical duplicate takes no space, links never do
+    assert_eq!(the_plan.bytes_needed(TransferMode::Copy), size);
+    assert_eq!(the_plan.bytes_needed(TransferMode::Hardlink), 0);
+    assert!(the_plan.same_filesystem);
+    assert_eq!(the_plan.bytes_needed(TransferMode::Move), 0);
+    assert!(the_plan.available_space.is_some());
+
+    // when: deselected items do not count
+    for item in &mut the_plan.items {
+        item.selected = false;
+    }
+
+    // then
+    assert_eq!(the_plan.bytes_needed(TransferMode::Copy), 0);
+    assert!(the_plan.space_check(TransferMode::Copy).is_sufficient());
+}
+
+#[test]
+fn parallel_execute_dedupes_and_suffixes_exactly_like_a_sequential_run() {
+    // given: nine same-named files aiming at one target (three contents,
+    // three times each, so scan order does not change the outcome), plus
+    // twenty unrelated files to keep the workers busy
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let png_sig = [0x89u8, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+    for i in 0..9u8 {
+        let dir = source.join(format!("dir{i}"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("same.png"), [&png_sig[..], &[i % 3]].concat()).unwrap();
+    }
+    for i in 0..20u8 {
+        let content = [&png_sig[..], &[i]].concat();
+        fs::write(source.join(format!("other{i}.png")), content).unwrap();
+    }
+    let sequential = ProcessOptions {
+        collision: CollisionPolicy::Dedupe,
+        ..ProcessOptions::default()
+    };
+    let parallel = ProcessOptions {
+        jobs: 4,
+        ..sequential.clone()
+    };
+    let sequential_target = tmp.join("sequential");
+    let parallel_target = tmp.join("parallel");
+    let sequential_plan = plan(&source, &sequential_target, &sequential, |_, _| {}).unwrap();
+    let parallel_plan = plan(&source, &parallel_target, &parallel, |_, _| {}).unwrap();
+    let total = parallel_plan.items.len();
+
+    // when
+    let expected = execute(sequential_plan, &sequential, |_, _| {}).unwrap();
+    let mut reported = Vec::new();
+    let summary = execute(parallel_plan, &parallel, |index, _| reported.push(index)).unwrap();
+
+    // then: every item reported exactly once, same decisions as in order
+    reported.sort_unstable();
+    assert_eq!(reported, (0..total).collect::<Vec<_>>());
+    assert_eq!(summary.transferred, expected.transferred);
+    assert_eq!(summary.duplicates, expected.duplicates);
+    assert!(summary.duplicates > 0);
+    assert_eq!(
+        scan_dir(&parallel_target).unwrap().len(),
+        scan_dir(&sequential_target).unwrap().len()
+    );
+    // one manifest row per item, none torn
+    let entries = read_manifest(&parallel_target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), total);
+    assert!(entries.iter().all(|entry| entry.source.ends_with(".png")));
+}
+
+#[test]
+fn cancelled_plan_fails_with_cancelled() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    options.control.cancel();
+
+    // when
+    let result = plan(&source, &tmp.join("sorted"), &options, |_, _| {});
+
+    // then
+    let error = result.err().expect("a cancelled plan must not succeed");
+    assert!(matches!(
+        error.downcast_ref::<AppError>(),
+        Some(AppError::Cancelled())
+    ));
+}
+
+#[test]
+fn cancelled_execute_stops_between_items_and_resume_finishes_it() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when: cancelled while the first item is being reported
+    let summary = execute(the_plan, &options, |_, _| options.control.cancel()).unwrap();
+
+    // then: one item done and recorded, the journal kept for resume
+    assert!(summary.cancelled);
+    assert_eq!(summary.total(), 1);
+    let manifest = target.join("exif-sorter-manifest.csv");
+    assert_eq!(read_manifest(&manifest).unwrap().len(), 1);
+    assert!(target.join(JOURNAL_FILENAME).exists());
+
+    // when
+    let mut resumed = Vec::new();
+    let (_, summary) = resume(&target, &RunControl::new(), |index, _| resumed.push(index)).unwrap();
+
+    // then
+    assert!(!summary.cancelled);
+    assert_eq!(resumed, vec![1, 2]);
+    assert_eq!(read_manifest(&manifest).unwrap().len(), 3);
+    assert!(!target.join(JOURNAL_FILENAME).exists());
+}
+
+#[test]
+fn paused_execute_waits_until_resumed() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions {
+        jobs: 2,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    options.control.pause();
+
+    // when
+    let run = {
+        let options = options.clone();
+        std::thread::spawn(move || execute(the_plan, &options, |_, _| {}).unwrap())
+    };
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    // then: nothing transferred while paused
+    let transferred = scan_dir(&target)
+        .unwrap()
+        .into_iter()
+        .filter(|entry| !entry.path().starts_with(target.join(JOURNAL_FILENAME)))
+        .count();
+    assert_eq!(transferred, 0);
+
+    // when
+    options.control.resume();
+    let summary = run.join().unwrap();
+
+    // then
+    assert!(!summary.cancelled);
+    assert_eq!(summary.total(), 3);
+}
+
+#[test]
+fn cancelling_a_paused_execute_ends_it() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    options.control.pause();
+
+    // when: cancelled while it waits, without being resumed
+    let (done, finished) = std::sync::mpsc::channel();
+    {
+        let options = options.clone();
+        std::thread::spawn(move || {
+            let _ = done.send(execute(the_plan, &options, |_, _| {}).unwrap());
+        });
+    }
+    options.control.cancel();
+
+    // then
+    let summary = finished
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("a cancelled run returns even while paused");
+    assert!(summary.cancelled);
+    assert_eq!(summary.total(), 0);
+}
+
+#[test]
+fn revert_leaves_changed_copies_alone_and_removes_emptied_dirs() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+    let edited = target.join("1991/1991-01-01/exif_dated.png");
+    let mut content = fs::read(&edited).unwrap();
+    content.push(0);
+    fs::write(&edited, content).unwrap();
+
+    // when
+    let manifest = target.join("exif-sorter-manifest.csv");
+    let summary = revert(&manifest, &RevertFilter::default(), false).unwrap();
+
+    // then: the edited copy survives, the others and their folders are gone
+    assert_eq!(summary.reverted, 2);
+    assert_eq!(
+        summary.modified,
+        vec![edited.to_string_lossy().into_owned()]
+    );
+    assert!(edited.exists());
+    assert!(!target.join("corrupt").exists());
+    assert!(summary.removed_dirs >= 1);
+    assert!(source.join("garbage.jpg").exists());
+}
+
+#[test]
+fn filtered_revert_can_itself_be_reverted() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions {
+        mode: TransferMode::Move,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+    let moved = target.join("corrupt/garbage.jpg");
+    let manifest = target.join("exif-sorter-manifest.csv");
+    let filter = RevertFilter {
+        categories: vec!["corrupt".to_string()],
+        ..RevertFilter::default()
+    };
+
+    // when
+    let summary = revert(&manifest, &filter, false).unwrap();
+
+    // then: only the corrupt file went back
+    assert_eq!(summary.reverted, 1);
+    assert!(source.join("garbage.jpg").exists());
+    assert!(!moved.exists());
+    assert!(target.join("1991/1991-01-01/exif_dated.png").exists());
+
+    // when
+    let undo = summary
+        .manifest
+        .expect("the revert writes its own manifest");
+    let summary = revert(&undo, &RevertFilter::default(), false).unwrap();
+
+    // then
+    assert_eq!(summary.reverted, 1);
+    assert!(moved.exists());
+    assert!(!source.join("garbage.jpg").exists());
+}
+
+#[test]
+fn revert_by_run_and_path_touches_only_matching_rows() {
+    // given: two runs into the same target
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let first = execute(
+        plan(&source, &target, &options, |_, _| {}).unwrap(),
+        &options,
+        |_, _| {},
+    )
+    .unwrap();
+    let second = execute(
+        plan(&source, &target, &options, |_, _| {}).unwrap(),
+        &options,
+        |_, _| {},
+    )
+    .unwrap();
+    assert_ne!(first.run_id, second.run_id);
+    let manifest = target.join("exif-sorter-manifest.csv");
+
+    // when
+    let by_run = RevertFilter {
+        run: Some(second.run_id.clone()),
+        path: Some(globset::Glob::new("**/*.png").unwrap().compile_matcher()),
+        ..RevertFilter::default()
+    };
+    let summary = revert(&manifest, &by_run, false).unwrap();
+
+    // then: the second run's two PNGs are gone, its corrupt copy and the
+    // whole first run stay
+    assert_eq!(summary.reverted, 2);
+    assert_eq!(scan_dir(&target.join("1991")).unwrap().len(), 1);
+    assert_eq!(scan_dir(&target.join("corrupt")).unwrap().len(), 2);
+}
+
+#[test]
+fn jsonl_manifest_records_the_dating_facts_and_reverts_like_csv() {
+    // given: the same photo twice, so dedupe records a duplicate
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(source.join("again")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("photo.png")).unwrap();
+    fs::copy(
+        "tests/data/dateTimeOriginal.png",
+        source.join("again/photo.png"),
+    )
+    .unwrap();
+    let options = ProcessOptions {
+        collision: CollisionPolicy::Dedupe,
+        manifest: ManifestFormat::Jsonl,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then
+    let manifest = target.join("exif-sorter-manifest.jsonl");
+    assert!(!target.join("exif-sorter-manifest.csv").exists());
+    let entries = read_manifest(&manifest).unwrap();
+    assert_eq!(entries.len(), 2);
+    let copied = entries.iter().find(|e| e.action == "copied").unwrap();
+    let stored = target.join("1991/1991-01-01/photo.png");
+    assert_eq!(copied.date_source, Some(DateSource::ExifDateTimeOriginal));
+    assert_eq!(copied.datetime.unwrap().date(), copied.date.unwrap());
+    assert_eq!(copied.collision, Some(CollisionPolicy::Dedupe));
+    assert_eq!(copied.size, Some(fs::metadata(&stored).unwrap().len()));
+    assert!(copied.mtime_before.is_some() && copied.mtime_after.is_some());
+    assert!(copied
+        .candidates
+        .iter()
+        .any(|c| c.source == DateSource::ExifDateTimeOriginal && c.plausible));
+    assert!(copied
+        .candidates
+        .iter()
+        .any(|c| c.source == DateSource::FileModified));
+    let duplicate = entries.iter().find(|e| e.action == "duplicate").unwrap();
+    assert_eq!(
+        duplicate.duplicate_of.as_deref(),
+        Some(stored.to_string_lossy().as_ref())
+    );
+
+    // when
+    let summary = revert(&manifest, &RevertFilter::default(), false).unwrap();
+
+    // then
+    assert_eq!(summary.reverted, 1);
+    assert!(!stored.exists());
+    assert_eq!(
+        summary.manifest.unwrap().extension().unwrap(),
+        "jsonl",
+        "a revert records itself in the format it read"
+    );
+}
+
+#[test]
+fn execute_refuses_to_append_to_a_manifest_with_an_older_header() {
+    // given: a target with a manifest from 1.0.0
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::create_dir_all(&target).unwrap();
+    build_mixed_source(&source);
+    let manifest = target.join("exif-sorter-manifest.csv");
+    let old = "timestamp,action,category,source,target,date,date_source,reason\n";
+    fs::write(&manifest, old).unwrap();
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when
+    let result = execute(the_plan, &options, |_, _| {});
+
+    // then: nothing is touched, not even the manifest
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("older version"), "{error}");
+    assert_eq!(fs::read_to_string(&manifest).unwrap(), old);
+    assert!(!target.join(JOURNAL_FILENAME).exists());
+    assert!(!target.join("1991").exists());
+}
+
+#[test]
+fn catalog_follows_execute_and_revert_and_answers_queries() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then: every stored file is catalogued with its facts
+    let catalog = Catalog::open_existing(&target).unwrap();
+    assert_eq!(catalog.query(&CatalogQuery::default()).unwrap().len(), 3);
+    let from_1991 = catalog
+        .query(&CatalogQuery {
+            year: Some(1991),
+            ..CatalogQuery::default()
+        })
+        .unwrap();
+    assert_eq!(from_1991.len(), 1);
+    assert_eq!(
+        from_1991[0].date_source.as_deref(),
+        Some("EXIF DateTimeOriginal")
+    );
+    assert!(from_1991[0].width.is_some() && from_1991[0].height.is_some());
+    let low_confidence = catalog
+        .query(&CatalogQuery {
+            low_confidence: true,
+            ..CatalogQuery::default()
+        })
+        .unwrap();
+    assert_eq!(low_confidence.len(), 1);
+    assert!(low_confidence[0].path.ends_with("recognized_no_date.png"));
+
+    // when
+    fs::remove_file(source.join("garbage.jpg")).unwrap();
+
+    // then
+    let source_missing = catalog
+        .query(&CatalogQuery {
+            source_missing: true,
+            ..CatalogQuery::default()
+        })
+        .unwrap();
+    assert_eq!(source_missing.len(), 1);
+    assert_eq!(source_missing[0].category, "corrupt");
+
+    // when: revert leaves only the copy whose source is gone
+    revert(
+        &target.join("exif-sorter-manifest.csv"),
+        &RevertFilter::default(),
+        false,
+    )
+    .unwrap();
+
+    // then
+    let left = catalog.query(&CatalogQuery::default()).unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].category, "corrupt");
+}
+
+#[test]
+fn planning_a_real_run_reads_the_facts_its_manifest_rows_carry() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let target = tmp.join("sorted");
+
+    // when
+    let real = plan(&source, &target, &ProcessOptions::default(), |_, _| {}).unwrap();
+    let dry = ProcessOptions {
+        dry_run: true,
+        ..ProcessOptions::default()
+    };
+    let dry = plan(&source, &target, &dry, |_, _| {}).unwrap();
+
+    // then
+    assert!(real.items.iter().all(|item| item.image.facts.is_some()));
+    assert!(dry.items.iter().all(|item| item.image.facts.is_none()));
+}
+
+#[test]
+fn verify_reports_library_drift_and_fix_moves_misfiled_files() {
+    // given: a library with one photo recorded (and stored) in the wrong
+    // date folder, one recorded file gone and one file nobody recorded
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+    let manifest = target.join("exif-sorter-manifest.csv");
+    let wrong = target.join("2000/2000-01-01/exif_dated.png");
+    fs::create_dir_all(wrong.parent().unwrap()).unwrap();
+    fs::rename(target.join("1991/1991-01-01/exif_dated.png"), &wrong).unwrap();
+    let rewritten = fs::read_to_string(&manifest).unwrap().replace(
+        "1991/1991-01-01/exif_dated.png",
+        "2000/2000-01-01/exif_dated.png",
+    );
+    fs::write(&manifest, rewritten).unwrap();
+    let corrupt = fs::read_dir(target.join("corrupt"))
+        .unwrap()
+        .next()
+        .unwrap();
+    fs::remove_file(corrupt.unwrap().path()).unwrap();
+    fs::write(target.join("stray.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+
+    // when
+    let report = verify(&target, "{year}/{date}", false).unwrap();
+
+    // then
+    assert_eq!(report.checked, 3);
+    assert_eq!(report.missing.len(), 1);
+    assert!(report.changed.is_empty());
+    assert_eq!(report.untracked.len(), 1);
+    assert!(report.untracked[0].ends_with("stray.png"));
+    assert_eq!(report.misfiled.len(), 1);
+    assert!(report.misfiled[0].expected_dir.ends_with("1991/1991-01-01"));
+    assert!(
+        wrong.exists(),
+        "verify without --fix must not move anything"
+    );
+
+    // when
+    let report = verify(&target, "{year}/{date}", true).unwrap();
+
+    // then: moved, recorded, and the library agrees with its manifest
+    assert_eq!(report.fixed, 1);
+    assert!(!wrong.exists());
+    assert!(target.join("1991/1991-01-01/exif_dated.png").exists());
+    let entries = read_manifest(&manifest).unwrap();
+    let fix = entries.last().unwrap();
+    assert_eq!(fix.action, "moved");
+    assert_eq!(Some(&fix.run_id), report.fix_run_id.as_ref());
+    let report = verify(&target, "{year}/{date}", false).unwrap();
+    assert!(report.misfiled.is_empty());
+    assert_eq!(report.problems(), 2, "missing and untracked remain");
+}
+
+#[test]
+fn relayout_moves_the_library_to_a_new_pattern_and_reverts() {
+    // given: a library sorted with the default "{year}/{date}"
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    let options = ProcessOptions::default();
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+    let old_path = target.join("1991/1991-01-01/exif_dated.png");
+    let new_path = target.join("1991/01/exif_dated.png");
+
+    // when: a dry run
+    let report = relayout(&target, "{year}/{month}", true).unwrap();
+
+    // then
+    assert_eq!(report.relocations.len(), 2, "both dated files move");
+    assert!(report.run_id.is_none());
+    assert!(old_path.exists(), "a dry run moves nothing");
+
+    // when
+    let report = relayout(&target, "{year}/{month}", false).unwrap();
+
+    // then: moved, the old folders are gone, the catalog follows, and the
+    // library verifies against the new layout
+    assert_eq!(report.relocations.len(), 2);
+    assert!(report.failed.is_empty());
+    assert!(new_path.exists());
+    assert!(!target.join("1991/1991-01-01").exists());
+    let run_id = report.run_id.unwrap();
+    let runs = read_runs(&target).unwrap();
+    assert_eq!(runs.last().unwrap().id, run_id);
+    assert_eq!(runs.last().unwrap().options.pattern, "{year}/{month}");
+    let records = Catalog::open_existing(&target)
+        .unwrap()
+        .query(&CatalogQuery::default())
+        .unwrap();
+    assert!(records
+        .iter()
+        .any(|record| record.path.ends_with("1991/01/exif_dated.png")));
+    assert!(!records
+        .iter()
+        .any(|record| record.path.ends_with("1991-01-01/exif_dated.png")));
+    assert!(verify(&target, "{year}/{date}", false)
+        .unwrap()
+        .misfiled
+        .is_empty());
+    assert!(relayout(&target, "{year}/{month}", false)
+        .unwrap()
+        .relocations
+        .is_empty());
+
+    // when: the relayout run is reverted
+    let filter = RevertFilter {
+        run: Some(run_id),
+        ..RevertFilter::default()
+    };
+    let summary = revert(&report.manifest.unwrap(), &filter, false).unwrap();
+
+    // then: the previous layout is back and verifies again
+    assert_eq!(summary.reverted, 2);
+    assert!(old_path.exists());
+    assert!(!new_path.exists());
+    let report = verify(&target, "{year}/{date}", false).unwrap();
+    assert!(report.misfiled.is_empty());
+    assert_eq!(report.problems(), 0);
+}
+
+#[test]
+fn merge_trusts_recorded_dates_dedupes_by_content_and_keeps_provenance() {
+    // given: library `ours`, and library `theirs` holding a renamed copy of
+    // one of our photos plus a photo whose recorded date we corrected
+    let tmp = testdir::testdir!();
+    let ours = tmp.join("ours");
+    let theirs = tmp.join("theirs");
+    let our_source = tmp.join("our_source");
+    let their_source = tmp.join("their_source");
+    fs::create_dir_all(&our_source).unwrap();
+    fs::create_dir_all(&their_source).unwrap();
+    build_mixed_source(&our_source);
+    fs::copy(
+        "tests/data/dateTimeOriginal.png",
+        their_source.join("renamed.png"),
+    )
+    .unwrap();
+    let mut other = fs::read("tests/data/dateTimeOriginal.png").unwrap();
+    other.push(0);
+    fs::write(their_source.join("other.png"), other).unwrap();
+    let options = ProcessOptions {
+        manifest: ManifestFormat::Jsonl,
+        ..ProcessOptions::default()
+    };
+    let the_plan = plan(&our_source, &ours, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+    let the_plan = plan(
+        &their_source,
+        &theirs,
+        &ProcessOptions::default(),
+        |_, _| {},
+    )
+    .unwrap();
+    execute(the_plan, &ProcessOptions::default(), |_, _| {}).unwrap();
+    let their_manifest = theirs.join("exif-sorter-manifest.csv");
+    let corrected = fs::read_to_string(&their_manifest)
+        .unwrap()
+        .lines()
+        .map(|line| match line.contains("other.png") {
+            true => line.replacen(",1991-01-01,", ",2001-02-03,", 1),
+            false => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    fs::write(&their_manifest, corrected).unwrap();
+
+    // when
+    let merge_plan = plan_merge(&theirs, &ours, &options).unwrap();
+    let summary = execute(merge_plan, &options, |_, _| {}).unwrap();
+
+    // then: the copy is a duplicate of our file, the other photo goes by
+    // its recorded date, and both rows say where the file came from
+    assert_eq!(summary.duplicates, 1);
+    assert_eq!(summary.transferred, 1);
+    assert!(ours.join("2001/2001-02-03/other.png").exists());
+    assert!(!ours.join("1991/1991-01-01/renamed.png").exists());
+    let rows = read_manifest(&ours.join("exif-sorter-manifest.jsonl")).unwrap();
+    let merged: Vec<_> = rows
+        .iter()
+        .filter(|row| row.run_id == summary.run_id)
+        .collect();
+    assert_eq!(merged.len(), 2);
+    let duplicate = merged.iter().find(|row| row.action == "duplicate").unwrap();
+    assert!(duplicate
+        .duplicate_of
+        .as_deref()
+        .is_some_and(|path| path.ends_with("1991/1991-01-01/exif_dated.png")));
+    assert!(duplicate
+        .origin
+        .as_deref()
+        .is_some_and(|origin| origin.ends_with("their_source/renamed.png")));
+    let copied = merged.iter().find(|row| row.action == "copied").unwrap();
+    assert_eq!(copied.date_source, Some(DateSource::ExifDateTimeOriginal));
+    assert!(copied
+        .origin
+        .as_deref()
+        .is_some_and(|origin| origin.ends_with("their_source/other.png")));
+
+    // when: merging again
+    let merge_plan = plan_merge(&theirs, &ours, &options).unwrap();
+
+    // then: everything is already stored
+    assert!(merge_plan.items.iter().all(|item| item.already_stored));
+}
+
+#[cfg(unix)]
+#[test]
+fn scan_errors_travel_from_plan_to_summary() {
+    // given
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    build_mixed_source(&source);
+    std::os::unix::fs::symlink(tmp.join("gone"), source.join("broken.jpg")).unwrap();
+    let mut options = ProcessOptions::default();
+    options.scan.follow_symlinks = true;
+
+    // when
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // then
+    assert_eq!(the_plan.items.len(), 3, "the readable files are planned");
+    assert_eq!(the_plan.scan_errors.len(), 1);
+    let summary = execute(the_plan, &options, |_, _| {}).unwrap();
+    assert_eq!(summary.scan_errors, 1);
+}
+
+#[test]
+fn fix_extensions_names_targets_after_their_content() {
+    // given: a PNG named .jpg and one without an extension
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("f1.jpg")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("f2")).unwrap();
+    let mut options = ProcessOptions {
+        manifest: ManifestFormat::Jsonl,
+        ..ProcessOptions::default()
+    };
+    options.scan.detect_content = true;
+
+    // when: extensionless files get one either way, wrong ones only when fixed
+    let unfixed = plan(&source, &target, &options, |_, _| {}).unwrap();
+    options.fix_extensions = true;
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then
+    let mut unfixed_targets: Vec<_> = unfixed
+        .items
+        .iter()
+        .map(|item| item.image.target_filename())
+        .collect();
+    unfixed_targets.sort();
+    assert_eq!(unfixed_targets, ["f1.jpg", "f2.png"]);
+    let dated = target.join("1991/1991-01-01");
+    assert!(dated.join("f1.png").exists());
+    assert!(dated.join("f2.png").exists());
+    let entries = read_manifest(&target.join("exif-sorter-manifest.jsonl")).unwrap();
+    let original_of = |name: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.target.ends_with(name))
+            .and_then(|entry| entry.original_extension.clone())
+    };
+    assert_eq!(original_of("f1.png").as_deref(), Some("jpg"));
+    assert_eq!(original_of("f2.png").as_deref(), Some(""));
+}
+
+#[test]
+fn archives_are_planned_as_directories_and_only_selected_members_extracted() {
+    // given: a ZIP and a gzipped TAR in the source
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    let photo = fs::read("tests/data/dateTimeOriginal.png").unwrap();
+    let zip_path = source.join("takeout.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+    for name in ["Photos/a.png", "Photos/skipped.png", "notes.txt"] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, &photo).unwrap();
+    }
+    zip.finish().unwrap();
+    let tar_path = source.join("backup.tar.gz");
+    let encoder = flate2::write::GzEncoder::new(
+        fs::File::create(&tar_path).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut tar = tar::Builder::new(encoder);
+    tar.append_path_with_name("tests/data/dateTimeOriginal.png", "old/b.png")
+        .unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+    let mut options = ProcessOptions::default();
+    options.scan.archives = true;
+
+    // when
+    let mut the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+    let only_zip = plan(&zip_path, &target, &ProcessOptions::default(), |_, _| {}).unwrap();
+
+    // then: members are planned under their archive path
+    let mut sources: Vec<_> = the_plan
+        .items
+        .iter()
+        .map(|item| item.image.source_full())
+        .collect();
+    sources.sort();
+    assert_eq!(
+        sources,
+        [
+            format!("{}!/old/b.png", tar_path.display()),
+            format!("{}!/Photos/a.png", zip_path.display()),
+            format!("{}!/Photos/skipped.png", zip_path.display()),
+        ]
+    );
+    assert!(the_plan
+        .items
+        .iter()
+        .all(|item| matches!(item.action, PlannedAction::Sorted { .. })));
+    assert_eq!(only_zip.items.len(), 2, "an archive source is a directory");
+
+    // when: one member is deselected
+    for item in &mut the_plan.items {
+        item.selected = !item.image.source_full().ends_with("skipped.png");
+    }
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then
+    let dated = target.join("1991/1991-01-01");
+    assert_eq!(fs::read(dated.join("a.png")).unwrap(), photo);
+    assert_eq!(fs::read(dated.join("b.png")).unwrap(), photo);
+    assert!(!dated.join("skipped.png").exists());
+    assert!(!target.join(".exif-sorter-staging").exists());
+    let manifest = target.join("exif-sorter-manifest.csv");
+    let entries = read_manifest(&manifest).unwrap();
+    let a = entries
+        .iter()
+        .find(|entry| entry.target.ends_with("a.png"))
+        .unwrap();
+    assert_eq!(a.action, "copied", "the archive keeps its members");
+    assert_eq!(a.source, format!("{}!/Photos/a.png", zip_path.display()));
+    assert_eq!(a.checksum, file_checksum(&dated.join("a.png")).unwrap());
+
+    // when: reverted, then the revert reverted
+    let summary = revert(&manifest, &RevertFilter::default(), false).unwrap();
+    assert_eq!(summary.reverted, 2);
+    assert!(!dated.join("a.png").exists());
+    let undo = summary.manifest.unwrap();
+    let summary = revert(&undo, &RevertFilter::default(), false).unwrap();
+
+    // then: extracted again
+    assert_eq!(summary.reverted, 2);
+    assert_eq!(fs::read(dated.join("a.png")).unwrap(), photo);
+}
+
+#[test]
+fn zip_member_times_are_read_as_local_time_unless_the_zip_recorded_utc() {
+    // given: a ZIP whose members carry a DOS time, one of them also an
+    // extended timestamp
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    let photo = fs::read("tests/data/noDateTimeOriginal.png").unwrap();
+    let dos_time = zip::DateTime::from_date_and_time(2001, 1, 1, 12, 0, 0).unwrap();
+    let mut zip = zip::ZipWriter::new(fs::File::create(source.join("takeout.zip")).unwrap());
+    let options = zip::write::FileOptions::<zip::write::ExtendedFileOptions>::default()
+        .last_modified_time(dos_time);
+    zip.start_file("dos.png", options.clone()).unwrap();
+    std::io::Write::write_all(&mut zip, &photo).unwrap();
+    let mut extended = options;
+    let mut field = vec![0b1];
+    field.extend_from_slice(&1_000_000_000u32.to_le_bytes());
+    extended
+        .add_extra_data(0x5455, field.into_boxed_slice(), false)
+        .unwrap();
+    zip.start_file("extended.png", extended).unwrap();
+    std::io::Write::write_all(&mut zip, &photo).unwrap();
+    zip.finish().unwrap();
+    let mut options = ProcessOptions::default();
+    options.scan.archives = true;
+
+    // when
+    let the_plan = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // then
+    let modified = |name: &str| {
+        the_plan
+            .items
+            .iter()
+            .find(|item| item.image.source_full().ends_with(name))
+            .and_then(|item| item.image.dates.file_modified)
+            .unwrap()
+    };
+    let local = chrono::NaiveDate::from_ymd_opt(2001, 1, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+    assert_eq!(
+        modified("dos.png"),
+        Local.from_local_datetime(&local).unwrap().naive_utc()
+    );
+    assert_eq!(
+        modified("extended.png"),
+        chrono::DateTime::from_timestamp(1_000_000_000, 0)
+            .unwrap()
+            .naive_utc()
+    );
+}
+
+#[test]
+fn archive_members_sharing_a_name_are_extracted_each_for_its_own_item() {
+    // given: a TAR holding two different files under the same name
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    let first = fs::read("tests/data/dateTimeOriginal.png").unwrap();
+    let mut second = first.clone();
+    second.extend_from_slice(b"appended");
+    let tar_path = source.join("backup.tar");
+    let mut tar = tar::Builder::new(fs::File::create(&tar_path).unwrap());
+    for content in [&first, &second] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "dup/a.png", content.as_slice())
+            .unwrap();
+    }
+    tar.finish().unwrap();
+    let options = ProcessOptions::default();
+
+    // when: only the second is selected
+    let mut the_plan = plan(&tar_path, &target, &options, |_, _| {}).unwrap();
+    assert_eq!(the_plan.items.len(), 2);
+    for item in &mut the_plan.items {
+        item.selected = item.size == second.len() as u64;
+    }
+    execute(the_plan, &options, |_, _| {}).unwrap();
+
+    // then
+    let dated = target.join("1991/1991-01-01");
+    assert_eq!(fs::read(dated.join("a.png")).unwrap(), second);
+}
+
+#[test]
+fn several_sources_are_planned_together_and_each_item_keeps_its_root() {
+    // given: the same picture in two sources, and a third source inside the
+    // first
+    let tmp = testdir::testdir!();
+    let phone = tmp.join("phone");
+    let camera = tmp.join("camera");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(phone.join("Burst")).unwrap();
+    fs::create_dir_all(&camera).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", phone.join("a.png")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", phone.join("Burst/b.png")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", camera.join("a.png")).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", camera.join("c.png")).unwrap();
+    let sources = [phone.clone(), camera.clone(), phone.join("Burst")];
+    let options = ProcessOptions {
+        collision: CollisionPolicy::Dedupe,
+        ..ProcessOptions::default()
+    };
+
+    // when
+    let the_plan = plan_sources(&sources, &target, &options, |_, _| {}).unwrap();
+
+    // then: nothing is planned twice, and items know where they came from
+    assert_eq!(the_plan.sources, sources);
+    assert_eq!(the_plan.items.len(), 4);
+    let root_of = |name: &str| {
+        let item = the_plan
+            .items
+            .iter()
+            .find(|item| item.image.source_full().ends_with(name))
+            .unwrap();
+        item.source_root.clone()
+    };
+    assert_eq!(root_of("phone/a.png"), phone);
+    assert_eq!(root_of("Burst/b.png"), phone);
+    assert_eq!(root_of("camera/a.png"), camera);
+    assert_eq!(root_of("camera/c.png"), camera);
+
+    // and: the two a.png meet in the target like files of one source
+    let summary = execute(the_plan, &options, |_, _| {}).unwrap();
+    assert_eq!(summary.transferred, 3);
+    assert_eq!(summary.duplicates, 1);
+}
+
+#[test]
+fn incremental_plan_skips_what_the_ledger_knows_even_when_renamed() {
+    // given: a first run over a weekly backup folder
+    let tmp = testdir::testdir!();
+    let source = tmp.join("backup");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("a.png")).unwrap();
+    let png_sig = [0x89u8, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+    fs::write(source.join("b.png"), png_sig).unwrap();
+    let options = ProcessOptions {
+        incremental: true,
+        ..ProcessOptions::default()
+    };
+    let first = plan(&source, &target, &options, |_, _| {}).unwrap();
+    assert_eq!(first.items.len(), 2);
+    assert!(first.imported.is_empty());
+    execute(first, &options, |_, _| {}).unwrap();
+
+    // when: a file is renamed and a new one arrives
+    fs::rename(source.join("a.png"), source.join("renamed.png")).unwrap();
+    fs::write(source.join("c.jpg"), b"not an image").unwrap();
+    let second = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // then: only the new file is planned
+    assert_eq!(second.items.len(), 1);
+    assert!(second.items[0].image.source_full().ends_with("c.jpg"));
+    assert_eq!(second.imported.len(), 2);
+    let renamed = second
+        .imported
+        .iter()
+        .find(|file| file.path.ends_with("renamed.png"))
+        .unwrap();
+    assert_eq!(
+        renamed.imported_as.as_deref(),
+        Some(source.join("a.png").as_path())
+    );
+    assert_eq!(renamed.target, target.join("1991/1991-01-01/a.png"));
+    let unchanged = second
+        .imported
+        .iter()
+        .find(|file| file.path.ends_with("b.png"))
+        .unwrap();
+    assert_eq!(unchanged.imported_as, None);
+
+    // and: without --incremental all is planned; a revert forgets the copies
+    // it removes, but keeps the one whose source is gone
+    let full = plan(&source, &target, &ProcessOptions::default(), |_, _| {}).unwrap();
+    assert_eq!(full.items.len(), 3);
+    revert(
+        &target.join("exif-sorter-manifest.csv"),
+        &RevertFilter::default(),
+        false,
+    )
+    .unwrap();
+    let after_revert = plan(&source, &target, &options, |_, _| {}).unwrap();
+    assert_eq!(after_revert.items.len(), 2);
+    assert_eq!(after_revert.imported.len(), 1);
+    assert!(after_revert.imported[0].path.ends_with("renamed.png"));
+}
+
+/// Poll `done` until it holds, for at most twenty seconds.
+fn wait_for(what: &str, done: impl Fn() -> bool) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(20);
+    while !done() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "timed out waiting for {what}"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn watch_sorts_files_once_they_are_complete_and_stops_when_cancelled() {
+    use std::io::Write as _;
+
+    // given: a file from before the watch began, and the watch running
+    let tmp = testdir::testdir!();
+    let source = tmp.join("inbox");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    fs::copy("tests/data/dateTimeOriginal.png", source.join("before.png")).unwrap();
+    let options = ProcessOptions::default();
+    let control = options.control.clone();
+    let watcher = {
+        let (source, target) = (source.clone(), target.clone());
+        std::thread::spawn(move || {
+            let mut transferred = 0;
+            let settle = std::time::Duration::from_millis(300);
+            watch(&[source], &target, &options, settle, |event| {
+                if let WatchEvent::Sorted(summary) = event {
+                    transferred += summary.transferred;
+                }
+            })
+            .unwrap();
+            transferred
+        })
+    };
+    let dated = target.join("1991/1991-01-01");
+    wait_for("the file from before", || dated.join("before.png").exists());
+
+    // when: one file arrives in one go, another is still being written
+    // (each with content of its own, or the ledger knows it already)
+    let photo = fs::read("tests/data/dateTimeOriginal.png").unwrap();
+    fs::write(source.join("new.png"), [photo.as_slice(), &[1]].concat()).unwrap();
+    let content = [photo.as_slice(), &[2]].concat();
+    let mut partial = fs::File::create(source.join("partial.png")).unwrap();
+    partial.write_all(&content[..100]).unwrap();
+    wait_for("the new file", || dated.join("new.png").exists());
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    assert!(
+        !dated.join("partial.png").exists(),
+        "a file still open for writing is left alone"
+    );
+    partial.write_all(&content[100..]).unwrap();
+    drop(partial);
+    wait_for("the finished file", || dated.join("partial.png").exists());
+
+    // then: every file was sorted exactly once, all into one manifest
+    control.cancel();
+    assert_eq!(watcher.join().unwrap(), 3);
+    assert_eq!(fs::read(dated.join("partial.png")).unwrap(), content);
+    let entries = read_manifest(&target.join("exif-sorter-manifest.csv")).unwrap();
+    assert_eq!(entries.len(), 3);
+}
+
+#[test]
+fn cards_are_imported_once_wherever_mounted_and_cleared_after_verifying() {
+    // given: a card among the mounts, next to a volume without DCIM
+    let tmp = testdir::testdir!();
+    let media = tmp.join("media");
+    let target = tmp.join("sorted");
+    let dcim = media.join("CARD-1/DCIM/100CANON");
+    fs::create_dir_all(&dcim).unwrap();
+    fs::create_dir_all(media.join("usb-stick/Documents")).unwrap();
+    let photo = fs::read("tests/data/dateTimeOriginal.png").unwrap();
+    for (name, tail) in [("IMG_0001.png", 1), ("IMG_0002.png", 2)] {
+        fs::write(dcim.join(name), [photo.as_slice(), &[tail]].concat()).unwrap();
+    }
+
+    // when: the card is found and imported
+    let cards = find_cards(Some(&media)).unwrap();
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].uuid, "CARD-1");
+    let import = |card: &Volume| {
+        let options = ProcessOptions {
+            incremental: true,
+            volume: Some(card.clone()),
+            ..ProcessOptions::default()
+        };
+        let the_plan = plan(&card.dcim(), &target, &options, |_, _| {}).unwrap();
+        let imported = the_plan.imported.clone();
+        let summary = execute(the_plan, &options, |_, _| {}).unwrap();
+        (summary.transferred, imported)
+    };
+    assert_eq!(import(&cards[0]).0, 2);
+
+    // and: the camera takes another picture, and the card comes back
+    // mounted elsewhere
+    fs::write(dcim.join("IMG_0003.png"), [photo.as_slice(), &[3]].concat()).unwrap();
+    let elsewhere = tmp.join("run-media");
+    fs::create_dir_all(&elsewhere).unwrap();
+    fs::rename(media.join("CARD-1"), elsewhere.join("CARD-1")).unwrap();
+    let card = Volume {
+        uuid: "CARD-1".to_string(),
+        root: elsewhere.join("CARD-1").canonicalize().unwrap(),
+    };
+
+    // then: only the new picture is imported; the others are known by
+    // their path on the card, not merely by content
+    let (transferred, imported) = import(&card);
+    assert_eq!(transferred, 1);
+    assert_eq!(imported.len(), 2);
+    assert!(imported.iter().all(|file| file.imported_as.is_none()));
+
+    // when: one library copy was damaged since
+    let dated = target.join("1991/1991-01-01");
+    fs::write(dated.join("IMG_0002.png"), b"damaged").unwrap();
+    fs::write(card.dcim().join("100CANON/IMG_0004.png"), b"not yet").unwrap();
+    let check = check_imports(&card, &target, &Default::default()).unwrap();
+
+    // then: only files with an identical copy are removed from the card
+    assert_eq!(check.verified.len(), 2);
+    assert_eq!(check.unverified.len(), 1);
+    assert_eq!(check.not_imported, 1);
+    assert!(remove_verified(&check).is_empty());
+    let left: Vec<_> = scan_dir(&card.dcim())
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(left.len(), 2);
+    assert!(left.contains(&"IMG_0002.png".to_string()));
+    assert!(left.contains(&"IMG_0004.png".to_string()));
+}
+
+#[test]
+fn streamed_plan_spills_to_disk_pages_and_executes_like_one_in_memory() {
+    // given: a tree with a nested folder and an archive
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(source.join("nested")).unwrap();
+    build_mixed_source(&source);
+    build_mixed_source(&source.join("nested"));
+    let mut zip = zip::ZipWriter::new(fs::File::create(source.join("old.zip")).unwrap());
+    zip.start_file("inside.png", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    std::io::Write::write_all(
+        &mut zip,
+        &fs::read("tests/data/dateTimeOriginal.png").unwrap(),
+    )
+    .unwrap();
+    zip.finish().unwrap();
+    let mut options = ProcessOptions::default();
+    options.scan.archives = true;
+    let in_memory = plan(&source, &target, &options, |_, _| {}).unwrap();
+
+    // when: the plan is streamed into a file
+    let plan_file = tmp.join("plan.jsonl");
+    let sources = [source];
+    let spill = PlanSpill::create(&plan_file, &sources, &target).unwrap();
+    let counted = std::sync::atomic::AtomicUsize::new(0);
+    let streamed = plan_streaming(
+        &sources,
+        &target,
+        &options,
+        |item| spill.push(item),
+        |done| {
+            counted.fetch_max(done, std::sync::atomic::Ordering::Relaxed);
+        },
+    )
+    .unwrap();
+    let spilled = spill.finish().unwrap();
+
+    // then: it holds the same items, read back a page at a time
+    assert!(streamed.items.is_empty(), "nothing is kept in memory");
+    assert_eq!(spilled.len(), 7);
+    assert_eq!(counted.into_inner(), 7);
+    let targets = |items: &[exif_sorter::sorter::PlannedItem]| {
+        let mut targets: Vec<_> = items
+            .iter()
+            .map(|item| item.planned_target.clone())
+            .collect();
+        targets.sort();
+        targets
+    };
+    let mut paged = Vec::new();
+    for start in (0..spilled.len()).step_by(3) {
+        paged.extend(spilled.page(start, 3).unwrap());
+    }
+    assert_eq!(targets(&paged), targets(&in_memory.items));
+    assert!(spilled.page(7, 3).unwrap().is_empty());
+    let reopened = SpilledPlan::open(&plan_file).unwrap();
+    assert_eq!(reopened.len(), 7);
+    assert_eq!(
+        reopened.page(6, 3).unwrap()[0].planned_target,
+        spilled.page(6, 1).unwrap()[0].planned_target
+    );
+
+    // and: executing it from the file stores every file
+    let summary = execute_spilled(streamed, &spilled, &options, |_, _| {}).unwrap();
+    assert_eq!(summary.total(), 7);
+    assert!(target.join("1991/1991-01-01/inside.png").exists());
+}
+
+#[test]
+fn spilled_archive_members_sharing_a_name_in_different_chunks_get_their_own_content() {
+    // given: a TAR with more members than a chunk holds, the first and the
+    // last of the same name and size
+    let tmp = testdir::testdir!();
+    let source = tmp.join("source");
+    let target = tmp.join("sorted");
+    fs::create_dir_all(&source).unwrap();
+    let mut tar = tar::Builder::new(fs::File::create(source.join("big.tar")).unwrap());
+    let mut add = |name: &str, content: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content).unwrap();
+    };
+    add("dup.png", b"first");
+    for i in 0..1100 {
+        add(&format!("filler-{i}.png"), b"filler");
+    }
+    add("dup.png", b"other");
+    tar.finish().unwrap();
+    let mut options = ProcessOptions::default();
+    options.scan.archives = true;
+    let plan_file = tmp.join("plan.jsonl");
+    let sources = [source];
+    let spill = PlanSpill::create(&plan_file, &sources, &target).unwrap();
+    let streamed =
+        plan_streaming(&sources, &target, &options, |item| spill.push(item), |_| {}).unwrap();
+    let spilled = spill.finish().unwrap();
+
+    // when
+    let summary = execute_spilled(streamed, &spilled, &options, |_, _| {}).unwrap();
+
+    // then
+    assert_eq!(summary.total(), 1102);
+    let mut dups: Vec<_> = read_manifest(&target.join("exif-sorter-manifest.csv"))
+        .unwrap()
+        .into_iter()
+        .filter(|entry| entry.source.ends_with("!/dup.png"))
+        .map(|entry| fs::read(&entry.target).unwrap())
+        .collect();
+    dups.sort();
+    assert_eq!(dups, [b"first".to_vec(), b"other".to_vec()]);
+}